@@ -0,0 +1,142 @@
+//! This module contains the colors of the documents, in either of the color spaces of pdf.
+
+use printpdf::{Cmyk, Rgb};
+use serde::{Deserialize, Serialize};
+
+/// The color space in which the colors of a document are written.
+///
+/// Screens show colors in rgb, while professional printers expect them in cmyk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    /// Red, green and blue.
+    Rgb,
+
+    /// Cyan, magenta, yellow and black.
+    Cmyk,
+}
+
+impl Default for ColorSpace {
+    fn default() -> ColorSpace {
+        ColorSpace::Rgb
+    }
+}
+
+/// A color, specified in either color space, with components between 0 and 1.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Color {
+    /// A color in rgb.
+    Rgb {
+        /// The red component.
+        r: f64,
+
+        /// The green component.
+        g: f64,
+
+        /// The blue component.
+        b: f64,
+    },
+
+    /// A color in cmyk.
+    Cmyk {
+        /// The cyan component.
+        c: f64,
+
+        /// The magenta component.
+        m: f64,
+
+        /// The yellow component.
+        y: f64,
+
+        /// The black component.
+        k: f64,
+    },
+}
+
+impl Color {
+    /// Creates a grey, from black at 0 to white at 1.
+    pub fn grey(level: f64) -> Color {
+        Color::Rgb {
+            r: level,
+            g: level,
+            b: level,
+        }
+    }
+
+    /// Converts the color to a color space.
+    ///
+    /// The conversion is the naive one, without any color profile, which is enough for text and
+    /// plain shapes.
+    pub fn to_space(self, space: ColorSpace) -> Color {
+        match (self, space) {
+            (Color::Rgb { r, g, b }, ColorSpace::Cmyk) => {
+                let k = 1.0 - r.max(g).max(b);
+                let chroma = |x: f64| {
+                    if k < 1.0 {
+                        (1.0 - x - k) / (1.0 - k)
+                    } else {
+                        0.0
+                    }
+                };
+                Color::Cmyk {
+                    c: chroma(r),
+                    m: chroma(g),
+                    y: chroma(b),
+                    k,
+                }
+            }
+            (Color::Cmyk { c, m, y, k }, ColorSpace::Rgb) => Color::Rgb {
+                r: (1.0 - c) * (1.0 - k),
+                g: (1.0 - m) * (1.0 - k),
+                b: (1.0 - y) * (1.0 - k),
+            },
+            (color, _) => color,
+        }
+    }
+
+    /// Converts the color to a color space, as a printpdf color.
+    pub fn to_pdf(self, space: ColorSpace) -> printpdf::Color {
+        match self.to_space(space) {
+            Color::Rgb { r, g, b } => printpdf::Color::Rgb(Rgb::new(r, g, b, None)),
+            Color::Cmyk { c, m, y, k } => printpdf::Color::Cmyk(Cmyk::new(c, m, y, k, None)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::document::color::{Color, ColorSpace};
+
+    #[test]
+    fn test_conversions() {
+        let red = Color::Rgb {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+        };
+        let cmyk = red.to_space(ColorSpace::Cmyk);
+        assert_eq!(
+            cmyk,
+            Color::Cmyk {
+                c: 0.0,
+                m: 1.0,
+                y: 1.0,
+                k: 0.0
+            }
+        );
+        assert_eq!(cmyk.to_space(ColorSpace::Rgb), red);
+
+        // Black has no chroma.
+        let black = Color::grey(0.0).to_space(ColorSpace::Cmyk);
+        assert_eq!(
+            black,
+            Color::Cmyk {
+                c: 0.0,
+                m: 0.0,
+                y: 0.0,
+                k: 1.0
+            }
+        );
+    }
+}
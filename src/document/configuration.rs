@@ -2,16 +2,39 @@
 //! typeset. The configuration is parsed from a TOML file located at the
 //! root of the SpanDeX project. Mandatory measurements take default values
 //! that are also provided by this module.
+//!
+//! Measurements are in PostScript points, 72 to the inch, which is the unit printpdf uses for
+//! its coordinates, and not in TeX points, 72.27 to the inch.
 
+use std::fs::File;
+use std::path::PathBuf;
 use std::{fmt, result};
 
-use printpdf::{Mm, Pt};
+use printpdf::{Mm, OffsetDateTime, Pt};
 use serde::de::{self, Visitor};
+use serde::ser::SerializeMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use spandex_hyphenation::load::{self, Load};
+use spandex_hyphenation::Standard;
+use toml::value::Table;
 
-use crate::document::{Document, Window};
+use crate::document::color::{Color, ColorSpace};
+use crate::document::{Document, PageNumbering, Skips, Tolerances, Window, DEFAULT_DATE_FORMAT};
+use crate::fonts::configuration::TitleSizes;
 use crate::fonts::manager::FontManager;
-use crate::Result as CResult;
+use crate::layout::constants::{
+    CLAUSE_SPACE_FACTOR, DEFAULT_ABBREVIATIONS, DEFAULT_FONT_SIZE, DEFAULT_LIST_ITEM_SKIP,
+    DEFAULT_LIST_SKIP, DEFAULT_MAX_WORD_SPACE, DEFAULT_MIN_WORD_SPACE, DEFAULT_MISSING_GLYPH,
+    DEFAULT_OVERFULL_TOLERANCE, DEFAULT_PAGE_BREAK_PENALTY, DEFAULT_PARAGRAPH_SKIP,
+    DEFAULT_UNDERFULL_BADNESS, LIST_MARKER, TAB_WIDTH, TITLE_SIZE_BOOST, TITLE_SIZE_RATIO,
+};
+use crate::layout::paragraphs::abbreviations::Abbreviations;
+use crate::layout::paragraphs::engine::WordSpacing;
+use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+use crate::layout::paragraphs::kerning::KernOverrides;
+use crate::layout::paragraphs::ligatures::Ligatures;
+use crate::units::{Length, ReportUnit};
+use crate::{Error, Result as CResult};
 
 /// Serializes a `Pt` structure.
 // This is required to use in macro `serialize_with`.
@@ -69,6 +92,455 @@ impl<'a> Visitor<'a> for PtVisitor {
     visit_from!(visit_f64, f64);
 }
 
+/// The side of the page on which the line numbers are drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarginSide {
+    /// In the left margin.
+    Left,
+
+    /// In the right margin.
+    Right,
+}
+
+/// Which section is shown in the running header of a page that holds more than one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunningHeader {
+    /// The section at the top of the page, which may have started on a previous page.
+    First,
+
+    /// The last section started on the page.
+    Last,
+}
+
+/// What happens to the content that doesn't fit in the maximum number of pages.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageOverflow {
+    /// The content is clipped, with a warning.
+    Warn,
+
+    /// The build fails.
+    Error,
+}
+
+impl Default for PageOverflow {
+    fn default() -> PageOverflow {
+        PageOverflow::Warn
+    }
+}
+
+/// The bundled family of the fonts of a document.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontFamily {
+    /// CMU Serif, the computer modern of TeX.
+    Serif,
+
+    /// CMU Sans Serif.
+    Sans,
+
+    /// CMU Typewriter Text, a monospace family.
+    Typewriter,
+}
+
+impl Default for FontFamily {
+    fn default() -> FontFamily {
+        FontFamily::Serif
+    }
+}
+
+/// How the lines of a document are numbered in the margin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineNumbering {
+    /// Only the lines whose number is a multiple of this one get their number drawn.
+    pub every: usize,
+
+    /// The margin in which the numbers are drawn.
+    pub side: MarginSide,
+
+    /// Whether the numbering starts over on each page instead of running through the document.
+    pub per_page: bool,
+}
+
+impl Default for LineNumbering {
+    fn default() -> LineNumbering {
+        LineNumbering {
+            every: 1,
+            side: MarginSide::Left,
+            per_page: false,
+        }
+    }
+}
+
+/// The height of the lines of a document, from one baseline to the next.
+#[derive(Copy, Clone, Debug)]
+pub enum LineHeight {
+    /// A height in pt, written as a string like `"15pt"`.
+    Absolute(Pt),
+
+    /// A ratio of the font size, written as a number like `1.5`.
+    Ratio(f64),
+}
+
+impl LineHeight {
+    /// Returns the height of the lines for a font size.
+    pub fn resolve(self, size: Pt) -> Pt {
+        match self {
+            LineHeight::Absolute(height) => height,
+            LineHeight::Ratio(ratio) => size * ratio,
+        }
+    }
+}
+
+impl Serialize for LineHeight {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        match self {
+            LineHeight::Absolute(height) => serializer.serialize_str(&format!("{}pt", height.0)),
+            LineHeight::Ratio(ratio) => serializer.serialize_f64(*ratio),
+        }
+    }
+}
+
+impl<'a> Deserialize<'a> for LineHeight {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<LineHeight, D::Error> {
+        deserializer.deserialize_any(LineHeightVisitor)
+    }
+}
+
+/// Visitor for the `LineHeight` enum.
+pub struct LineHeightVisitor;
+
+impl<'a> Visitor<'a> for LineHeightVisitor {
+    type Value = LineHeight;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a ratio of the font size or a height in pt, like \"15pt\"")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<LineHeight, E> {
+        Ok(LineHeight::Ratio(value as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<LineHeight, E> {
+        Ok(LineHeight::Ratio(value as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<LineHeight, E> {
+        Ok(LineHeight::Ratio(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<LineHeight, E> {
+        value
+            .strip_suffix("pt")
+            .and_then(|height| height.trim().parse().ok())
+            .map(|height| LineHeight::Absolute(Pt(height)))
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+/// The input files of a document.
+///
+/// A single path is accepted for backward compatibility, but documents split
+/// into chapters can list their files, which are concatenated in order.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Input {
+    /// A single input file.
+    File(String),
+
+    /// Many input files, typeset one after the other.
+    Files(Vec<String>),
+}
+
+impl Input {
+    /// Returns the paths of the input files, in order.
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            Input::File(path) => vec![path.as_str()],
+            Input::Files(paths) => paths.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// The text area of some pages, given like the one of the whole document.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct PageWindow {
+    /// The top margin of the pages.
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub top_margin: Pt,
+
+    /// The left margin of the pages.
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub left_margin: Pt,
+
+    /// The text width of the pages.
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub text_width: Pt,
+
+    /// The text height of the pages.
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub text_height: Pt,
+}
+
+impl PageWindow {
+    /// Checks that the text area is inside pages of a size.
+    fn check(&self, page_width: Pt, page_height: Pt) -> CResult<()> {
+        if self.top_margin.0 < 0.0 || self.left_margin.0 < 0.0 {
+            return Err(Error::InvalidGeometry(String::from(
+                "the margins are negative",
+            )));
+        }
+
+        if self.text_width.0 <= 0.0 || self.text_height.0 <= 0.0 {
+            return Err(Error::InvalidGeometry(String::from(
+                "the margins leave no room for the text",
+            )));
+        }
+
+        // Rounding the lengths in mm may put the text a hair outside the page.
+        let epsilon = 1e-6;
+        if self.left_margin.0 + self.text_width.0 > page_width.0 + epsilon
+            || self.top_margin.0 + self.text_height.0 > page_height.0 + epsilon
+        {
+            return Err(Error::InvalidGeometry(String::from(
+                "the text goes past the edge of the pages",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the window of the pages.
+    pub fn window(&self) -> Window {
+        Window {
+            x: self.left_margin,
+            y: self.top_margin,
+            width: self.text_width,
+            height: self.text_height,
+        }
+    }
+}
+
+/// The settings of how the text of a document is typeset.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TypesettingConfig {
+    /// The minimal width of a space between two words of a justified line.
+    #[serde(serialize_with = "serialize_pt")]
+    pub min_word_space: Pt,
+
+    /// The maximal width of a space between two words of a justified line.
+    #[serde(serialize_with = "serialize_pt")]
+    pub max_word_space: Pt,
+
+    /// The length by which a line can exceed the text before it raises a warning, like TeX's
+    /// `\hfuzz`.
+    #[serde(serialize_with = "serialize_pt")]
+    pub overfull_tolerance: Pt,
+
+    /// The badness above which a loose line raises a warning, like TeX's `\hbadness`.
+    pub underfull_badness: f64,
+
+    /// The distance between two tab stops, to which the tabulations of the text advance.
+    #[serde(serialize_with = "serialize_pt")]
+    pub tab_width: Pt,
+
+    /// Words whose hyphenation overrides the dictionary, with their breaks marked by dashes,
+    /// e.g. `"data-base"`.
+    pub hyphenation_exceptions: Vec<String>,
+
+    /// Whether the words starting with an uppercase letter, often proper nouns, are hyphenated.
+    pub hyphenate_capitalized: bool,
+
+    /// The file of the dictionary that hyphenates the words, for the languages or patterns that
+    /// aren't embedded. The embedded english dictionary is used if unset.
+    pub hyphenation_dictionary: Option<PathBuf>,
+
+    /// The abbreviations, whose dots don't end a sentence, and which are never broken across
+    /// lines, e.g. `"e.g."` or `"e. g."`.
+    pub abbreviations: Vec<String>,
+
+    /// The space factor of the whitespaces after colons and semicolons, from 1 for an interword
+    /// space to 3 for a sentence space, like TeX's `\sfcode`.
+    pub clause_space_factor: f64,
+
+    /// Whether the whitespaces after the end of a sentence or of a clause are interword spaces,
+    /// like TeX's `\frenchspacing`, rather than wider ones.
+    pub french_spacing: bool,
+
+    /// Whether the single line breaks within the paragraphs of the source are breakpoints that
+    /// the lines prefer, rather than plain whitespaces.
+    pub respect_source_breaks: bool,
+
+    /// Whether the words wider than a line are broken between their characters instead of
+    /// overflowing the margin.
+    pub emergency_breaks: bool,
+
+    /// The stretch of the glue that ends the paragraphs, relative to the text width, like TeX's
+    /// `\parfillskip`: 0 justifies the last lines, and 0.75 keeps them from being much shorter
+    /// than a quarter of the width. Unset, the last lines are ragged.
+    pub parfill_stretch: Option<f64>,
+
+    /// The ligatures applied to the text: `"all"`, `"none"`, or all but some, like
+    /// `{ except = ["fi"] }`.
+    // This one may be a table, so it must come last.
+    pub ligatures: Ligatures,
+
+    /// The adjustments in pt of the gap between some pairs of adjacent chars, added to the
+    /// kerning of the fonts, like `{ AV = -1.5 }`.
+    pub kern_overrides: KernOverrides,
+}
+
+impl Default for TypesettingConfig {
+    fn default() -> TypesettingConfig {
+        TypesettingConfig {
+            min_word_space: DEFAULT_MIN_WORD_SPACE,
+            max_word_space: DEFAULT_MAX_WORD_SPACE,
+            overfull_tolerance: DEFAULT_OVERFULL_TOLERANCE,
+            underfull_badness: DEFAULT_UNDERFULL_BADNESS,
+            tab_width: TAB_WIDTH,
+            hyphenation_exceptions: vec![],
+            hyphenate_capitalized: true,
+            hyphenation_dictionary: None,
+            abbreviations: DEFAULT_ABBREVIATIONS
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            clause_space_factor: CLAUSE_SPACE_FACTOR,
+            french_spacing: false,
+            respect_source_breaks: false,
+            emergency_breaks: false,
+            parfill_stretch: None,
+            ligatures: Ligatures::All,
+            kern_overrides: KernOverrides::default(),
+        }
+    }
+}
+
+/// The typesetting settings written in a config file, any of which may be missing.
+#[derive(Default, Deserialize)]
+struct TypesettingKeys {
+    min_word_space: Option<f64>,
+    max_word_space: Option<f64>,
+    overfull_tolerance: Option<f64>,
+    underfull_badness: Option<f64>,
+    tab_width: Option<f64>,
+    hyphenation_exceptions: Option<Vec<String>>,
+    hyphenate_capitalized: Option<bool>,
+    hyphenation_dictionary: Option<PathBuf>,
+    abbreviations: Option<Vec<String>>,
+    clause_space_factor: Option<f64>,
+    french_spacing: Option<bool>,
+    respect_source_breaks: Option<bool>,
+    emergency_breaks: Option<bool>,
+    parfill_stretch: Option<f64>,
+    ligatures: Option<Ligatures>,
+    kern_overrides: Option<KernOverrides>,
+}
+
+impl TypesettingKeys {
+    /// Completes the settings with other ones, and then with the default ones.
+    fn or(self, other: TypesettingKeys) -> TypesettingConfig {
+        let default = TypesettingConfig::default();
+
+        TypesettingConfig {
+            min_word_space: self
+                .min_word_space
+                .or(other.min_word_space)
+                .map_or(default.min_word_space, Pt),
+            max_word_space: self
+                .max_word_space
+                .or(other.max_word_space)
+                .map_or(default.max_word_space, Pt),
+            overfull_tolerance: self
+                .overfull_tolerance
+                .or(other.overfull_tolerance)
+                .map_or(default.overfull_tolerance, Pt),
+            underfull_badness: self
+                .underfull_badness
+                .or(other.underfull_badness)
+                .unwrap_or(default.underfull_badness),
+            tab_width: self
+                .tab_width
+                .or(other.tab_width)
+                .map_or(default.tab_width, Pt),
+            hyphenation_exceptions: self
+                .hyphenation_exceptions
+                .or(other.hyphenation_exceptions)
+                .unwrap_or(default.hyphenation_exceptions),
+            hyphenate_capitalized: self
+                .hyphenate_capitalized
+                .or(other.hyphenate_capitalized)
+                .unwrap_or(default.hyphenate_capitalized),
+            hyphenation_dictionary: self.hyphenation_dictionary.or(other.hyphenation_dictionary),
+            abbreviations: self
+                .abbreviations
+                .or(other.abbreviations)
+                .unwrap_or(default.abbreviations),
+            clause_space_factor: self
+                .clause_space_factor
+                .or(other.clause_space_factor)
+                .unwrap_or(default.clause_space_factor),
+            french_spacing: self
+                .french_spacing
+                .or(other.french_spacing)
+                .unwrap_or(default.french_spacing),
+            respect_source_breaks: self
+                .respect_source_breaks
+                .or(other.respect_source_breaks)
+                .unwrap_or(default.respect_source_breaks),
+            emergency_breaks: self
+                .emergency_breaks
+                .or(other.emergency_breaks)
+                .unwrap_or(default.emergency_breaks),
+            parfill_stretch: self.parfill_stretch.or(other.parfill_stretch),
+            ligatures: self
+                .ligatures
+                .or(other.ligatures)
+                .unwrap_or(default.ligatures),
+            kern_overrides: self
+                .kern_overrides
+                .or(other.kern_overrides)
+                .unwrap_or(default.kern_overrides),
+        }
+    }
+}
+
+/// Serializes the typesetting settings as a `[typesetting]` table.
+fn serialize_typesetting<S: Serializer>(
+    typesetting: &TypesettingConfig,
+    serializer: S,
+) -> result::Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry("typesetting", typesetting)?;
+    map.end()
+}
+
+/// Deserializes the typesetting settings, from the `[typesetting]` table first and then from
+/// the top level.
+fn deserialize_typesetting<'a, D: Deserializer<'a>>(
+    deserializer: D,
+) -> Result<TypesettingConfig, D::Error> {
+    /// The places where the typesetting settings can be written.
+    #[derive(Deserialize)]
+    struct Keys {
+        #[serde(default)]
+        typesetting: TypesettingKeys,
+
+        #[serde(flatten)]
+        top_level: TypesettingKeys,
+    }
+
+    let keys = Keys::deserialize(deserializer)?;
+    Ok(keys.typesetting.or(keys.top_level))
+}
+
 /// Holds the configuration of a document, including various measurements
 /// common to all pages.
 #[derive(Clone, Serialize, Deserialize)]
@@ -106,44 +578,869 @@ pub struct Config {
     #[serde(deserialize_with = "deserialize_pt")]
     pub text_height: Pt,
 
-    /// The path to the first file of the spandex content.
-    pub input: String,
+    /// The path to the spandex content, or the list of paths to its chapters.
+    pub input: Input,
+
+    /// The vertical space after a paragraph.
+    #[serde(default = "default_paragraph_skip")]
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub paragraph_skip: Pt,
+
+    /// The vertical space after an item of a list.
+    #[serde(default = "default_list_item_skip")]
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub list_item_skip: Pt,
+
+    /// The vertical space after a list, added to the space after its last item.
+    #[serde(default = "default_list_skip")]
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub list_skip: Pt,
+
+    /// Whether the generated pdf must be the same byte for byte for the same input.
+    #[serde(default)]
+    pub reproducible: bool,
+
+    /// Whether the document is a draft, with a watermark stamped across each page.
+    #[serde(default)]
+    pub draft: bool,
+
+    /// Whether the lines of the document are numbered in the margin.
+    #[serde(default)]
+    pub line_numbers: bool,
+
+    /// Whether titles are prefixed with their section number.
+    #[serde(default = "default_number_headings")]
+    pub number_headings: bool,
+
+    /// The penalty for breaking a page inside a paragraph, see
+    /// `Document::set_page_break_penalty`.
+    #[serde(default = "default_page_break_penalty")]
+    pub page_break_penalty: f64,
+
+    /// The distance from the top of the text to the baseline of the first line of each page,
+    /// e.g. the ascent of the font, about 7.5 for a 10pt text. The first baseline is at the top
+    /// of the text by default. It can also be written `top_skip`, as in TeX.
+    #[serde(default = "default_first_baseline", alias = "top_skip")]
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub first_baseline: Pt,
+
+    /// The space kept empty at the bottom of the text of each page, below the last baseline.
+    #[serde(default = "default_bottom_skip")]
+    #[serde(serialize_with = "serialize_pt")]
+    #[serde(deserialize_with = "deserialize_pt")]
+    pub bottom_skip: Pt,
+
+    /// The format of the date written by `\today`, with the specifiers of `strftime`.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+
+    /// The char drawn instead of the chars that the fonts can't draw.
+    #[serde(default = "default_missing_glyph")]
+    pub missing_glyph: char,
+
+    /// The marker drawn before the items of unordered lists, replaced by a hyphen if the font
+    /// can't draw it.
+    #[serde(default = "default_list_marker")]
+    pub list_marker: char,
+
+    /// How much larger than the body text a title of level 0 is, relative to the body size,
+    /// e.g. 1 for titles twice as large as the text.
+    #[serde(default = "default_title_size_boost")]
+    pub title_size_boost: f64,
+
+    /// The ratio between the size boosts of two consecutive title levels, between 0 for titles
+    /// of level 1 and deeper as large as the text, and 1 for titles of all levels as large.
+    #[serde(default = "default_title_size_ratio")]
+    pub title_size_ratio: f64,
+
+    /// The height of the lines, the font size if unset.
+    #[serde(default)]
+    pub line_height: Option<LineHeight>,
+
+    /// Whether the straight apostrophes between two letters, as in "don't", are replaced by
+    /// typographic ones.
+    #[serde(default)]
+    pub curly_apostrophes: bool,
+
+    /// Whether the diagnostics that aren't errors are silenced.
+    ///
+    /// This is set from the command line rather than from the config file.
+    #[serde(skip)]
+    pub quiet: bool,
+
+    /// Whether the build fails if any warning is raised, e.g. to keep a document clean in CI.
+    #[serde(default)]
+    pub deny_warnings: bool,
+
+    /// Whether statistics on the lines, like their maximum badness, are reported after the
+    /// build, e.g. to tune the typesetting.
+    #[serde(default)]
+    pub line_stats: bool,
+
+    /// Which section is shown at the top of each page, if any.
+    #[serde(default)]
+    pub running_header: Option<RunningHeader>,
+
+    /// Whether the `[[note: ...]]` comments are attached to the pdf as annotations.
+    #[serde(default)]
+    pub show_annotations: bool,
+
+    /// Whether the pages are numbered at the bottom.
+    #[serde(default)]
+    pub page_numbers: bool,
+
+    /// The number of the first page of the main matter, or of the first page if there is no
+    /// `\mainmatter` marker, e.g. for a document that is part of a larger work.
+    #[serde(default = "default_first_page_number")]
+    pub first_page_number: i32,
+
+    /// Whether the pages before the `\mainmatter` marker are numbered with roman numerals.
+    #[serde(default)]
+    pub roman_front_matter: bool,
+
+    /// Whether the page numbers and the running headers sit in the outer margin, on the left of
+    /// the even pages and on the right of the odd ones.
+    #[serde(default)]
+    pub two_sided: bool,
+
+    /// The unit of the lengths in the warnings: `"pt"`, `"mm"` or `"cm"`.
+    #[serde(default)]
+    pub report_units: ReportUnit,
+
+    /// The color space in which the colors are written, `"rgb"` or `"cmyk"` for print.
+    #[serde(default)]
+    pub color_space: ColorSpace,
+
+    /// The directory where the metrics of the fonts are cached between builds, e.g.
+    /// `".spandex-cache"`, to avoid measuring the glyphs again.
+    #[serde(default)]
+    pub font_cache: Option<String>,
+
+    /// The maximum number of pages of the document, e.g. the page limit of a submission.
+    #[serde(default)]
+    pub max_pages: Option<usize>,
+
+    /// The bundled family of the fonts of the document, `"serif"`, `"sans"` or `"typewriter"`.
+    #[serde(default)]
+    pub font_family: FontFamily,
+
+    /// What happens to the content after `max_pages`, `"warn"` to clip it or `"error"` to fail.
+    #[serde(default)]
+    pub page_overflow: PageOverflow,
+
+    /// The number of lines under which a page that isn't empty raises a warning, e.g. 3 to
+    /// catch a stray line at the end of a chapter.
+    #[serde(default)]
+    pub min_lines_per_page: Option<usize>,
+
+    /// Whether the fonts that miss an italic or a bold face get one synthesized, by slanting or
+    /// stroking their regular face, instead of failing to load as a family.
+    #[serde(default)]
+    pub synthetic_styles: bool,
+
+    // Tables must come after values for the config to be serialized to toml.
+    /// How the lines are numbered, when they are.
+    #[serde(default)]
+    pub line_numbering: LineNumbering,
+
+    /// The color of the text, e.g. `{ c = 0.0, m = 0.0, y = 0.0, k = 0.9 }`, black if unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<Color>,
+
+    /// The text areas of the first pages, in `[[page_windows]]` tables, the last one being used
+    /// for the pages after them, e.g. for a wide first page. The margins and text size of the
+    /// document are used for all the pages if there are none.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub page_windows: Vec<PageWindow>,
+
+    /// How the text is typeset, read from a `[typesetting]` table or, like in older configs,
+    /// from the top level.
+    #[serde(flatten)]
+    #[serde(serialize_with = "serialize_typesetting")]
+    #[serde(deserialize_with = "deserialize_typesetting")]
+    pub typesetting: TypesettingConfig,
+}
+
+/// Returns the default space after a paragraph.
+fn default_paragraph_skip() -> Pt {
+    DEFAULT_PARAGRAPH_SKIP
+}
+
+/// Returns the default distance from the top of the text to the first baseline of a page.
+fn default_first_baseline() -> Pt {
+    Pt(0.0)
+}
+
+/// Returns the default space kept empty at the bottom of the text of a page.
+fn default_bottom_skip() -> Pt {
+    Pt(0.0)
+}
+
+/// Returns the default space after an item of a list.
+fn default_list_item_skip() -> Pt {
+    DEFAULT_LIST_ITEM_SKIP
+}
+
+/// Returns the default space after a list.
+fn default_list_skip() -> Pt {
+    DEFAULT_LIST_SKIP
+}
+
+/// Returns whether titles are numbered by default.
+fn default_number_headings() -> bool {
+    true
+}
+
+/// Returns the default penalty for breaking a page inside a paragraph.
+fn default_page_break_penalty() -> f64 {
+    DEFAULT_PAGE_BREAK_PENALTY
+}
+
+/// Returns the default number of the first page.
+fn default_first_page_number() -> i32 {
+    1
+}
+
+/// Returns the default boost of the size of the titles of level 0.
+fn default_title_size_boost() -> f64 {
+    TITLE_SIZE_BOOST
+}
+
+/// Returns the default ratio between the size boosts of two consecutive title levels.
+fn default_title_size_ratio() -> f64 {
+    TITLE_SIZE_RATIO
+}
+
+/// Returns the default format of the date.
+fn default_date_format() -> String {
+    String::from(DEFAULT_DATE_FORMAT)
+}
+
+/// Checks that a date format only holds valid `strftime` specifiers.
+fn check_date_format(format: &str) -> CResult<()> {
+    time::validate_format_string(format)
+        .map_err(|e| Error::InvalidConfig(format!("invalid date format \"{}\": {}", format, e)))
+}
+
+/// Returns the default char drawn instead of the chars that the fonts can't draw.
+fn default_missing_glyph() -> char {
+    DEFAULT_MISSING_GLYPH
+}
+
+/// Returns the default marker of the items of unordered lists.
+fn default_list_marker() -> char {
+    LIST_MARKER
+}
+
+/// Returns the keys of a config, with every optional setting set so that none is missing.
+fn known_keys() -> Table {
+    let mut config = Config::with_title("");
+    config.line_height = Some(LineHeight::Ratio(1.0));
+    config.running_header = Some(RunningHeader::First);
+    config.font_cache = Some(String::new());
+    config.max_pages = Some(1);
+    config.min_lines_per_page = Some(1);
+    config.text_color = Some(Color::grey(0.0));
+    config.typesetting.parfill_stretch = Some(0.0);
+    config.typesetting.hyphenation_dictionary = Some(PathBuf::new());
+    config.page_windows = vec![PageWindow {
+        top_margin: config.top_margin,
+        left_margin: config.left_margin,
+        text_width: config.text_width,
+        text_height: config.text_height,
+    }];
+
+    match toml::Value::try_from(&config) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => unreachable!("a config is always a table"),
+    }
+}
+
+/// Returns an error for the first key of a table that isn't among the known keys.
+fn check_table(table: &Table, known: &Table, prefix: &str) -> CResult<()> {
+    match table.keys().find(|key| !known.contains_key(*key)) {
+        Some(key) => Err(Error::UnknownConfigKey(format!("{}{}", prefix, key))),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a config has no unknown key, at the top level or in its tables.
+///
+/// `deny_unknown_fields` can't be used, since the typesetting settings are flattened in the
+/// config.
+fn check_keys(table: &Table) -> CResult<()> {
+    let mut known = known_keys();
+    let typesetting = match known.get("typesetting") {
+        Some(toml::Value::Table(typesetting)) => typesetting.clone(),
+        _ => Table::new(),
+    };
+
+    for (name, prefix) in &[
+        ("typesetting", "typesetting."),
+        ("line_numbering", "line_numbering."),
+    ] {
+        if let (Some(toml::Value::Table(table)), Some(toml::Value::Table(known))) =
+            (table.get(*name), known.get(*name))
+        {
+            check_table(table, known, prefix)?;
+        }
+    }
+
+    // The typesetting settings of older configs are at the top level.
+    known.extend(typesetting);
+    known.insert(String::from("top_skip"), toml::Value::Float(0.0));
+    check_table(table, &known, "")
 }
 
 impl Config {
     /// Creates a default configuration with a title.
     pub fn with_title(title: &str) -> Config {
-        let page_width: Pt = Mm(210.0).into();
-        let page_height: Pt = Mm(297.0).into();
-        let top_margin: Pt = Mm(30.0).into();
-        let left_margin: Pt = Mm(30.0).into();
-        let text_width: Pt = Mm(150.0).into();
-        let text_height: Pt = Mm(237.0).into();
+        // An A4 page, with the same margins on every side.
+        let page_width = Length::from(Mm(210.0));
+        let page_height = Length::from(Mm(297.0));
+        let margin = Length::from(Mm(30.0));
 
         Config {
             title: String::from(title),
-            page_width,
-            page_height,
-            top_margin,
-            left_margin,
-            text_width,
-            text_height,
-            input: String::from("main.dex"),
+            page_width: page_width.pt(),
+            page_height: page_height.pt(),
+            top_margin: margin.pt(),
+            left_margin: margin.pt(),
+            text_width: (page_width - margin * 2.0).pt(),
+            text_height: (page_height - margin * 2.0).pt(),
+            input: Input::File(String::from("main.dex")),
+            paragraph_skip: DEFAULT_PARAGRAPH_SKIP,
+            list_item_skip: DEFAULT_LIST_ITEM_SKIP,
+            list_skip: DEFAULT_LIST_SKIP,
+            reproducible: false,
+            draft: false,
+            line_numbers: false,
+            number_headings: true,
+            page_break_penalty: DEFAULT_PAGE_BREAK_PENALTY,
+            first_baseline: default_first_baseline(),
+            bottom_skip: default_bottom_skip(),
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+            missing_glyph: DEFAULT_MISSING_GLYPH,
+            list_marker: LIST_MARKER,
+            title_size_boost: TITLE_SIZE_BOOST,
+            title_size_ratio: TITLE_SIZE_RATIO,
+            line_height: None,
+            curly_apostrophes: false,
+            quiet: false,
+            deny_warnings: false,
+            line_stats: false,
+            running_header: None,
+            show_annotations: false,
+            page_numbers: false,
+            first_page_number: 1,
+            roman_front_matter: false,
+            two_sided: false,
+            report_units: ReportUnit::Pt,
+            color_space: ColorSpace::Rgb,
+            font_cache: None,
+            max_pages: None,
+            min_lines_per_page: None,
+            synthetic_styles: false,
+            font_family: FontFamily::Serif,
+            page_overflow: PageOverflow::Warn,
+            line_numbering: LineNumbering::default(),
+            text_color: None,
+            page_windows: vec![],
+            typesetting: TypesettingConfig::default(),
         }
     }
 
+    /// Reads a config from the content of a spandex.toml, and checks it.
+    ///
+    /// Unlike the deserialization of a config, this fails on the keys that aren't settings.
+    pub fn from_toml(content: &str) -> CResult<Config> {
+        let value: toml::Value = toml::from_str(content)?;
+        if let Some(table) = value.as_table() {
+            check_keys(table)?;
+        }
+
+        let config: Config = value.try_into()?;
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Checks that the settings of the config are possible, e.g. that its text fits in its
+    /// pages.
+    pub fn check(&self) -> CResult<()> {
+        if self.page_width.0 <= 0.0 || self.page_height.0 <= 0.0 {
+            return Err(Error::InvalidGeometry(String::from(
+                "the pages have no area",
+            )));
+        }
+
+        let window = PageWindow {
+            top_margin: self.top_margin,
+            left_margin: self.left_margin,
+            text_width: self.text_width,
+            text_height: self.text_height,
+        };
+        for window in std::iter::once(&window).chain(&self.page_windows) {
+            window.check(self.page_width, self.page_height)?;
+        }
+
+        if self.typesetting.tab_width.0 <= 0.0 {
+            return Err(Error::InvalidConfig(String::from(
+                "the tab stops must be a positive distance apart",
+            )));
+        }
+
+        if self.typesetting.min_word_space.0 > self.typesetting.max_word_space.0 {
+            return Err(Error::InvalidConfig(String::from(
+                "the minimal word space is above the maximal one",
+            )));
+        }
+
+        if self.first_baseline.0 + self.bottom_skip.0 >= self.text_height.0 {
+            return Err(Error::InvalidConfig(String::from(
+                "the top and bottom skips leave no room for the text",
+            )));
+        }
+
+        if self.title_size_boost < 0.0 || !(0.0..=1.0).contains(&self.title_size_ratio) {
+            return Err(Error::InvalidConfig(String::from(
+                "the titles must be larger than the text, and smaller as they get deeper",
+            )));
+        }
+
+        if self.line_numbering.every == 0 {
+            return Err(Error::InvalidConfig(String::from(
+                "the lines can't be numbered every 0 lines",
+            )));
+        }
+
+        check_date_format(&self.date_format)?;
+
+        Ok(())
+    }
+
+    /// Formats a date with the date format of the config.
+    ///
+    /// The format is checked first, since formatting a date with an invalid one panics.
+    pub fn format_date(&self, date: OffsetDateTime) -> CResult<String> {
+        check_date_format(&self.date_format)?;
+        Ok(date.format(self.date_format.as_str()))
+    }
+
     /// Creates a document and a font maanger from the config.
     pub fn init(&self) -> CResult<(Document, FontManager)> {
-        let window = Window {
-            x: self.left_margin,
-            y: self.top_margin,
-            width: self.text_width,
-            height: self.text_height,
+        let window = PageWindow {
+            top_margin: self.top_margin,
+            left_margin: self.left_margin,
+            text_width: self.text_width,
+            text_height: self.text_height,
         };
 
-        let mut document = Document::new("Hello", self.page_width, self.page_height, window);
-        let font_manager = FontManager::init(&mut document)?;
+        let mut document = Document::new(
+            &self.title,
+            self.page_width,
+            self.page_height,
+            window.window(),
+        );
+        document.set_page_windows(self.page_windows.iter().map(PageWindow::window).collect());
+        document.set_first_baseline(self.first_baseline);
+        document.set_bottom_skip(self.bottom_skip);
+        document.set_list_marker(self.list_marker);
+        document.set_max_pages(self.max_pages);
+        document.set_min_lines_per_page(self.min_lines_per_page);
+        document.set_color_space(self.color_space);
+        document.set_text_color(self.text_color);
+        document.set_reproducible(self.reproducible);
+        document.set_number_headings(self.number_headings);
+        document.set_draft(self.draft)?;
+        document.set_running_header(self.running_header)?;
+        document.set_show_annotations(self.show_annotations);
+        document.set_two_sided(self.two_sided);
+        document.set_report_units(self.report_units);
+        document.set_page_numbering(if self.page_numbers {
+            Some(PageNumbering {
+                first: self.first_page_number,
+                roman_front_matter: self.roman_front_matter,
+            })
+        } else {
+            None
+        })?;
+        document.set_word_spacing(WordSpacing {
+            min: self.typesetting.min_word_space,
+            max: self.typesetting.max_word_space,
+        });
+        document.set_skips(Skips {
+            paragraph: self.paragraph_skip,
+            list_item: self.list_item_skip,
+            list: self.list_skip,
+        });
+        document.set_tolerances(Tolerances {
+            overfull: self.typesetting.overfull_tolerance,
+            underfull_badness: self.typesetting.underfull_badness,
+        });
+        if self.line_numbers {
+            document.set_line_numbering(Some(self.line_numbering));
+        }
+        document.set_page_break_penalty(self.page_break_penalty);
+        let mut exceptions = HyphenationExceptions::new(&self.typesetting.hyphenation_exceptions);
+        exceptions.set_hyphenate_capitalized(self.typesetting.hyphenate_capitalized);
+        document.set_hyphenation_exceptions(exceptions);
+        if let Some(path) = &self.typesetting.hyphenation_dictionary {
+            // The dictionary may be for any language, not only for the embedded english one.
+            let mut file = File::open(path).map_err(load::Error::from)?;
+            document.set_dictionary(Standard::any_from_reader(&mut file)?);
+        }
+        document.set_abbreviations(Abbreviations::new(&self.typesetting.abbreviations));
+        document.set_clause_space_factor(self.typesetting.clause_space_factor);
+        document.set_french_spacing(self.typesetting.french_spacing);
+        document.set_source_breaks(self.typesetting.respect_source_breaks);
+        document.set_emergency_breaks(self.typesetting.emergency_breaks);
+        document.set_tab_width(self.typesetting.tab_width);
+        document.set_parfill_stretch(self.typesetting.parfill_stretch);
+        document.set_kern_overrides(self.typesetting.kern_overrides.clone());
+        if let Some(line_height) = self.line_height {
+            document.set_line_height(Some(line_height.resolve(DEFAULT_FONT_SIZE)));
+        }
+        let mut font_manager = FontManager::init(&mut document)?;
+        font_manager.set_missing_glyph(self.missing_glyph);
+        font_manager.set_title_sizes(TitleSizes {
+            boost: self.title_size_boost,
+            ratio: self.title_size_ratio,
+        });
+        font_manager.set_family(self.font_family)?;
+        if self.synthetic_styles {
+            font_manager.synthesize_styles();
+        }
+        if let Some(font_cache) = &self.font_cache {
+            font_manager.load_metrics(font_cache);
+        }
 
         Ok((document, font_manager))
     }
 }
+
+/// Builds a config in code, from the default config of `Config::with_title`.
+///
+/// # Example
+///
+/// ```
+/// # use printpdf::Mm;
+/// # use spandex::document::configuration::{ConfigBuilder, Input};
+/// # fn main() -> spandex::Result<()> {
+/// let config = ConfigBuilder::new("My book")
+///     .page_size(Mm(148.0), Mm(210.0))
+///     .margins(Mm(20.0), Mm(25.0))
+///     .input(Input::File(String::from("book.dex")))
+///     .build()?;
+/// assert_eq!(config.title, "My book");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ConfigBuilder {
+    /// The config being built.
+    config: Config,
+
+    /// The horizontal and vertical margins, the same on both sides of the page.
+    margins: (Length, Length),
+}
+
+impl ConfigBuilder {
+    /// Creates a builder, with the default config for a title.
+    pub fn new(title: &str) -> ConfigBuilder {
+        let config = Config::with_title(title);
+        let margins = (config.left_margin.into(), config.top_margin.into());
+        ConfigBuilder { config, margins }
+    }
+
+    /// Sets the size of the pages, in pt or in mm.
+    pub fn page_size<W: Into<Length>, H: Into<Length>>(mut self, width: W, height: H) -> Self {
+        self.config.page_width = width.into().pt();
+        self.config.page_height = height.into().pt();
+        self
+    }
+
+    /// Sets the margins on the left and the right of the pages, and on their top and bottom.
+    pub fn margins<H: Into<Length>, V: Into<Length>>(mut self, horizontal: H, vertical: V) -> Self {
+        self.margins = (horizontal.into(), vertical.into());
+        self
+    }
+
+    /// Sets the path to the content, or the paths to its chapters.
+    pub fn input(mut self, input: Input) -> Self {
+        self.config.input = input;
+        self
+    }
+
+    /// Returns the config, with the size of the text given by the pages and the margins, or an
+    /// error if the text would have no room.
+    pub fn build(self) -> CResult<Config> {
+        let mut config = self.config;
+        let (horizontal, vertical) = self.margins;
+
+        if horizontal.0 < 0.0 || vertical.0 < 0.0 {
+            return Err(Error::InvalidGeometry(String::from(
+                "the margins are negative",
+            )));
+        }
+
+        config.left_margin = horizontal.pt();
+        config.top_margin = vertical.pt();
+        config.text_width = (Length::from(config.page_width) - horizontal * 2.0).pt();
+        config.text_height = (Length::from(config.page_height) - vertical * 2.0).pt();
+
+        if config.text_width.0 <= 0.0 || config.text_height.0 <= 0.0 {
+            return Err(Error::InvalidGeometry(String::from(
+                "the margins leave no room for the text",
+            )));
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use printpdf::{Mm, Pt};
+    use spandex_hyphenation::Hyphenator;
+
+    use crate::document::configuration::{
+        Config, ConfigBuilder, Input, LineHeight, PageWindow, TypesettingConfig,
+    };
+    use crate::layout::paragraphs::ligatures::Ligatures;
+    use crate::{validate_config, Error, Result};
+
+    #[test]
+    fn test_config_builder() -> Result<()> {
+        let config = ConfigBuilder::new("Test")
+            .page_size(Mm(148.0), Mm(210.0))
+            .input(Input::File(String::from("book.dex")))
+            .build()?;
+        let default = Config::with_title("Test");
+
+        // The margins are the default ones, and the text fills the rest of the page.
+        assert_eq!(config.left_margin.0, default.left_margin.0);
+        let expected = Pt::from(Mm(148.0)).0 - 2.0 * default.left_margin.0;
+        assert!((config.text_width.0 - expected).abs() < 1e-9);
+        assert_eq!(config.input, Input::File(String::from("book.dex")));
+        assert_eq!(config.paragraph_skip.0, default.paragraph_skip.0);
+        assert_eq!(config.date_format, default.date_format);
+
+        let wide_margins = ConfigBuilder::new("Test").margins(Mm(150.0), Mm(30.0));
+        assert!(matches!(
+            wide_margins.build(),
+            Err(Error::InvalidGeometry(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = toml::to_string(&Config::with_title("Test")).unwrap();
+        assert_eq!(Config::from_toml(&config).unwrap().title, "Test");
+
+        // The keys of older configs are still known.
+        let older = format!("max_word_space = 15\n{}", config);
+        assert!(Config::from_toml(&older).is_ok());
+
+        let top_skip = config.replace("first_baseline = 0.0", "top_skip = 12.0");
+        assert_eq!(Config::from_toml(&top_skip).unwrap().first_baseline.0, 12.0);
+
+        let mut windows = Config::with_title("Test");
+        windows.page_windows = vec![PageWindow {
+            top_margin: Pt(100.0),
+            left_margin: windows.left_margin,
+            text_width: windows.text_width,
+            text_height: Pt(400.0),
+        }];
+        let windows = toml::to_string(&windows).unwrap();
+        assert!(windows.contains("[[page_windows]]"));
+        assert_eq!(validate_config(&windows).unwrap().page_windows.len(), 1);
+
+        let typo = format!("tittle = \"Test\"\n{}", config);
+        assert!(matches!(
+            Config::from_toml(&typo),
+            Err(Error::UnknownConfigKey(key)) if key == "tittle"
+        ));
+
+        let typo = config.replace("min_word_space", "min_word_spaec");
+        assert!(matches!(
+            Config::from_toml(&typo),
+            Err(Error::UnknownConfigKey(key)) if key == "typesetting.min_word_spaec"
+        ));
+
+        let missing = config.replace("input = \"main.dex\"", "");
+        assert!(matches!(
+            Config::from_toml(&missing),
+            Err(Error::TomlError(_))
+        ));
+
+        let mut wide = Config::with_title("Test");
+        wide.left_margin = Pt(500.0);
+        let wide = toml::to_string(&wide).unwrap();
+        assert!(matches!(
+            Config::from_toml(&wide),
+            Err(Error::InvalidGeometry(_))
+        ));
+
+        let mut no_tabs = Config::with_title("Test");
+        no_tabs.typesetting.tab_width = Pt(0.0);
+        let no_tabs = toml::to_string(&no_tabs).unwrap();
+        assert!(matches!(
+            Config::from_toml(&no_tabs),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        let mut bad_date = Config::with_title("Test");
+        bad_date.date_format = String::from("100%");
+        assert!(matches!(bad_date.check(), Err(Error::InvalidConfig(_))));
+        let bad_date = toml::to_string(&bad_date).unwrap();
+        assert!(matches!(
+            Config::from_toml(&bad_date),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_title_sizes() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.title_size_boost = 1.0;
+        config.title_size_ratio = 0.5;
+
+        let (_, font_manager) = config.init()?;
+        let title_sizes = font_manager.default_config().title_sizes;
+        assert_eq!(title_sizes.size(Pt(10.0), 0).0, 20.0);
+        assert_eq!(title_sizes.size(Pt(10.0), 1).0, 15.0);
+
+        config.title_size_ratio = 2.0;
+        assert!(matches!(config.check(), Err(Error::InvalidConfig(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyphenation_dictionary() {
+        let mut config = Config::with_title("Test");
+        config.typesetting.hyphenation_dictionary = Some(PathBuf::from("missing.dic"));
+        assert!(matches!(config.init(), Err(Error::HyphenationLoadError(_))));
+
+        let toml = toml::to_string(&config).unwrap();
+        assert!(toml.contains("hyphenation_dictionary = \"missing.dic\""));
+
+        // A portuguese dictionary breaks a word that the english one leaves whole.
+        let word = "palavra";
+        let (document, _) = Config::with_title("Test").init().unwrap();
+        assert!(document.dictionary.hyphenate(word).breaks.is_empty());
+
+        let path = PathBuf::from("assets/tests/hyphenation/pt.standard.bincode");
+        config.typesetting.hyphenation_dictionary = Some(path);
+        let (document, _) = config.init().unwrap();
+        assert_eq!(document.dictionary.hyphenate(word).breaks, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_input_single_file() {
+        let mut config = toml::to_string(&Config::with_title("Test")).unwrap();
+        assert!(config.contains("input = \"main.dex\""));
+
+        config = config.replace("input = \"main.dex\"", "input = \"book.dex\"");
+        let config: Config = toml::from_str(&config).unwrap();
+        assert_eq!(config.input, Input::File(String::from("book.dex")));
+        assert_eq!(config.input.paths(), vec!["book.dex"]);
+    }
+
+    #[test]
+    fn test_input_many_files() {
+        let config = toml::to_string(&Config::with_title("Test")).unwrap();
+        let config = config.replace("input = \"main.dex\"", "input = [\"one.dex\", \"two.dex\"]");
+        let config: Config = toml::from_str(&config).unwrap();
+        assert_eq!(config.input.paths(), vec!["one.dex", "two.dex"]);
+    }
+
+    #[test]
+    fn test_line_height() {
+        let config = toml::to_string(&Config::with_title("Test")).unwrap();
+        let read = |line_height: &str| {
+            let config = format!("line_height = {}\n{}", line_height, config);
+            let config: Config = toml::from_str(&config).unwrap();
+            config.line_height.unwrap().resolve(Pt(10.0)).0
+        };
+
+        assert!((read("1.5") - 15.0).abs() < 1e-9);
+        assert!((read("2") - 20.0).abs() < 1e-9);
+        assert!((read("\"12pt\"") - 12.0).abs() < 1e-9);
+
+        let mut config = Config::with_title("Test");
+        config.line_height = Some(LineHeight::Absolute(Pt(12.0)));
+        let config = toml::to_string(&config).unwrap();
+        assert!(config.contains("line_height = \"12pt\""));
+    }
+
+    #[test]
+    fn test_ligatures() {
+        let config = toml::to_string(&Config::with_title("Test")).unwrap();
+        assert!(config.contains("ligatures = \"all\""));
+
+        let read = |ligatures: &str| {
+            let config = format!(
+                "ligatures = {}\n{}",
+                ligatures,
+                config.replace("ligatures = \"all\"", "")
+            );
+            toml::from_str::<Config>(&config)
+                .unwrap()
+                .typesetting
+                .ligatures
+        };
+
+        assert_eq!(read("\"none\""), Ligatures::None);
+        assert_eq!(
+            read("{ except = [\"fi\"] }"),
+            Ligatures::Except(vec![String::from("fi")])
+        );
+    }
+
+    #[test]
+    fn test_typesetting_table() {
+        let config = toml::to_string(&Config::with_title("Test")).unwrap();
+        assert!(config.contains("[typesetting]"));
+
+        let read = |typesetting: &str| -> TypesettingConfig {
+            let config = format!("{}\n{}", config.replace("[typesetting]", ""), typesetting);
+            toml::from_str::<Config>(&config).unwrap().typesetting
+        };
+
+        let nested = read(
+            "[typesetting]\n\
+             min_word_space = 3\n\
+             underfull_badness = 500.0\n\
+             hyphenation_exceptions = [\"data-base\"]\n\
+             ligatures = \"none\"",
+        );
+        assert_eq!(nested.min_word_space.0, 3.0);
+        assert_eq!(nested.underfull_badness, 500.0);
+        assert_eq!(
+            nested.hyphenation_exceptions,
+            vec![String::from("data-base")]
+        );
+        assert_eq!(nested.ligatures, Ligatures::None);
+        assert_eq!(
+            nested.max_word_space.0,
+            TypesettingConfig::default().max_word_space.0
+        );
+
+        // The keys of older configs are still read from the top level.
+        let older = &config[..config.find("[typesetting]").unwrap()];
+        let config = format!("max_word_space = 15\n{}", older);
+        let flat: Config = toml::from_str(&config).unwrap();
+        assert_eq!(flat.typesetting.max_word_space.0, 15.0);
+    }
+}
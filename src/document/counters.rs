@@ -1,24 +1,185 @@
 //! Logic for recursive counters in a document for titles and other
 //! counted items.
 
+use std::collections::HashMap;
 use std::fmt;
 
+/// The way a counter is displayed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CounterFormat {
+    /// Arabic numerals: 1, 2, 3...
+    Arabic,
+
+    /// Upper case letters: A, B, C...
+    UpperAlpha,
+
+    /// Lower case letters: a, b, c...
+    LowerAlpha,
+
+    /// Upper case roman numerals: I, II, III...
+    UpperRoman,
+
+    /// Lower case roman numerals: i, ii, iii...
+    LowerRoman,
+}
+
+impl Default for CounterFormat {
+    fn default() -> CounterFormat {
+        CounterFormat::Arabic
+    }
+}
+
+impl CounterFormat {
+    /// Formats the value of a counter.
+    ///
+    /// Letters go on with two letters after Z, and zero is always displayed as an arabic numeral
+    /// since it has no alphabetic nor roman counterpart.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use spandex::document::counters::CounterFormat;
+    /// assert_eq!(CounterFormat::Arabic.format(12), "12");
+    /// assert_eq!(CounterFormat::UpperAlpha.format(1), "A");
+    /// assert_eq!(CounterFormat::UpperAlpha.format(28), "AB");
+    /// assert_eq!(CounterFormat::LowerAlpha.format(3), "c");
+    /// assert_eq!(CounterFormat::UpperRoman.format(1994), "MCMXCIV");
+    /// assert_eq!(CounterFormat::LowerRoman.format(4), "iv");
+    /// ```
+    pub fn format(self, value: usize) -> String {
+        if value == 0 {
+            return value.to_string();
+        }
+
+        match self {
+            CounterFormat::Arabic => value.to_string(),
+            CounterFormat::UpperAlpha => alphabetic(value),
+            CounterFormat::LowerAlpha => alphabetic(value).to_lowercase(),
+            CounterFormat::UpperRoman => roman(value),
+            CounterFormat::LowerRoman => roman(value).to_lowercase(),
+        }
+    }
+}
+
+/// Converts a strictly positive number into upper case letters.
+fn alphabetic(mut value: usize) -> String {
+    let mut letters = vec![];
+
+    while value > 0 {
+        value -= 1;
+        letters.push((b'A' + (value % 26) as u8) as char);
+        value /= 26;
+    }
+
+    letters.iter().rev().collect()
+}
+
+/// Converts a strictly positive number into upper case roman numerals.
+fn roman(mut value: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut output = String::new();
+
+    for (number, numeral) in NUMERALS.iter() {
+        while value >= *number {
+            output.push_str(numeral);
+            value -= number;
+        }
+    }
+
+    output
+}
+
+/// A counter of some items of a document, like its figures, independent from the titles.
+#[derive(Clone, Debug, Default)]
+pub struct NamedCounter {
+    /// The value of the counter.
+    pub value: usize,
+
+    /// The level of the titles that reset the counter, if any: 0 for the sections.
+    pub reset_level: Option<usize>,
+
+    /// The number of the section in which the counter was last incremented, when it is reset.
+    section: String,
+}
+
 /// The struct that manages the counters for the document.
 #[derive(Clone, Default)]
 pub struct Counters {
     /// The counters.
     pub counters: Vec<usize>,
+
+    /// The formats of the counters, the missing ones being arabic.
+    pub formats: Vec<CounterFormat>,
+
+    /// The counters of the figures, tables, equations, and other items, by name.
+    pub named: HashMap<String, NamedCounter>,
 }
 
 impl Counters {
     /// Creates a new empty counters.
     pub fn new() -> Counters {
-        Counters { counters: vec![0] }
+        Counters {
+            counters: vec![0],
+            formats: vec![],
+            named: HashMap::new(),
+        }
+    }
+
+    /// Sets the format of a specific counter.
+    pub fn set_format(&mut self, counter_id: usize, format: CounterFormat) {
+        if self.formats.len() <= counter_id {
+            self.formats.resize(counter_id + 1, CounterFormat::Arabic);
+        }
+        self.formats[counter_id] = format;
+    }
+
+    /// Returns the format of a specific counter.
+    pub fn format(&self, counter_id: usize) -> CounterFormat {
+        self.formats.get(counter_id).copied().unwrap_or_default()
+    }
+
+    /// Starts the appendices: the top level counter is reset and displayed with letters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use spandex::document::counters::Counters;
+    /// let mut counters = Counters::new();
+    /// counters.increment(0);
+    /// counters.increment(0);
+    /// counters.start_appendix();
+    /// counters.increment(0);
+    /// assert_eq!(counters.to_string(), "A");
+    /// counters.increment(1);
+    /// assert_eq!(counters.to_string(), "A.1");
+    /// counters.increment(0);
+    /// assert_eq!(counters.to_string(), "B");
+    /// ```
+    pub fn start_appendix(&mut self) {
+        self.counters = vec![0];
+        self.set_format(0, CounterFormat::UpperAlpha);
     }
 
     /// Increases the corresponding counter and returns it if it is correct.
     ///
-    /// The counters of the subsections will be reinitialized.
+    /// The counters of the subsections will be reinitialized. The counters of the skipped levels,
+    /// if any, start at 1, so that a subsubsection right after a section is numbered 1.1.1
+    /// rather than 1.0.1.
     ///
     /// # Example
     ///
@@ -36,14 +197,33 @@ impl Counters {
     /// counters.increment(0);
     /// assert_eq!(counters.counter(0), 2);
     /// assert_eq!(counters.counter(1), 0);
-    /// println!("{}", counters);
+    /// counters.increment(2);
+    /// assert_eq!(counters.to_string(), "2.1.1");
     /// ```
     pub fn increment(&mut self, counter_id: usize) -> usize {
         self.counters.resize(counter_id + 1, 0);
+        for counter in &mut self.counters[..counter_id] {
+            *counter = (*counter).max(1);
+        }
         self.counters[counter_id] += 1;
         self.counters[counter_id]
     }
 
+    /// Returns whether incrementing a counter skips a level, whose counter is still at zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use spandex::document::counters::Counters;
+    /// let mut counters = Counters::new();
+    /// counters.increment(0);
+    /// assert!(!counters.skips_level(1));
+    /// assert!(counters.skips_level(2));
+    /// ```
+    pub fn skips_level(&self, counter_id: usize) -> bool {
+        (0..counter_id).any(|id| self.counter(id) == 0)
+    }
+
     /// Returns a specific value of a counter.
     pub fn counter(&self, counter_id: usize) -> usize {
         match self.counters.get(counter_id) {
@@ -51,6 +231,91 @@ impl Counters {
             None => 0,
         }
     }
+
+    /// Returns the number of the current title at a level, e.g. "2.3" for the subsection 3 of
+    /// the section 2 at level 1.
+    fn section(&self, level: usize) -> String {
+        (0..=level)
+            .map(|id| self.format(id).format(self.counter(id)))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Adds a named counter, reset by the titles of a level if any, or resets it if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use spandex::document::counters::Counters;
+    /// let mut counters = Counters::new();
+    /// counters.add_named("figure", Some(0));
+    /// counters.add_named("table", None);
+    /// counters.increment(0);
+    /// counters.increment_named("figure");
+    /// counters.increment_named("table");
+    /// counters.increment(0);
+    /// assert_eq!(counters.increment_named("figure"), 1);
+    /// assert_eq!(counters.increment_named("table"), 2);
+    /// assert_eq!(counters.named_to_string("figure"), "2.1");
+    /// assert_eq!(counters.named_to_string("table"), "2");
+    /// ```
+    pub fn add_named(&mut self, name: &str, reset_level: Option<usize>) {
+        self.named.insert(
+            name.to_string(),
+            NamedCounter {
+                reset_level,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Increases a named counter and returns it, starting it over if its section has changed.
+    ///
+    /// The counter is added, without reset, if it doesn't exist yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use spandex::document::counters::Counters;
+    /// let mut counters = Counters::new();
+    /// assert_eq!(counters.increment_named("equation"), 1);
+    /// assert_eq!(counters.increment_named("equation"), 2);
+    /// assert_eq!(counters.named("figure"), 0);
+    /// ```
+    pub fn increment_named(&mut self, name: &str) -> usize {
+        let section = self.named.get(name).and_then(|counter| counter.reset_level);
+        let section = section.map(|level| self.section(level)).unwrap_or_default();
+        let counter = self.named.entry(name.to_string()).or_default();
+
+        if counter.section != section {
+            counter.value = 0;
+            counter.section = section;
+        }
+
+        counter.value += 1;
+        counter.value
+    }
+
+    /// Returns the value of a named counter, 0 if it doesn't exist or its section has changed.
+    pub fn named(&self, name: &str) -> usize {
+        match self.named.get(name) {
+            Some(counter) => match counter.reset_level {
+                Some(level) if counter.section != self.section(level) => 0,
+                _ => counter.value,
+            },
+            None => 0,
+        }
+    }
+
+    /// Returns the number of the current item of a named counter, prefixed by its section if it
+    /// is reset by the titles.
+    pub fn named_to_string(&self, name: &str) -> String {
+        let value = self.named(name).to_string();
+        match self.named.get(name).and_then(|counter| counter.reset_level) {
+            Some(level) => format!("{}.{}", self.section(level), value),
+            None => value,
+        }
+    }
 }
 
 impl fmt::Display for Counters {
@@ -60,7 +325,8 @@ impl fmt::Display for Counters {
             "{}",
             self.counters
                 .iter()
-                .map(std::string::ToString::to_string)
+                .enumerate()
+                .map(|(id, value)| self.format(id).format(*value))
                 .collect::<Vec<_>>()
                 .join(".")
         )
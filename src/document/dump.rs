@@ -0,0 +1,66 @@
+//! This module describes the positioned layout of a document, that can be dumped as JSON with
+//! `spandex build --dump-layout layout.json`.
+//!
+//! The dump is an array of glyphs, in the order they were drawn, each being an object with the
+//! following fields:
+//!
+//! - `page`: the number of the page the glyph is on, starting at 1,
+//! - `x`: the horizontal position of the glyph, from the left of the page,
+//! - `y`: the vertical position of the baseline of the glyph, from the bottom of the page,
+//! - `glyph`: the text that was drawn, a single character for typeset paragraphs,
+//! - `font`: the postscript name of the font,
+//! - `size`: the size of the font in pt,
+//! - `shear`: the horizontal shear of a glyph slanted into a synthesized italic, only present
+//!   if it isn't zero,
+//! - `stroke`: the width of the outline of a glyph stroked into a synthesized bold, only
+//!   present if it isn't zero.
+//!
+//! The positions and the outlines are in the `report_units` of the config, pt by default, while
+//! the font sizes are always in pt.
+//!
+//! For example:
+//!
+//! ```json
+//! [{"page":1,"x":85.03,"y":756.85,"glyph":"H","font":"CMUSerif-Roman","size":10.0}]
+//! ```
+//!
+//! This schema is stable: fields may be added, but existing fields won't be renamed or removed.
+
+use serde::Serialize;
+
+/// A glyph drawn at some position of the document.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PositionedGlyph {
+    /// The number of the page the glyph is on, starting at 1.
+    pub page: usize,
+
+    /// The horizontal position of the glyph in pt, from the left of the page.
+    pub x: f64,
+
+    /// The vertical position of the baseline of the glyph in pt, from the bottom of the page.
+    pub y: f64,
+
+    /// The text that was drawn.
+    pub glyph: String,
+
+    /// The postscript name of the font.
+    pub font: String,
+
+    /// The size of the font in pt.
+    pub size: f64,
+
+    /// The horizontal shear of the glyph, if it is slanted into a synthesized italic.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub shear: f64,
+
+    /// The width in pt of the outline of the glyph, if it is stroked into a synthesized bold.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub stroke: f64,
+}
+
+/// Returns whether an optional field of a glyph is left out of the dump.
+// This is required to use in macro `skip_serializing_if`.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(x: &f64) -> bool {
+    *x == 0.0
+}
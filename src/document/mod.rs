@@ -1,25 +1,290 @@
 //! This module allows to create beautiful documents.
 
+pub mod color;
 pub mod configuration;
 pub mod counters;
+pub mod dump;
+pub mod raw;
 
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::f64;
+use std::fmt;
 use std::fs::File;
-use std::io::BufWriter;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::rc::Rc;
 
-use printpdf::{PdfDocument, PdfDocumentReference, PdfLayerReference, PdfPageReference, Pt};
+use colored::*;
+use printpdf::lopdf::content::Operation;
+use printpdf::lopdf::Object;
+use printpdf::{
+    BuiltinFont, CurTransMat, CustomPdfConformance, IndirectFontRef, OffsetDateTime,
+    PdfConformance, PdfDocument, PdfDocumentReference, PdfLayerReference, PdfPageReference, Pt,
+    TextRenderingMode,
+};
 use spandex_hyphenation::load::Load;
 use spandex_hyphenation::{Language, Standard};
 
-use crate::document::counters::Counters;
+use crate::document::color::{Color, ColorSpace};
+use crate::document::configuration::{LineNumbering, MarginSide, RunningHeader};
+use crate::document::counters::{CounterFormat, Counters};
+use crate::document::dump::PositionedGlyph;
+use crate::document::raw::{is_balanced, PDF_BACKEND};
 use crate::fonts::configuration::FontConfig;
 use crate::fonts::Font;
-use crate::layout::paragraphs::justification::{Justifier, LatexJustifier};
-use crate::layout::paragraphs::utils::ast::itemize_ast;
+use crate::layout::constants::{
+    ANCHOR_GLYPH, DEFAULT_LIST_ITEM_SKIP, DEFAULT_LIST_SKIP, DEFAULT_OVERFULL_TOLERANCE,
+    DEFAULT_PAGE_BREAK_PENALTY, DEFAULT_PARAGRAPH_SKIP, DEFAULT_UNDERFULL_BADNESS,
+    FALLBACK_LIST_MARKER, FIT_SIZE_PRECISION, LIST_INDENT, LIST_MARKER, MAX_PAGE_BADNESS,
+    TAB_WIDTH,
+};
+use crate::layout::paragraphs::abbreviations::Abbreviations;
+use crate::layout::paragraphs::engine::WordSpacing;
+use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+use crate::layout::paragraphs::items::Content;
+use crate::layout::paragraphs::justification::{
+    JustifiedLine, Justifier, LatexJustifier, RaggedJustifier,
+};
+use crate::layout::paragraphs::kerning::KernOverrides;
+use crate::layout::paragraphs::spacing::Spacing;
+use crate::layout::paragraphs::utils::ast::{itemize_ast, itemize_verbatim};
+use crate::layout::paragraphs::Paragraph;
 use crate::parser::ast::Ast;
+use crate::parser::warning::WarningType;
+use crate::units::ReportUnit;
+use crate::Result;
+
+/// The environment variable that fixes the dates embedded in reproducible documents.
+///
+/// See https://reproducible-builds.org/specs/source-date-epoch/.
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// The default format of the date written by `\today`, as in "January 01, 1970".
+pub const DEFAULT_DATE_FORMAT: &str = "%B %d, %Y";
+
+/// The text of the watermark stamped on each page of a draft.
+pub const WATERMARK: &str = "DRAFT";
+
+/// The size of the running headers, in pt.
+const RUNNING_HEADER_SIZE: f64 = 9.0;
+
+/// The space between the running headers and the text.
+const RUNNING_HEADER_GAP: Pt = Pt(15.0);
+
+/// The size of the page numbers, in pt.
+const PAGE_NUMBER_SIZE: f64 = 10.0;
+
+/// The space between the text and the page numbers below it.
+const PAGE_NUMBER_GAP: Pt = Pt(25.0);
+
+/// The size of the icons of the annotations, in pt.
+const ANNOTATION_SIZE: f64 = 16.0;
+
+/// The size of the watermark, in pt.
+const WATERMARK_SIZE: f64 = 96.0;
+
+/// The width of `WATERMARK`, "DRAFT", in ems of the builtin Helvetica Bold it is written in.
+///
+/// The builtin fonts can't be measured, so it is the sum of the advances of the letters in the
+/// standard metrics of the font, 0.722 for D, R and A, and 0.611 for F and T.
+const WATERMARK_WIDTH_EMS: f64 = 3.388;
+
+/// The grey level of the watermark, 1.0 being white.
+const WATERMARK_GREY: f64 = 0.85;
+
+/// The size of the line numbers, relative to the size of the text.
+const LINE_NUMBER_SCALE: f64 = 0.6;
+
+/// The space between the line numbers and the text.
+const LINE_NUMBER_GAP: Pt = Pt(10.0);
+
+/// The horizontal shear of the synthesized italics, about 11 degrees.
+const SYNTHETIC_SLANT: f64 = 0.2;
+
+/// The width of the outline that strokes the synthesized bold, relative to the font size.
+const SYNTHETIC_STROKE: f64 = 0.03;
+
+/// A warning raised while laying out a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutWarning {
+    /// The type of the warning.
+    pub ty: WarningType,
+
+    /// The text that raised the warning.
+    pub content: String,
+
+    /// How much the text overflows, formatted in the unit of the reports, if it does.
+    pub overflow: Option<String>,
+}
+
+impl fmt::Display for LayoutWarning {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            fmt,
+            "{}{}",
+            "warning: ".bold().yellow(),
+            self.ty.title().bold()
+        )?;
+        writeln!(fmt, " {} {}", "|".blue().bold(), self.content)?;
+        let detail = match &self.overflow {
+            Some(overflow) => format!("{} ({} too wide)", self.ty.detail(), overflow),
+            None => self.ty.detail().to_string(),
+        };
+        writeln!(fmt, " {} {}", "|".blue().bold(), detail.bold().yellow())?;
+
+        if let Some(note) = self.ty.note() {
+            writeln!(fmt, " {} {}{}", "=".blue().bold(), "note: ".bold(), note)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Statistics on the lines of a document, for the authors tuning its typesetting.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LineStats {
+    /// The number of lines.
+    pub lines: usize,
+
+    /// The highest badness of a line.
+    pub max_badness: f64,
+
+    /// The sum of the adjustment ratios of the lines.
+    pub total_adjustment_ratio: f64,
+
+    /// The number of lines broken inside a word.
+    pub hyphenated_lines: usize,
+}
+
+impl LineStats {
+    /// Adds a line to the statistics.
+    fn add(&mut self, line: &JustifiedLine) {
+        self.lines += 1;
+        self.max_badness = self.max_badness.max(line.badness);
+        self.total_adjustment_ratio += line.adjustment_ratio;
+        self.hyphenated_lines += usize::from(line.hyphenated);
+    }
+
+    /// Returns the average adjustment ratio of the lines, 0 if there are none.
+    pub fn average_adjustment_ratio(&self) -> f64 {
+        match self.lines {
+            0 => 0.0,
+            lines => self.total_adjustment_ratio / lines as f64,
+        }
+    }
+}
+
+impl fmt::Display for LineStats {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} lines, {} hyphenated, maximum badness {:.0}, average adjustment ratio {:.3}",
+            self.lines,
+            self.hyphenated_lines,
+            self.max_badness,
+            self.average_adjustment_ratio()
+        )
+    }
+}
+
+/// A comment for the reviewers, attached to a position of a page as a pdf annotation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// The number of the page the annotation is on, starting at 1.
+    pub page: usize,
+
+    /// The horizontal position of the annotation in pt, from the left of the page.
+    pub x: f64,
+
+    /// The vertical position of the annotation in pt, from the bottom of the page.
+    pub y: f64,
+
+    /// The comment.
+    pub note: String,
+}
+
+/// A top level section of a document, as needed by the running headers.
+#[derive(Clone, Debug)]
+struct Section {
+    /// The number of the page on which the section starts.
+    page: usize,
+
+    /// Whether the section starts at the top of its page.
+    at_top: bool,
+
+    /// The title of the section.
+    title: String,
+}
+
+/// The vertical spaces added after the different blocks of a document.
+#[derive(Copy, Clone, Debug)]
+pub struct Skips {
+    /// The space after a paragraph.
+    pub paragraph: Pt,
+
+    /// The space after an item of a list.
+    pub list_item: Pt,
+
+    /// The space after a list, added to the space after its last item.
+    pub list: Pt,
+}
+
+impl Default for Skips {
+    fn default() -> Skips {
+        Skips {
+            paragraph: DEFAULT_PARAGRAPH_SKIP,
+            list_item: DEFAULT_LIST_ITEM_SKIP,
+            list: DEFAULT_LIST_SKIP,
+        }
+    }
+}
+
+/// How bad a line can be before it raises a warning.
+#[derive(Copy, Clone, Debug)]
+pub struct Tolerances {
+    /// The length by which a line can exceed the text, like TeX's `\hfuzz`.
+    pub overfull: Pt,
+
+    /// The badness above which a loose line is reported, like TeX's `\hbadness`.
+    pub underfull_badness: f64,
+}
+
+impl Default for Tolerances {
+    fn default() -> Tolerances {
+        Tolerances {
+            overfull: DEFAULT_OVERFULL_TOLERANCE,
+            underfull_badness: DEFAULT_UNDERFULL_BADNESS,
+        }
+    }
+}
+
+/// How the pages of a document are numbered.
+#[derive(Copy, Clone, Debug)]
+pub struct PageNumbering {
+    /// The number of the first page of the main matter, or of the first page if there is no
+    /// `\mainmatter` marker.
+    pub first: i32,
+
+    /// Whether the pages before the `\mainmatter` marker are numbered with lower case roman
+    /// numerals from i, instead of arabic numerals from 1.
+    pub roman_front_matter: bool,
+}
+
+impl Default for PageNumbering {
+    fn default() -> PageNumbering {
+        PageNumbering {
+            first: 1,
+            roman_front_matter: false,
+        }
+    }
+}
 
 /// The window that is the part of the page on which we're allowed to write.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Window {
     /// The x coordinate of the window, in pt.
     pub x: Pt,
@@ -34,6 +299,26 @@ pub struct Window {
     pub height: Pt,
 }
 
+/// A rectangular region of a page.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Region {
+    /// The x coordinate of the bottom left corner of the region, in pt.
+    pub x: Pt,
+
+    /// The y coordinate of the bottom left corner of the region, in pt.
+    pub y: Pt,
+
+    /// The width of the region, in pt.
+    pub width: Pt,
+
+    /// The height of the region, in pt.
+    pub height: Pt,
+}
+
+/// A function called on each page once the document is laid out, with the layer of the page and
+/// its number, starting at 1.
+pub type PageHook = Box<dyn FnMut(&PdfLayerReference, usize)>;
+
 /// This struct contains the pdf document.
 pub struct Document {
     /// The inner document from printpdf.
@@ -54,8 +339,140 @@ pub struct Document {
     /// The current page size, in pt.
     page_size: (Pt, Pt),
 
+    /// The number of the current page, starting at 1.
+    page_number: usize,
+
+    /// The glyphs drawn so far, with their positions.
+    layout: Vec<PositionedGlyph>,
+
     /// The counters of the document
     counters: Counters,
+
+    /// The numbers of the labeled items, like equations, by label.
+    labels: HashMap<String, String>,
+
+    /// Statistics on the lines written so far.
+    line_stats: LineStats,
+
+    /// Whether the generated pdf must be the same byte for byte for the same input.
+    reproducible: bool,
+
+    /// Whether titles are prefixed with their section number.
+    number_headings: bool,
+
+    /// How the lines are numbered in the margin, if they are.
+    line_numbering: Option<LineNumbering>,
+
+    /// The number of the last line written.
+    line_number: usize,
+
+    /// The font of the draft watermark, if the document is a draft.
+    watermark: Option<IndirectFontRef>,
+
+    /// The penalty for breaking a page inside a paragraph.
+    page_break_penalty: f64,
+
+    /// The limits of the spaces between words.
+    word_spacing: WordSpacing,
+
+    /// The vertical spaces after the blocks.
+    skips: Skips,
+
+    /// How bad a line can be before it raises a warning.
+    tolerances: Tolerances,
+
+    /// The height of the lines, the font size if unset.
+    line_height: Option<Pt>,
+
+    /// The warnings raised while laying out the document.
+    warnings: Vec<LayoutWarning>,
+
+    /// The dictionary that hyphenates the words, the embedded english one by default.
+    dictionary: Rc<Standard>,
+
+    /// The words hyphenated by the user rather than by the dictionary.
+    hyphenation_exceptions: HyphenationExceptions,
+
+    /// How the whitespaces between the words are laid out.
+    spacing: Spacing,
+
+    /// Whether the words wider than a line are broken between their characters.
+    emergency_breaks: bool,
+
+    /// The unit of the lengths in the warnings.
+    report_units: ReportUnit,
+
+    /// The distance between two tab stops.
+    tab_width: Pt,
+
+    /// The stretch of the glue that ends the paragraphs, relative to the text width, or none
+    /// for an infinite one.
+    parfill_stretch: Option<f64>,
+
+    /// The adjustments of the gap between some pairs of adjacent chars.
+    kern_overrides: KernOverrides,
+
+    /// The color space in which the colors are written.
+    color_space: ColorSpace,
+
+    /// The color of the text, black if unset.
+    text_color: Option<Color>,
+
+    /// The layer of each page, to draw on the pages once they are all laid out.
+    layers: Vec<PdfLayerReference>,
+
+    /// The windows of the first pages, the last one being used for the following pages, or none
+    /// to use the same window on all the pages.
+    page_windows: Vec<Window>,
+
+    /// The distance from the top of the window to the baseline of the first line of each page.
+    first_baseline: Pt,
+
+    /// The space kept empty at the bottom of the window of each page.
+    bottom_skip: Pt,
+
+    /// The functions that draw on each page once they are all laid out.
+    page_hooks: Vec<PageHook>,
+
+    /// The maximum number of pages, if any.
+    max_pages: Option<usize>,
+
+    /// The number of lines under which a page that isn't empty raises a warning.
+    min_lines_per_page: Option<usize>,
+
+    /// Whether some content was laid out after the maximum number of pages, and not drawn.
+    clipped: bool,
+
+    /// The marker drawn before the items of unordered lists.
+    list_marker: char,
+
+    /// The top level sections of the document.
+    sections: Vec<Section>,
+
+    /// Which section is shown in the running headers, with the font of the headers.
+    running_header: Option<(RunningHeader, IndirectFontRef)>,
+
+    /// How the pages are numbered, with the font of the numbers, if they are.
+    page_numbering: Option<(PageNumbering, IndirectFontRef)>,
+
+    /// The page on which the main matter starts, if the document has a front matter.
+    main_matter: Option<usize>,
+
+    /// Whether the page numbers and the running headers sit in the outer margin, alternating
+    /// between the left of the even pages and the right of the odd ones.
+    two_sided: bool,
+
+    /// Whether the annotations are attached to the document.
+    show_annotations: bool,
+
+    /// The annotations attached to the document.
+    annotations: Vec<Annotation>,
+
+    /// The pages that only show a region, with the region.
+    crops: Vec<(usize, Region)>,
+
+    /// The pdf files whose pages are appended after the pages of the document.
+    appended: Vec<lopdf::Document>,
 }
 
 impl Document {
@@ -69,7 +486,8 @@ impl Document {
         let width: Pt = width.into();
         let height: Pt = height.into();
 
-        let (document, page, layer) = PdfDocument::new(name, width.into(), height.into(), "");
+        let (document, page, layer) =
+            PdfDocument::new(name, width.into(), height.into(), layer_name(1));
 
         let page = document.get_page(page);
         let layer = page.get_layer(layer);
@@ -77,140 +495,2959 @@ impl Document {
         Document {
             document,
             page,
+            layers: vec![layer.clone()],
+            page_windows: vec![],
+            first_baseline: Pt(0.0),
+            bottom_skip: Pt(0.0),
+            page_hooks: vec![],
+            max_pages: None,
+            min_lines_per_page: None,
+            clipped: false,
+            list_marker: LIST_MARKER,
             layer,
             window,
             cursor: (window.x, window.height + window.y),
             page_size: (width, height),
+            page_number: 1,
+            layout: vec![],
             counters: Counters::new(),
+            labels: HashMap::new(),
+            line_stats: LineStats::default(),
+            reproducible: false,
+            number_headings: true,
+            line_numbering: None,
+            line_number: 0,
+            watermark: None,
+            page_break_penalty: DEFAULT_PAGE_BREAK_PENALTY,
+            word_spacing: WordSpacing::default(),
+            skips: Skips::default(),
+            tolerances: Tolerances::default(),
+            line_height: None,
+            warnings: vec![],
+            dictionary: Rc::new(Standard::from_embedded(Language::EnglishUS).unwrap()),
+            hyphenation_exceptions: HyphenationExceptions::default(),
+            spacing: Spacing::default(),
+            emergency_breaks: false,
+            color_space: ColorSpace::Rgb,
+            text_color: None,
+            report_units: ReportUnit::Pt,
+            tab_width: TAB_WIDTH,
+            parfill_stretch: None,
+            kern_overrides: KernOverrides::default(),
+            sections: vec![],
+            running_header: None,
+            page_numbering: None,
+            main_matter: None,
+            two_sided: false,
+            show_annotations: false,
+            annotations: vec![],
+            crops: vec![],
+            appended: vec![],
         }
     }
 
-    /// Returns a reference to the inner pdf document.
-    pub fn inner(&self) -> &PdfDocumentReference {
-        &self.document
+    /// Sets whether the generated pdf must be the same byte for byte for the same input.
+    ///
+    /// When enabled, the dates of the pdf are set to the value of the `SOURCE_DATE_EPOCH`
+    /// environment variable, or to the unix epoch if it is not set, and the identifiers of the
+    /// document are derived from its content instead of being random.
+    pub fn set_reproducible(&mut self, reproducible: bool) {
+        self.reproducible = reproducible;
     }
 
-    /// Returns a mutable reference to the inner pdf document.
-    pub fn inner_mut(&mut self) -> &mut PdfDocumentReference {
-        &mut self.document
+    /// Sets whether the document is a draft, with a watermark stamped across each page.
+    ///
+    /// The watermark is drawn when a page is created, under its content, so this should be called
+    /// before rendering anything.
+    pub fn set_draft(&mut self, draft: bool) -> Result<()> {
+        match (draft, &self.watermark) {
+            (true, None) => {
+                let font = self.document.add_builtin_font(BuiltinFont::HelveticaBold)?;
+                self.watermark = Some(font);
+                self.stamp_watermark();
+            }
+            (false, _) => self.watermark = None,
+            _ => (),
+        }
+
+        Ok(())
     }
 
-    /// Renders an AST to the document.
-    pub fn render(&mut self, ast: &Ast, font_config: &FontConfig, size: Pt) {
-        let en = Standard::from_embedded(Language::EnglishUS).unwrap();
+    /// Stamps the draft watermark diagonally across the current page, if the document is a draft.
+    fn stamp_watermark(&self) {
+        let font = match &self.watermark {
+            Some(font) => font,
+            None => return,
+        };
 
-        match ast {
-            Ast::File(_, children) => {
-                for child in children {
-                    self.render(child, font_config, size);
-                }
+        // Start the text so that its middle is at the center of the page once rotated.
+        let half_width = WATERMARK_WIDTH_EMS * WATERMARK_SIZE / 2.0 * f64::consts::FRAC_1_SQRT_2;
+        let x = self.page_size.0 / 2.0 - Pt(half_width);
+        let y = self.page_size.1 / 2.0 - Pt(half_width);
+
+        self.layer.save_graphics_state();
+        self.layer
+            .set_fill_color(Color::grey(WATERMARK_GREY).to_pdf(self.color_space));
+        self.layer
+            .set_ctm(CurTransMat::Translate(x.into(), y.into()));
+        self.layer.set_ctm(CurTransMat::Rotate(45.0));
+        self.layer.use_text(
+            WATERMARK,
+            WATERMARK_SIZE,
+            Pt(0.0).into(),
+            Pt(0.0).into(),
+            font,
+        );
+        self.layer.restore_graphics_state();
+    }
+
+    /// Sets the color space in which the colors are written.
+    ///
+    /// It should be set before anything is drawn, since the colors already drawn are not
+    /// converted.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    /// Sets the color of the text, from the current page on.
+    pub fn set_text_color(&mut self, text_color: Option<Color>) {
+        self.text_color = text_color;
+        self.apply_text_color();
+    }
+
+    /// Sets the fill color of the current page to the color of the text, if it has one.
+    fn apply_text_color(&self) {
+        if let Some(color) = self.text_color {
+            self.layer.set_fill_color(color.to_pdf(self.color_space));
+        }
+    }
+
+    /// Sets which section is shown at the top of each page, or disables the running headers.
+    ///
+    /// The headers are drawn once the whole document is laid out, since the sections of a page
+    /// are only known then.
+    pub fn set_running_header(&mut self, running_header: Option<RunningHeader>) -> Result<()> {
+        self.running_header = match running_header {
+            Some(running_header) => {
+                let font = self.document.add_builtin_font(BuiltinFont::TimesItalic)?;
+                Some((running_header, font))
             }
+            None => None,
+        };
 
-            Ast::Title { level, children } => {
-                self.counters.increment(*level as usize);
-                let mut new_children = vec![Ast::Text(format!("{}  ", self.counters))];
-                new_children.extend_from_slice(children);
-                let new_ast = Ast::Title {
-                    level: *level,
-                    children: new_children,
-                };
-                self.write_paragraph::<LatexJustifier>(&new_ast, font_config, size, &en);
-                self.new_line(size);
+        Ok(())
+    }
+
+    /// Returns the text of the running header of each page, the first page coming first.
+    pub fn running_headers(&self) -> Vec<Option<String>> {
+        let running_header = match &self.running_header {
+            Some((running_header, _)) => *running_header,
+            None => return vec![None; self.page_number],
+        };
+
+        let mut sections = self.sections.iter().peekable();
+        let mut current: Option<&Section> = None;
+        let mut headers = vec![];
+
+        for page in 1..=self.page_number {
+            let top = current;
+            let mut first = None;
+
+            while let Some(section) = sections.next_if(|section| section.page == page) {
+                first.get_or_insert(section);
+                current = Some(section);
             }
 
-            Ast::Paragraph(_) => {
-                self.write_paragraph::<LatexJustifier>(ast, font_config, size, &en);
-                self.new_line(size);
-                self.new_line(size);
+            let header = match (running_header, first) {
+                (RunningHeader::First, Some(first)) if first.at_top || top.is_none() => Some(first),
+                (RunningHeader::First, _) => top,
+                (RunningHeader::Last, _) => current,
+            };
+
+            headers.push(header.map(|section| section.title.clone()));
+        }
+
+        headers
+    }
+
+    /// Draws the running headers on all the pages.
+    fn draw_running_headers(&self) {
+        let font = match &self.running_header {
+            Some((_, font)) => font,
+            None => return,
+        };
+
+        let headers = self.running_headers().into_iter().enumerate();
+        for (layer, (page, header)) in self.layers.iter().zip(headers) {
+            if let Some(header) = header {
+                let window = self.window_of(page + 1);
+                let y = window.y + window.height + RUNNING_HEADER_GAP;
+                let x = if self.two_sided {
+                    self.outer_x(page + 1, estimated_width(&header, RUNNING_HEADER_SIZE))
+                } else {
+                    window.x
+                };
+                layer.use_text(header, RUNNING_HEADER_SIZE, x.into(), y.into(), font);
             }
+        }
+    }
 
-            _ => (),
+    /// Sets the windows of the first pages, the last one being used for the following pages, e.g.
+    /// for a wide first page and narrower ones after it.
+    ///
+    /// The window of the current page changes at once, so they should be set before anything
+    /// is written.
+    pub fn set_page_windows(&mut self, page_windows: Vec<Window>) {
+        self.page_windows = page_windows;
+        self.window = self.window_of(self.page_number);
+        self.cursor = (self.window.x, self.top());
+    }
+
+    /// Sets the distance from the top of the window to the baseline of the first line of each
+    /// page, e.g. the ascent of the text so that it doesn't rise above the window.
+    ///
+    /// The cursor of the current page moves at once, so it should be set before anything is
+    /// written.
+    pub fn set_first_baseline(&mut self, first_baseline: Pt) {
+        self.first_baseline = first_baseline;
+        self.cursor.1 = self.top();
+    }
+
+    /// Sets the space kept empty at the bottom of the window of each page, so that the last
+    /// baseline of a page is at least this far above the bottom of the window.
+    pub fn set_bottom_skip(&mut self, bottom_skip: Pt) {
+        self.bottom_skip = bottom_skip;
+    }
+
+    /// Sets the maximum number of pages of the document.
+    ///
+    /// The content that doesn't fit in these pages is still laid out, but is not drawn, and a
+    /// warning is raised.
+    pub fn set_max_pages(&mut self, max_pages: Option<usize>) {
+        self.max_pages = max_pages;
+    }
+
+    /// Sets the number of lines under which a page that isn't empty raises a warning, e.g. to
+    /// catch a stray line at the end of a chapter.
+    ///
+    /// The pages are checked as they are ended, and the last one by `check_last_page`.
+    pub fn set_min_lines_per_page(&mut self, min_lines_per_page: Option<usize>) {
+        self.min_lines_per_page = min_lines_per_page;
+    }
+
+    /// Returns the number of lines on the current page, counted as the different baselines of
+    /// its glyphs.
+    fn lines_on_page(&self) -> usize {
+        let mut baselines = self
+            .layout
+            .iter()
+            .rev()
+            .take_while(|glyph| glyph.page == self.page_number)
+            .map(|glyph| glyph.y)
+            .collect::<Vec<_>>();
+        baselines.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        baselines.dedup();
+        baselines.len()
+    }
+
+    /// Warns if the current page holds some lines, but fewer than the minimum.
+    pub fn check_last_page(&mut self) {
+        let lines = self.lines_on_page();
+
+        if self
+            .min_lines_per_page
+            .map_or(false, |min| lines > 0 && lines < min)
+        {
+            self.warnings.push(LayoutWarning {
+                ty: WarningType::SparsePage,
+                content: format!("page {}", self.page_number),
+                overflow: None,
+            });
         }
     }
 
-    /// Writes content on the document.
-    pub fn write_content(&mut self, content: &str, font_config: &FontConfig, size: Pt) {
-        let en = Standard::from_embedded(Language::EnglishUS).unwrap();
+    /// Sets the marker drawn before the items of unordered lists.
+    pub fn set_list_marker(&mut self, list_marker: char) {
+        self.list_marker = list_marker;
+    }
 
-        for paragraph in content.split('\n') {
-            let ast = Ast::Text(paragraph.to_owned());
-            self.write_paragraph::<LatexJustifier>(&ast, font_config, size, &en);
-            self.new_line(size);
+    /// Returns whether some content didn't fit in the maximum number of pages.
+    pub fn clipped(&self) -> bool {
+        self.clipped
+    }
+
+    /// Returns the vertical position of the first baseline of the current page.
+    fn top(&self) -> Pt {
+        self.window.y + self.window.height - self.first_baseline
+    }
+
+    /// Returns the vertical position under which no line is written on the current page.
+    fn bottom(&self) -> Pt {
+        self.window.y + self.bottom_skip
+    }
+
+    /// Returns the window of a page, starting at 1.
+    fn window_of(&self, page: usize) -> Window {
+        match self.page_windows.len() {
+            0 => self.window,
+            len => self.page_windows[page.min(len) - 1],
         }
     }
 
-    /// Writes a paragraph on the document.
-    pub fn write_paragraph<J: Justifier>(
-        &mut self,
-        paragraph: &Ast,
-        font_config: &FontConfig,
-        size: Pt,
-        dict: &Standard,
-    ) {
-        let paragraph = itemize_ast(paragraph, font_config, size, dict, Pt(0.0));
-        let justified = J::justify(&paragraph, self.window.width);
+    /// Sets whether the page numbers and the running headers alternate between the outer
+    /// margins of the even and odd pages, like in a book.
+    pub fn set_two_sided(&mut self, two_sided: bool) {
+        self.two_sided = two_sided;
+    }
 
-        for line in justified {
-            for glyph in line {
-                self.layer.use_text(
-                    glyph.0.glyph.to_string(),
-                    Into::<Pt>::into(glyph.0.scale).0 as f64,
-                    (self.window.x + glyph.1).into(),
-                    self.cursor.1.into(),
-                    glyph.0.font.printpdf(),
-                );
+    /// Returns the horizontal position of something of some width on the outer side of a page:
+    /// at the left of the text on even pages and at its right on odd pages.
+    fn outer_x(&self, page: usize, width: Pt) -> Pt {
+        let window = self.window_of(page);
+        if page % 2 == 0 {
+            window.x
+        } else {
+            window.x + window.width - width
+        }
+    }
+
+    /// Returns the horizontal position of the number of a page.
+    fn page_number_x(&self, page: usize, label: &str) -> Pt {
+        let width = estimated_width(label, PAGE_NUMBER_SIZE);
+        let window = self.window_of(page);
+        if self.two_sided {
+            self.outer_x(page, width)
+        } else {
+            window.x + (window.width - width) / 2.0
+        }
+    }
+
+    /// Sets how the pages are numbered, or disables the page numbers.
+    pub fn set_page_numbering(&mut self, page_numbering: Option<PageNumbering>) -> Result<()> {
+        self.page_numbering = match page_numbering {
+            Some(page_numbering) => {
+                let font = self.document.add_builtin_font(BuiltinFont::TimesRoman)?;
+                Some((page_numbering, font))
             }
+            None => None,
+        };
 
-            self.new_line(size);
-            self.cursor.0 = self.window.x;
+        Ok(())
+    }
 
-            if self.cursor.1 <= size + self.window.y {
-                self.new_page();
+    /// Returns the number shown at the bottom of each page, the first page coming first.
+    pub fn page_labels(&self) -> Vec<Option<String>> {
+        let numbering = match &self.page_numbering {
+            Some((numbering, _)) => *numbering,
+            None => return vec![None; self.page_number],
+        };
+
+        let main_matter = self.main_matter.unwrap_or(1);
+
+        (1..=self.page_number)
+            .map(|page| {
+                Some(if page >= main_matter {
+                    (numbering.first + (page - main_matter) as i32).to_string()
+                } else if numbering.roman_front_matter {
+                    CounterFormat::LowerRoman.format(page)
+                } else {
+                    page.to_string()
+                })
+            })
+            .collect()
+    }
+
+    /// Draws the page numbers below the text, centered or in the outer margin.
+    fn draw_page_numbers(&self) {
+        let font = match &self.page_numbering {
+            Some((_, font)) => font,
+            None => return,
+        };
+
+        let labels = self.page_labels().into_iter().enumerate();
+        for (layer, (page, label)) in self.layers.iter().zip(labels) {
+            if let Some(label) = label {
+                let y = self.window_of(page + 1).y - PAGE_NUMBER_GAP;
+                let x = self.page_number_x(page + 1, &label);
+                layer.use_text(label, PAGE_NUMBER_SIZE, x.into(), y.into(), font);
             }
         }
     }
 
-    /// Writes a line in the document.
-    pub fn write_line(&mut self, words: &[&str], font: &Font, size: Pt, spacing: Pt) {
-        let size_f64 = Into::<Pt>::into(size).0 as f64;
-        let mut current_width = self.window.x;
+    /// Registers a function that draws on each page, e.g. for stamps or custom decorations.
+    ///
+    /// The function is called with the layer and the number of each page, in order, once the
+    /// whole document is laid out, after the running headers and the page numbers are drawn.
+    pub fn on_page<F: FnMut(&PdfLayerReference, usize) + 'static>(&mut self, hook: F) {
+        self.page_hooks.push(Box::new(hook));
+    }
 
-        for word in words {
-            let width = current_width;
-            let height = self.cursor.1;
+    /// Calls the functions registered to draw on each page.
+    fn run_page_hooks(&mut self) {
+        for (page, layer) in self.layers.iter().enumerate() {
+            for hook in &mut self.page_hooks {
+                hook(layer, page + 1);
+            }
+        }
+    }
 
-            self.layer.use_text(
-                word.to_owned(),
-                size_f64,
-                width.into(),
-                (height - size).into(),
-                font.printpdf(),
-            );
-            current_width += font.text_width(word, size) + spacing;
+    /// Sets whether the comments for the reviewers are attached to the document as pdf
+    /// annotations.
+    pub fn set_show_annotations(&mut self, show_annotations: bool) {
+        self.show_annotations = show_annotations;
+    }
+
+    /// Crops a page, starting at 1, so that it only shows a region, e.g. for a thumbnail.
+    ///
+    /// The media box of the page is set to the region, as well as its crop and trim boxes.
+    pub fn crop_page(&mut self, page: usize, region: Region) {
+        self.crops.push((page, region));
+    }
+
+    /// Returns the annotations attached to the document so far.
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// Sets how the lines are numbered in the margin, or disables the numbering.
+    pub fn set_line_numbering(&mut self, line_numbering: Option<LineNumbering>) {
+        self.line_numbering = line_numbering;
+    }
+
+    /// Numbers the line about to be written at the cursor, if the lines are numbered.
+    fn number_line(&mut self, font: &Font, size: Pt) {
+        let numbering = match self.line_numbering {
+            Some(numbering) => numbering,
+            None => return,
+        };
+
+        self.line_number += 1;
+        if self.line_number % numbering.every.max(1) != 0 {
+            return;
         }
 
-        self.new_line(size);
+        let text = self.line_number.to_string();
+        let size = size * LINE_NUMBER_SCALE;
+        let x = match numbering.side {
+            MarginSide::Left => self.window.x - LINE_NUMBER_GAP - font.text_width(&text, size),
+            MarginSide::Right => self.window.x + self.window.width + LINE_NUMBER_GAP,
+        };
+
+        self.draw(text, font, size, (x, self.cursor.1));
     }
 
-    /// Goes to the beginning of the next line.
-    pub fn new_line(&mut self, size: Pt) {
-        self.cursor.1 -= size;
+    /// Sets whether titles are prefixed with their section number.
+    ///
+    /// When disabled, the counters are left untouched and titles are rendered as they are.
+    pub fn set_number_headings(&mut self, number_headings: bool) {
+        self.number_headings = number_headings;
     }
 
-    /// Creates a new page and append it to the document.
-    pub fn new_page(&mut self) {
-        let page = self
-            .document
-            .add_page(self.page_size.0.into(), self.page_size.1.into(), "");
-        self.page = self.document.get_page(page.0);
-        self.layer = self.page.get_layer(page.1);
-        self.cursor.1 = self.window.height + self.window.y;
+    /// Sets the penalty for breaking a page inside a paragraph.
+    ///
+    /// When a paragraph doesn't fit in the space left on the current page, but would fit on an
+    /// empty page, the cost of moving it wholesale to the next page is the badness of the
+    /// current page, `10000 * b³` where `b` is the fraction of the text height left blank.
+    /// The paragraph is moved when this badness is lower than the penalty, so a penalty of 0
+    /// always breaks pages inside paragraphs, and a penalty of 10000 never does when possible.
+    pub fn set_page_break_penalty(&mut self, penalty: f64) {
+        self.page_break_penalty = penalty;
     }
 
-    /// Saves the document into a file.
-    pub fn save<P: AsRef<Path>>(self, path: P) {
-        let file = File::create(path.as_ref()).unwrap();
+    /// Sets the limits of the spaces between words, once the lines are justified.
+    pub fn set_word_spacing(&mut self, word_spacing: WordSpacing) {
+        self.word_spacing = word_spacing;
+    }
+
+    /// Sets the vertical spaces after the blocks of the document.
+    pub fn set_skips(&mut self, skips: Skips) {
+        self.skips = skips;
+    }
+
+    /// Sets how bad a line can be before it raises a warning.
+    pub fn set_tolerances(&mut self, tolerances: Tolerances) {
+        self.tolerances = tolerances;
+    }
+
+    /// Sets the height of the lines, from one baseline to the next.
+    ///
+    /// When unset, the lines are as high as the size of their font.
+    pub fn set_line_height(&mut self, line_height: Option<Pt>) {
+        self.line_height = line_height;
+    }
+
+    /// Sets the dictionary that hyphenates the words, e.g. one loaded from a file.
+    pub fn set_dictionary(&mut self, dictionary: Standard) {
+        self.dictionary = Rc::new(dictionary);
+    }
+
+    /// Sets the words whose hyphenation is given by the user rather than by the dictionary.
+    pub fn set_hyphenation_exceptions(&mut self, exceptions: HyphenationExceptions) {
+        self.hyphenation_exceptions = exceptions;
+    }
+
+    /// Sets the abbreviations, whose dots don't end a sentence and within which lines don't
+    /// break.
+    pub fn set_abbreviations(&mut self, abbreviations: Abbreviations) {
+        self.spacing.abbreviations = abbreviations;
+    }
+
+    /// Sets whether the whitespaces after the end of a sentence or of a clause are interword
+    /// spaces, like TeX's `\frenchspacing`.
+    pub fn set_french_spacing(&mut self, french_spacing: bool) {
+        self.spacing.french_spacing = french_spacing;
+    }
+
+    /// Sets the space factor of the whitespaces after colons and semicolons, from 1 for an
+    /// interword space to `SENTENCE_SPACE_FACTOR` for a sentence space.
+    pub fn set_clause_space_factor(&mut self, factor: f64) {
+        self.spacing.clause_space_factor = factor;
+    }
+
+    /// Sets whether the line breaks of the source are breakpoints that the lines prefer, rather
+    /// than plain whitespaces.
+    pub fn set_source_breaks(&mut self, source_breaks: bool) {
+        self.spacing.source_breaks = source_breaks;
+    }
+
+    /// Sets the stretch of the glue that ends the paragraphs, relative to the text width, like
+    /// TeX's `\parfillskip`, or none for an infinite one that leaves the last lines ragged.
+    pub fn set_parfill_stretch(&mut self, parfill_stretch: Option<f64>) {
+        self.parfill_stretch = parfill_stretch;
+    }
+
+    /// Sets the adjustments of the gap between some pairs of adjacent chars, which are added to
+    /// the advance of the first char of each pair.
+    pub fn set_kern_overrides(&mut self, kern_overrides: KernOverrides) {
+        self.kern_overrides = kern_overrides;
+    }
+
+    /// Sets the distance between two tab stops.
+    pub fn set_tab_width(&mut self, tab_width: Pt) {
+        self.tab_width = tab_width;
+    }
+
+    /// Sets the unit of the lengths in the warnings.
+    pub fn set_report_units(&mut self, report_units: ReportUnit) {
+        self.report_units = report_units;
+    }
+
+    /// Sets whether the words wider than a line are broken between their characters instead of
+    /// overflowing.
+    pub fn set_emergency_breaks(&mut self, emergency_breaks: bool) {
+        self.emergency_breaks = emergency_breaks;
+    }
+
+    /// Returns the height of the lines of a font size.
+    fn leading(&self, size: Pt) -> Pt {
+        self.line_height.unwrap_or(size)
+    }
+
+    /// Returns the counters of the document, as left by the last rendered title.
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    /// Returns the number of a labeled item, like an equation, if it has been rendered.
+    pub fn reference(&self, label: &str) -> Option<&str> {
+        self.labels.get(label).map(String::as_str)
+    }
+
+    /// Returns statistics on the lines written so far.
+    pub fn line_stats(&self) -> LineStats {
+        self.line_stats
+    }
+
+    /// Returns the warnings raised while laying out the document so far.
+    pub fn warnings(&self) -> &[LayoutWarning] {
+        &self.warnings
+    }
+
+    /// Returns a reference to the inner pdf document.
+    pub fn inner(&self) -> &PdfDocumentReference {
+        &self.document
+    }
+
+    /// Returns a mutable reference to the inner pdf document.
+    pub fn inner_mut(&mut self) -> &mut PdfDocumentReference {
+        &mut self.document
+    }
+
+    /// Renders an AST to the document.
+    pub fn render(&mut self, ast: &Ast, font_config: &FontConfig, size: Pt) {
+        let dict = Rc::clone(&self.dictionary);
+
+        match ast {
+            Ast::File(_, children) => {
+                for child in children {
+                    self.render(child, font_config, size);
+                }
+            }
+
+            Ast::Title { .. } if !self.number_headings => {
+                self.write_title(ast, font_config, size, &dict);
+            }
+
+            Ast::Title { level, children } => {
+                if self.counters.skips_level(*level as usize) {
+                    self.warnings.push(LayoutWarning {
+                        ty: WarningType::SkippedTitleLevel,
+                        content: children.iter().map(Ast::to_string).collect(),
+                        overflow: None,
+                    });
+                }
+
+                self.counters.increment(*level as usize);
+                let mut new_children = vec![Ast::Text(format!("{}  ", self.counters))];
+                new_children.extend_from_slice(children);
+                let new_ast = Ast::Title {
+                    level: *level,
+                    children: new_children,
+                };
+                self.write_title(&new_ast, font_config, size, &dict);
+            }
+
+            Ast::Paragraph(_) => {
+                self.write_paragraph::<LatexJustifier>(ast, font_config, size, &dict);
+                self.new_line(self.skips.paragraph);
+            }
+
+            Ast::AppendixMarker => self.counters.start_appendix(),
+
+            Ast::MainMatterMarker => {
+                self.clear_page();
+                self.main_matter.get_or_insert(self.page_number);
+            }
+
+            Ast::ClearPage => self.clear_page(),
+
+            Ast::UnorderedList(children) => {
+                for child in children {
+                    self.render(child, font_config, size);
+                }
+                self.new_line(self.skips.list);
+            }
+
+            Ast::CodeBlock {
+                content,
+                line_numbers,
+                ..
+            } => {
+                self.write_code(content, *line_numbers, font_config, size);
+                self.new_line(self.skips.paragraph);
+            }
+
+            Ast::DisplayMath { content, label } => {
+                self.write_display_math(content, label.as_deref(), font_config, size);
+                self.new_line(self.skips.paragraph);
+            }
+
+            Ast::Raw { backend, content } => self.write_raw(backend, content),
+
+            Ast::UnorderedListItem { level, children } => {
+                let indent = LIST_INDENT * f64::from(*level);
+                let new_ast = Ast::Paragraph(children.clone());
+                let marker = self.list_marker(font_config.regular);
+                self.write_hanging_paragraph::<LatexJustifier>(
+                    &new_ast,
+                    indent,
+                    Some(marker),
+                    font_config,
+                    size,
+                    &dict,
+                );
+                self.new_line(self.skips.list_item);
+            }
+
+            _ => (),
+        }
+    }
+
+    /// Writes a title on the document, and records it if it starts a section.
+    fn write_title(&mut self, title: &Ast, font_config: &FontConfig, size: Pt, dict: &Standard) {
+        self.check_title_width(title, font_config, size, dict);
+
+        let start = self.layout.len();
+        self.write_paragraph::<LatexJustifier>(title, font_config, size, dict);
+        self.new_line(self.leading(size));
+
+        if let (Ast::Title { level: 0, children }, Some(glyph)) = (title, self.layout.get(start)) {
+            let at_top = self.layout[..start]
+                .last()
+                .map_or(true, |previous| previous.page != glyph.page);
+
+            self.sections.push(Section {
+                page: glyph.page,
+                at_top,
+                title: children.iter().map(Ast::to_string).collect(),
+            });
+        }
+    }
+
+    /// Warns if a title is wider than the text when typeset on a single line.
+    fn check_title_width(
+        &mut self,
+        title: &Ast,
+        font_config: &FontConfig,
+        size: Pt,
+        dict: &Standard,
+    ) {
+        let paragraph = itemize_ast(
+            title,
+            font_config,
+            size,
+            dict,
+            &self.hyphenation_exceptions,
+            &self.spacing,
+            Pt(0.0),
+        );
+        let width = paragraph
+            .items
+            .iter()
+            .filter(|item| !matches!(item.content, Content::Penalty { .. }))
+            .fold(Pt(0.0), |width, item| width + item.width);
+
+        if width > self.window.width {
+            self.warnings.push(LayoutWarning {
+                ty: WarningType::TitleOverflow,
+                content: title
+                    .children()
+                    .into_iter()
+                    .flatten()
+                    .map(Ast::to_string)
+                    .collect(),
+                overflow: Some(self.report_units.format(width - self.window.width)),
+            });
+        }
+    }
+
+    /// Warns about a line that overflows the text or that is too loose, and records it in the
+    /// statistics.
+    fn check_line(&mut self, line: &JustifiedLine) {
+        self.line_stats.add(line);
+
+        let (ty, overflow) = if line.overflow > self.tolerances.overfull {
+            let overflow = self.report_units.format(line.overflow);
+            (WarningType::OverfullLine, Some(overflow))
+        } else if line.badness > self.tolerances.underfull_badness {
+            (WarningType::UnderfullLine, None)
+        } else {
+            return;
+        };
+
+        let mut content = String::new();
+        let mut end = None;
+
+        for (glyph, offset) in line.glyphs.iter().filter(|x| x.0.glyph != ANCHOR_GLYPH) {
+            if end.map_or(false, |end| *offset > end) {
+                content.push(' ');
+            }
+            content.push(glyph.glyph);
+            end = Some(*offset + glyph.font.char_width(glyph.glyph, glyph.scale));
+        }
+
+        self.warnings.push(LayoutWarning {
+            ty,
+            content,
+            overflow,
+        });
+    }
+
+    /// Writes content on the document.
+    pub fn write_content(&mut self, content: &str, font_config: &FontConfig, size: Pt) {
+        let dict = Rc::clone(&self.dictionary);
+
+        for paragraph in content.split('\n') {
+            let ast = Ast::Text(paragraph.to_owned());
+            self.write_paragraph::<LatexJustifier>(&ast, font_config, size, &dict);
+            self.new_line(self.leading(size));
+        }
+    }
+
+    /// Writes some raw content on the current page, if it is meant for the pdf backend.
+    ///
+    /// The content is made of pdf operators, in the coordinates of the page, and is written
+    /// between `q` and `Q` so that it can't change the state of the page. Content whose `q` and
+    /// `BT` operators aren't closed is dropped with a warning, as well as the content for the
+    /// other backends, silently.
+    pub fn write_raw(&mut self, backend: &str, content: &str) {
+        if backend != PDF_BACKEND || self.clipped {
+            return;
+        }
+
+        if !is_balanced(content) {
+            self.warnings.push(LayoutWarning {
+                ty: WarningType::UnbalancedRaw,
+                content: content.trim().to_string(),
+                overflow: None,
+            });
+            return;
+        }
+
+        self.layer.save_graphics_state();
+        self.layer
+            .add_operation(Operation::new(content.trim(), vec![]));
+        self.layer.restore_graphics_state();
+    }
+
+    /// Writes some code on the document, line by line, in the monospace font of a font config.
+    ///
+    /// The whitespace of each line is kept as it is, and the lines are numbered if asked.
+    pub fn write_code(
+        &mut self,
+        content: &str,
+        line_numbers: bool,
+        font_config: &FontConfig,
+        size: Pt,
+    ) {
+        let monospace = font_config.monospace();
+        let lines = content.lines().collect::<Vec<_>>();
+        let digits = lines.len().to_string().len();
+
+        for (index, line) in lines.iter().enumerate() {
+            let line = match line_numbers {
+                true => format!("{:>digits$}  {}", index + 1, line, digits = digits),
+                false => line.to_string(),
+            };
+
+            // The indentation of the line is written as the indentation of its paragraph, so
+            // that the rest of the line is indented as well if it wraps.
+            let code = line.trim_start();
+            let indent = line[..line.len() - code.len()]
+                .chars()
+                .map(|c| match c {
+                    '\t' => self.tab_width,
+                    c => monospace.regular.char_width(c, size),
+                })
+                .fold(Pt(0.0), |indent, width| indent + width);
+
+            // The lines of code are ragged, and never hyphenated nor stretched.
+            let paragraph = itemize_verbatim(code, &monospace, size, self.tab_width);
+            let width = self.window.width - indent;
+            let lines = RaggedJustifier::justify(&paragraph, width, self.word_spacing);
+            self.write_lines(lines, &[], indent, None, &monospace, size);
+        }
+    }
+
+    /// Writes some display math on its own line, centered, with its number at the right margin
+    /// if it is labeled.
+    ///
+    /// The math is only written as plain text for now.
+    pub fn write_display_math(
+        &mut self,
+        content: &str,
+        label: Option<&str>,
+        font_config: &FontConfig,
+        size: Pt,
+    ) {
+        let font = font_config.regular;
+
+        if self.cursor.1 <= size + self.bottom() {
+            self.new_page();
+        }
+
+        let x = self.window.x + (self.window.width - font.text_width(content, size)) / 2.0;
+        self.number_line(font, size);
+        self.draw(content.to_string(), font, size, (x, self.cursor.1));
+
+        if let Some(label) = label {
+            let number = self.counters.increment_named("equation").to_string();
+            let text = format!("({})", number);
+            let x = self.window.x + self.window.width - font.text_width(&text, size);
+            self.draw(text, font, size, (x, self.cursor.1));
+            self.labels.insert(label.to_string(), number);
+        }
+
+        self.new_line(self.leading(size));
+    }
+
+    /// Writes a paragraph on the document.
+    pub fn write_paragraph<J: Justifier>(
+        &mut self,
+        paragraph: &Ast,
+        font_config: &FontConfig,
+        size: Pt,
+        dict: &Standard,
+    ) {
+        self.write_hanging_paragraph::<J>(paragraph, Pt(0.0), None, font_config, size, dict);
+    }
+
+    /// Returns the marker of the items of unordered lists, or a hyphen if the font can't draw
+    /// it, with a warning the first time.
+    fn list_marker(&mut self, font: &Font) -> char {
+        if font.has_glyph(self.list_marker) {
+            return self.list_marker;
+        }
+
+        if !self
+            .warnings
+            .iter()
+            .any(|warning| warning.ty == WarningType::MissingListMarker)
+        {
+            self.warnings.push(LayoutWarning {
+                ty: WarningType::MissingListMarker,
+                content: format!("the list marker '{}'", self.list_marker),
+                overflow: None,
+            });
+        }
+
+        FALLBACK_LIST_MARKER
+    }
+
+    /// Returns the height of a paragraph set at a font size on lines of a width, without
+    /// drawing it.
+    pub fn measure_paragraph(
+        &self,
+        paragraph: &Ast,
+        font_config: &FontConfig,
+        size: Pt,
+        width: Pt,
+    ) -> Pt {
+        let dict = Rc::clone(&self.dictionary);
+        self.paragraph_height(paragraph, font_config, size, width, &dict)
+    }
+
+    /// Returns the largest font size, between a minimum and a maximum, at which a paragraph
+    /// fits in a box, e.g. for a caption.
+    ///
+    /// Only the size of the box matters. If the paragraph doesn't fit even at the minimum
+    /// size, the minimum size is returned anyway.
+    pub fn fit_text_in_box(
+        &self,
+        paragraph: &Ast,
+        font_config: &FontConfig,
+        rect: Region,
+        max_size: Pt,
+        min_size: Pt,
+    ) -> Pt {
+        let dict = Rc::clone(&self.dictionary);
+        let fits = |size| {
+            self.paragraph_height(paragraph, font_config, size, rect.width, &dict) <= rect.height
+        };
+
+        if fits(max_size) {
+            return max_size;
+        }
+
+        // The height of a paragraph grows with its size, so the size is found by bisection.
+        let (mut low, mut high) = (min_size, max_size);
+        while high - low > FIT_SIZE_PRECISION {
+            let size = (low + high) / 2.0;
+            if fits(size) {
+                low = size;
+            } else {
+                high = size;
+            }
+        }
+
+        low
+    }
+
+    /// Returns the height of a paragraph set at a font size on lines of a width.
+    fn paragraph_height(
+        &self,
+        paragraph: &Ast,
+        font_config: &FontConfig,
+        size: Pt,
+        width: Pt,
+        dict: &Standard,
+    ) -> Pt {
+        let (paragraph, _) = self.prepare_paragraph(paragraph, font_config, size, width, dict);
+        let lines = LatexJustifier::justify(&paragraph, width, self.word_spacing).len();
+        self.leading(size) * lines as f64
+    }
+
+    /// Turns a paragraph into the items that are broken into lines of a width, with the
+    /// kerning, the tab stops and the fill of the document.
+    ///
+    /// Returns the items and the words that had to be broken between their characters to fit
+    /// in a line.
+    fn prepare_paragraph<'a>(
+        &self,
+        paragraph: &Ast,
+        font_config: &FontConfig<'a>,
+        size: Pt,
+        width: Pt,
+        dict: &Standard,
+    ) -> (Paragraph<'a>, Vec<String>) {
+        let is_title = matches!(paragraph, Ast::Title { .. });
+        let mut paragraph = itemize_ast(
+            paragraph,
+            font_config,
+            size,
+            dict,
+            &self.hyphenation_exceptions,
+            &self.spacing,
+            Pt(0.0),
+        );
+
+        paragraph.kern(&self.kern_overrides);
+        let broken = match self.emergency_breaks {
+            true => paragraph.break_wide_words(width),
+            false => vec![],
+        };
+        paragraph.tab_width = Some(self.tab_width);
+        if let (Some(stretch), false) = (self.parfill_stretch, is_title) {
+            paragraph.set_fill(width * stretch);
+        }
+
+        (paragraph, broken)
+    }
+
+    /// Writes a paragraph on the document, shifted to the right by an indentation, with an
+    /// optional marker hanging at the start of its first line.
+    ///
+    /// The marker starts at the indentation, and the text starts right after the marker on the
+    /// first line as well as on the following ones.
+    pub fn write_hanging_paragraph<J: Justifier>(
+        &mut self,
+        paragraph: &Ast,
+        indent: Pt,
+        marker: Option<char>,
+        font_config: &FontConfig,
+        size: Pt,
+        dict: &Standard,
+    ) {
+        let text_start = text_start(indent, marker, font_config.regular, size);
+        let text_width = self.window.width - text_start;
+        let (paragraph, broken) =
+            self.prepare_paragraph(paragraph, font_config, size, text_width, dict);
+        for word in broken {
+            self.warnings.push(LayoutWarning {
+                ty: WarningType::EmergencyBreak,
+                content: word,
+                overflow: None,
+            });
+        }
+
+        let justified = J::justify(&paragraph, text_width, self.word_spacing);
+        self.write_lines(
+            justified,
+            &paragraph.annotations,
+            indent,
+            marker,
+            font_config,
+            size,
+        );
+    }
+
+    /// Writes the lines of a paragraph on the document, shifted to the right by an indentation,
+    /// with an optional marker hanging at the start of the first one.
+    fn write_lines(
+        &mut self,
+        lines: Vec<JustifiedLine>,
+        annotations: &[String],
+        indent: Pt,
+        marker: Option<char>,
+        font_config: &FontConfig,
+        size: Pt,
+    ) {
+        let text_start = text_start(indent, marker, font_config.regular, size);
+        let mut annotations = annotations.iter();
+
+        if self.moves_to_next_page(lines.len(), size) {
+            self.new_page();
+        }
+
+        for (i, line) in lines.into_iter().enumerate() {
+            self.check_line(&line);
+            let line = line.glyphs;
+
+            // Lines holding glyphs larger than the paragraph are lowered so that they don't
+            // overlap the previous line.
+            let height = line
+                .iter()
+                .map(|glyph| glyph.0.scale)
+                .fold(size, |max, scale| if scale > max { scale } else { max });
+            self.cursor.1 -= height - size;
+
+            self.number_line(font_config.regular, size);
+
+            if let (0, Some(marker)) = (i, marker) {
+                let position = (self.window.x + indent, self.cursor.1);
+                self.draw(marker.to_string(), font_config.regular, size, position);
+            }
+
+            for glyph in line {
+                let position = (self.window.x + text_start + glyph.1, self.cursor.1);
+
+                if glyph.0.glyph == ANCHOR_GLYPH {
+                    let shown = self.show_annotations && !self.clipped;
+                    if let (Some(note), true) = (annotations.next(), shown) {
+                        self.annotations.push(Annotation {
+                            page: self.page_number,
+                            x: position.0 .0,
+                            y: position.1 .0,
+                            note: note.clone(),
+                        });
+                    }
+                    continue;
+                }
+
+                let text = glyph.0.glyph.to_string();
+                self.draw(text, glyph.0.font, glyph.0.scale, position);
+            }
+
+            self.new_line(self.leading(size));
+            self.cursor.0 = self.window.x;
+
+            if self.cursor.1 <= size + self.bottom() {
+                self.new_page();
+            }
+        }
+    }
+
+    /// Returns the number of lines of a given size that fit on the page from a vertical position.
+    fn lines_below(&self, y: Pt, size: Pt) -> usize {
+        let leading = self.leading(size);
+        let mut lines = 1;
+        let mut y = y - leading;
+
+        while y > size + self.bottom() {
+            lines += 1;
+            y -= leading;
+        }
+
+        lines
+    }
+
+    /// Returns whether a paragraph of some lines should be moved wholesale to the next page
+    /// rather than being broken across pages.
+    fn moves_to_next_page(&self, lines: usize, size: Pt) -> bool {
+        let top = self.top();
+
+        if self.cursor.1 >= top
+            || lines <= self.lines_below(self.cursor.1, size)
+            || lines > self.lines_below(top, size)
+        {
+            return false;
+        }
+
+        let blank = (self.cursor.1 - self.bottom()).0 / self.window.height.0;
+        let badness = (MAX_PAGE_BADNESS * blank.powi(3)).min(MAX_PAGE_BADNESS);
+
+        self.page_break_penalty > badness
+    }
+
+    /// Writes a line in the document.
+    pub fn write_line(&mut self, words: &[&str], font: &Font, size: Pt, spacing: Pt) {
+        let mut current_width = self.window.x;
+
+        for word in words {
+            let position = (current_width, self.cursor.1 - size);
+            self.draw(word.to_string(), font, size, position);
+            current_width += font.text_width(word, size) + spacing;
+        }
+
+        self.new_line(self.leading(size));
+    }
+
+    /// Draws some text at a position of the current page, and records it in the layout.
+    fn draw(&mut self, text: String, font: &Font, size: Pt, position: (Pt, Pt)) {
+        if self.clipped {
+            return;
+        }
+
+        let synthetic = font.synthetic();
+        let shear = if synthetic.italic {
+            SYNTHETIC_SLANT
+        } else {
+            0.0
+        };
+        let stroke = if synthetic.bold {
+            size.0 * SYNTHETIC_STROKE
+        } else {
+            0.0
+        };
+
+        self.layout.push(PositionedGlyph {
+            page: self.page_number,
+            x: position.0 .0,
+            y: position.1 .0,
+            glyph: text.clone(),
+            font: font.name(),
+            size: size.0,
+            shear,
+            stroke,
+        });
+
+        if !synthetic.bold && !synthetic.italic {
+            self.layer.use_text(
+                text,
+                size.0,
+                position.0.into(),
+                position.1.into(),
+                font.printpdf(),
+            );
+            return;
+        }
+
+        // The text matrix both places and shears the text, and the stroke of the bold must not
+        // leak to the text drawn afterwards.
+        self.layer.save_graphics_state();
+        self.layer.begin_text_section();
+        self.layer.set_font(font.printpdf(), size.0);
+        self.layer.add_operation(Operation::new(
+            "Tm",
+            [1.0, 0.0, shear, 1.0, position.0 .0, position.1 .0]
+                .iter()
+                .map(|x| Object::Real(*x))
+                .collect(),
+        ));
+        if synthetic.bold {
+            let color = self.text_color.unwrap_or_else(|| Color::grey(0.0));
+            self.layer.set_outline_color(color.to_pdf(self.color_space));
+            self.layer.set_outline_thickness(stroke);
+            self.layer
+                .set_text_rendering_mode(TextRenderingMode::FillStroke);
+        }
+        self.layer.write_text(text, font.printpdf());
+        self.layer.end_text_section();
+        self.layer.restore_graphics_state();
+    }
+
+    /// Returns the glyphs drawn so far, with their positions.
+    pub fn layout(&self) -> &[PositionedGlyph] {
+        &self.layout
+    }
+
+    /// Writes the positioned layout of the document as JSON into a file.
+    ///
+    /// See the `dump` module for the schema of the file. The positions and the outlines of the
+    /// glyphs are written in the report units, while the font sizes stay in pt.
+    pub fn dump_layout<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let units = self.report_units;
+        let layout = self
+            .layout
+            .iter()
+            .map(|glyph| PositionedGlyph {
+                x: units.convert(Pt(glyph.x)),
+                y: units.convert(Pt(glyph.y)),
+                stroke: units.convert(Pt(glyph.stroke)),
+                ..glyph.clone()
+            })
+            .collect::<Vec<_>>();
+
+        let file = File::create(path.as_ref())?;
+        serde_json::to_writer(BufWriter::new(file), &layout)?;
+        Ok(())
+    }
+
+    /// Goes to the beginning of the next line.
+    pub fn new_line(&mut self, size: Pt) {
+        self.cursor.1 -= size;
+    }
+
+    /// Creates a new page and append it to the document.
+    ///
+    /// Past the maximum number of pages, no page is added, and the content that follows is clipped.
+    pub fn new_page(&mut self) {
+        self.check_last_page();
+
+        if self.max_pages.map_or(false, |max| self.page_number >= max) {
+            if !self.clipped {
+                self.clipped = true;
+                self.warnings.push(LayoutWarning {
+                    ty: WarningType::ContentClipped,
+                    content: format!("the content after page {}", self.page_number),
+                    overflow: None,
+                });
+            }
+
+            // The rest of the content is laid out on a page that is never drawn.
+            self.cursor = (self.window.x, self.top());
+            return;
+        }
+
+        let page = self.document.add_page(
+            self.page_size.0.into(),
+            self.page_size.1.into(),
+            layer_name(self.page_number + 1),
+        );
+        self.page = self.document.get_page(page.0);
+        self.layer = self.page.get_layer(page.1);
+        self.layers.push(self.layer.clone());
+        self.page_number += 1;
+        self.window = self.window_of(self.page_number);
+        self.cursor.0 = self.window.x;
+        self.stamp_watermark();
+        self.apply_text_color();
+
+        if self
+            .line_numbering
+            .map_or(false, |numbering| numbering.per_page)
+        {
+            self.line_number = 0;
+        }
+        self.cursor.1 = self.top();
+    }
+
+    /// Ends the current page, so that what follows starts on a new one, like `\clearpage`.
+    ///
+    /// If the current page is still empty, it is kept instead of adding another one.
+    pub fn clear_page(&mut self) {
+        if self.cursor.1 < self.top() {
+            self.new_page();
+        }
+    }
+
+    /// Inserts a blank page, and goes to the page after it.
+    ///
+    /// If the current page is still empty, it is left blank instead of adding another one.
+    pub fn insert_blank_page(&mut self) {
+        self.clear_page();
+        self.new_page();
+    }
+
+    /// Appends the pages of an existing pdf file after the pages of the document, e.g. an
+    /// appendix produced by another tool.
+    ///
+    /// The file is read right away, but its pages are only merged when the document is saved, so
+    /// they come after all the pages of the document, whatever is rendered after this call.
+    pub fn append_pdf<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let pdf = lopdf::Document::load(path)?;
+        self.appended.push(pdf);
+        Ok(())
+    }
+
+    /// Returns the number of pages of the document, the appended pdf files included.
+    pub fn page_count(&self) -> usize {
+        let appended = self
+            .appended
+            .iter()
+            .map(|pdf| pdf.get_pages().len())
+            .sum::<usize>();
+        self.page_number + appended
+    }
+
+    /// Saves the document into a file.
+    pub fn save<P: AsRef<Path>>(self, path: P) {
+        let bytes = self.to_bytes().unwrap();
+        let file = File::create(path.as_ref()).unwrap();
         let mut writer = BufWriter::new(file);
-        self.document.save(&mut writer).unwrap();
+        writer.write_all(&bytes).unwrap();
+    }
+
+    /// Renders the document into the bytes of a pdf file.
+    pub fn to_bytes(mut self) -> Result<Vec<u8>> {
+        self.draw_running_headers();
+        self.draw_page_numbers();
+        self.run_page_hooks();
+
+        if !self.reproducible {
+            let bytes = self.document.save_to_bytes()?;
+            let bytes = update_pages(bytes, &self.annotations, &self.crops);
+            return append_pdfs(bytes, &self.appended);
+        }
+
+        let date = build_date(true);
+
+        // The XMP metadata contains random identifiers that we can't control, so we don't
+        // generate them.
+        let conformance = CustomPdfConformance {
+            requires_xmp_metadata: false,
+            requires_icc_profile: false,
+            ..Default::default()
+        };
+
+        let mut bytes = self
+            .document
+            .with_creation_date(date)
+            .with_mod_date(date)
+            .with_metadata_date(date)
+            .with_conformance(PdfConformance::Custom(conformance))
+            .save_to_bytes()?;
+
+        // The identifiers of the trailer are random as well, but they are located after the
+        // content, so we can replace them with a hash of the content.
+        let trailer = bytes.windows(7).rposition(|w| w == b"trailer");
+        if let Some(trailer) = trailer {
+            let mut hasher = DefaultHasher::new();
+            bytes[..trailer].hash(&mut hasher);
+            let hash = hasher.finish();
+            let id = format!("{:016X}{:016X}", hash, hash.rotate_left(32));
+
+            // The identifiers are made of a permanent identifier and an instance identifier.
+            let mut start = find(&bytes, b"/ID", trailer);
+            for _ in 0..2 {
+                let open = match start.and_then(|i| find(&bytes, b"(", i)) {
+                    Some(open) => open,
+                    None => break,
+                };
+
+                let close = open + 1 + id.len();
+                if bytes.get(close) != Some(&b')') {
+                    break;
+                }
+
+                bytes[open + 1..close].copy_from_slice(id.as_bytes());
+                start = Some(close);
+            }
+        }
+
+        let bytes = update_pages(bytes, &self.annotations, &self.crops);
+        append_pdfs(bytes, &self.appended)
+    }
+}
+
+/// Returns the approximate width of a text drawn with a builtin Times font.
+///
+/// The digits of Times are half an em wide, which is close enough for the other chars as
+/// well, since their widths aren't available for the builtin fonts.
+fn estimated_width(text: &str, size: f64) -> Pt {
+    Pt(text.chars().count() as f64 * size / 2.0)
+}
+
+/// Returns where the text of a paragraph starts, after its indentation and the marker hanging
+/// at the start of its first line.
+fn text_start(indent: Pt, marker: Option<char>, font: &Font, size: Pt) -> Pt {
+    match marker {
+        Some(marker) => indent + font.char_width(marker, size) + font.char_width(' ', size),
+        None => indent,
+    }
+}
+
+/// Returns the name of the layer of a page, starting at 1, which pdf readers show in their
+/// list of layers.
+fn layer_name(page: usize) -> String {
+    format!("Page {}", page)
+}
+
+/// Returns the date at which a document is built.
+///
+/// When the build must be reproducible, the date is read from the `SOURCE_DATE_EPOCH`
+/// environment variable, and defaults to the unix epoch.
+pub fn build_date(reproducible: bool) -> OffsetDateTime {
+    if !reproducible {
+        return OffsetDateTime::now_utc();
+    }
+
+    let timestamp = env::var(SOURCE_DATE_EPOCH)
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(0);
+    OffsetDateTime::from_unix_timestamp(timestamp)
+}
+
+/// The attributes of a page that it may inherit from the nodes of the page tree above it.
+const INHERITABLE_PAGE_ATTRIBUTES: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Appends the pages of some pdf files after the pages of a pdf file.
+///
+/// The objects of each appended file are renumbered after the ones of the file, and its pages
+/// are moved to the root of the page tree of the file, with the attributes they inherited from
+/// their own page tree.
+fn append_pdfs(bytes: Vec<u8>, appended: &[lopdf::Document]) -> Result<Vec<u8>> {
+    if appended.is_empty() {
+        return Ok(bytes);
+    }
+
+    let mut document = lopdf::Document::load_mem(&bytes)?;
+    let root = document.catalog()?.get(b"Pages")?.as_reference()?;
+
+    for pdf in appended {
+        let mut pdf = pdf.clone();
+        pdf.renumber_objects_with(document.max_id + 1);
+        document.max_id = pdf.max_id;
+
+        let mut pages = vec![];
+        for page in pdf.page_iter() {
+            let mut dictionary = pdf.get_dictionary(page)?.clone();
+            for key in INHERITABLE_PAGE_ATTRIBUTES.iter() {
+                if let (false, Some(value)) = (dictionary.has(key), inherited(&pdf, page, key)) {
+                    dictionary.set(key.to_vec(), value);
+                }
+            }
+            dictionary.set("Parent", root);
+            pages.push((page, dictionary));
+        }
+
+        // The pages are drawn on layers, which must be listed with the ones of the document to
+        // be shown by the readers.
+        let layers = layers(&pdf);
+        document.objects.extend(pdf.objects);
+        add_layers(&mut document, &layers);
+
+        for (page, dictionary) in pages {
+            document
+                .objects
+                .insert(page, Object::Dictionary(dictionary));
+
+            let tree = document.get_object_mut(root)?.as_dict_mut()?;
+            tree.get_mut(b"Kids")?
+                .as_array_mut()?
+                .push(Object::Reference(page));
+            let count = tree.get(b"Count")?.as_i64()?;
+            tree.set("Count", count + 1);
+        }
+    }
+
+    // The catalogs and the page trees of the appended files are no longer referenced.
+    document.prune_objects();
+
+    let mut bytes = vec![];
+    document.save_to(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Returns the layers of a pdf file, listed in the optional content properties of its catalog.
+fn layers(pdf: &lopdf::Document) -> Vec<Object> {
+    pdf.catalog()
+        .and_then(|catalog| catalog.get(b"OCProperties"))
+        .and_then(Object::as_dict)
+        .and_then(|properties| properties.get(b"OCGs"))
+        .and_then(Object::as_array)
+        .ok()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Adds some layers to the layers of a pdf file, visible and in the order shown to the user.
+fn add_layers(pdf: &mut lopdf::Document, layers: &[Object]) -> Option<()> {
+    let root = pdf.trailer.get(b"Root").ok()?.as_reference().ok()?;
+    let catalog = pdf.get_object_mut(root).ok()?.as_dict_mut().ok()?;
+    let properties = catalog.get_mut(b"OCProperties").ok()?.as_dict_mut().ok()?;
+    let all = properties.get_mut(b"OCGs").ok()?.as_array_mut().ok()?;
+    all.extend_from_slice(layers);
+
+    let default = properties.get_mut(b"D").ok()?.as_dict_mut().ok()?;
+    for key in &[b"Order" as &[u8], b"ON"] {
+        if let Ok(array) = default.get_mut(key).and_then(Object::as_array_mut) {
+            array.extend_from_slice(layers);
+        }
+    }
+
+    Some(())
+}
+
+/// Returns the value of an attribute that a page of a pdf file inherits from its ancestors in
+/// the page tree, if any of them has it.
+fn inherited(pdf: &lopdf::Document, page: lopdf::ObjectId, key: &[u8]) -> Option<Object> {
+    let mut node = pdf.get_dictionary(page).ok()?;
+    loop {
+        let parent = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+        node = pdf.get_dictionary(parent).ok()?;
+        if let Ok(value) = node.get(key) {
+            return Some(value.clone());
+        }
+    }
+}
+
+/// Attaches annotations to the pages of a pdf file, and crops some of its pages.
+///
+/// Printpdf can't write annotations nor boxes other than the whole page, so they are appended
+/// to the file as an incremental update, which adds the annotations and replaces the pages they
+/// are on or that are cropped.
+fn update_pages(
+    mut bytes: Vec<u8>,
+    annotations: &[Annotation],
+    crops: &[(usize, Region)],
+) -> Vec<u8> {
+    let trailer = match bytes.windows(7).rposition(|w| w == b"trailer") {
+        Some(trailer) if !annotations.is_empty() || !crops.is_empty() => trailer,
+        _ => return bytes,
+    };
+
+    let (size, dictionary) = match (
+        find(&bytes, b"/Size ", trailer).and_then(|i| read_number(&bytes, i + 6)),
+        find(&bytes, b"<<", trailer).zip(find(&bytes, b"startxref", trailer)),
+    ) {
+        (Some(size), Some((start, end))) => (size, (start, end)),
+        _ => return bytes,
+    };
+    let prev = match read_number(&bytes, dictionary.1 + 10) {
+        Some(prev) => prev,
+        None => return bytes,
+    };
+    let dictionary = String::from_utf8_lossy(&bytes[dictionary.0..dictionary.1])
+        .trim()
+        .to_string();
+
+    // The pages are listed in order in the kids of the root of the page tree.
+    let kids = match find(&bytes, b"/Kids[", 0) {
+        Some(kids) => kids + 6,
+        None => return bytes,
+    };
+    let pages = match find(&bytes, b"]", kids) {
+        Some(end) => String::from_utf8_lossy(&bytes[kids..end])
+            .split(" R")
+            .filter_map(|x| x.split_whitespace().next()?.parse::<usize>().ok())
+            .collect::<Vec<_>>(),
+        None => return bytes,
+    };
+
+    let mut objects = vec![];
+    let mut next_id = size;
+
+    for (index, page) in pages.iter().enumerate() {
+        let mut ids = vec![];
+
+        for annotation in annotations.iter().filter(|x| x.page == index + 1) {
+            let contents = annotation
+                .note
+                .encode_utf16()
+                .fold(String::from("FEFF"), |acc, x| acc + &format!("{:04X}", x));
+
+            let object = format!(
+                "<</Type/Annot/Subtype/Text/Rect[{:.2} {:.2} {:.2} {:.2}]/Contents<{}>/Name/Comment>>",
+                annotation.x,
+                annotation.y,
+                annotation.x + ANNOTATION_SIZE,
+                annotation.y + ANNOTATION_SIZE,
+                contents
+            );
+            ids.push(next_id);
+            objects.push((next_id, object));
+            next_id += 1;
+        }
+
+        let crop = crops.iter().rev().find(|(p, _)| *p == index + 1);
+        if ids.is_empty() && crop.is_none() {
+            continue;
+        }
+
+        let header = format!("\n{} 0 obj", page);
+        let (start, end) = match find(&bytes, header.as_bytes(), 0)
+            .and_then(|i| Some((i + header.len(), find(&bytes, b"endobj", i)?)))
+        {
+            Some(bounds) => bounds,
+            None => return bytes,
+        };
+
+        let mut object = String::from_utf8_lossy(&bytes[start..end])
+            .trim()
+            .to_string();
+
+        if !ids.is_empty() {
+            let refs = ids.iter().map(|id| format!("{} 0 R", id));
+            let annots = format!("<</Annots[{}]", refs.collect::<Vec<_>>().join(" "));
+            object = object.replacen("<<", &annots, 1);
+        }
+
+        if let Some((_, region)) = crop {
+            let bounds = format!(
+                "{:.2} {:.2} {:.2} {:.2}",
+                region.x.0,
+                region.y.0,
+                region.x.0 + region.width.0,
+                region.y.0 + region.height.0
+            );
+
+            for name in ["/MediaBox[", "/TrimBox[", "/CropBox["] {
+                if let Some(start) = object.find(name).map(|i| i + name.len()) {
+                    if let Some(len) = object[start..].find(']') {
+                        object.replace_range(start..start + len, &bounds);
+                    }
+                }
+            }
+        }
+
+        objects.push((*page, object));
+    }
+
+    let mut update = String::new();
+    let mut xref = String::from("xref\n");
+
+    for (id, object) in &objects {
+        let offset = bytes.len() + 1 + update.len();
+        update.push_str(&format!("\n{} 0 obj\n{}\nendobj", id, object));
+        xref.push_str(&format!("{} 1\n{:010} 00000 n \n", id, offset));
+    }
+
+    let dictionary = dictionary.replacen(
+        &format!("/Size {}", size),
+        &format!("/Size {}/Prev {}", next_id, prev),
+        1,
+    );
+    let startxref = bytes.len() + 1 + update.len();
+    update.push_str(&format!(
+        "\n{}trailer\n{}\nstartxref\n{}\n%%EOF",
+        xref, dictionary, startxref
+    ));
+
+    bytes.extend_from_slice(update.as_bytes());
+    bytes
+}
+
+/// Reads the decimal number written at some offset of some bytes.
+fn read_number(bytes: &[u8], offset: usize) -> Option<usize> {
+    let digits = bytes
+        .get(offset..)?
+        .iter()
+        .take_while(|x| x.is_ascii_digit())
+        .map(|x| *x as char)
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+/// Finds the first occurence of a pattern in some bytes after an offset.
+fn find(bytes: &[u8], pattern: &[u8], offset: usize) -> Option<usize> {
+    bytes
+        .get(offset..)?
+        .windows(pattern.len())
+        .position(|w| w == pattern)
+        .map(|i| i + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::env;
+    use std::fs::{self, File};
+    use std::rc::Rc;
+
+    use printpdf::{Mm, Pt};
+    use spandex_hyphenation::load::Load;
+    use spandex_hyphenation::{Language, Standard};
+
+    use crate::document::color::{Color, ColorSpace};
+    use crate::document::configuration::{Config, LineHeight, PageWindow, RunningHeader};
+    use crate::document::dump::PositionedGlyph;
+    use crate::document::{find, layers, LineStats, Region, SYNTHETIC_SLANT, WATERMARK};
+    use crate::layout::constants::{
+        FALLBACK_LIST_MARKER, LIST_INDENT, LIST_MARKER, MAX_PAGE_BADNESS,
+    };
+    use crate::layout::paragraphs::engine::WordSpacing;
+    use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+    use crate::layout::paragraphs::justification::{Justifier, LatexJustifier};
+    use crate::layout::paragraphs::kerning::KernOverrides;
+    use crate::layout::paragraphs::spacing::Spacing;
+    use crate::layout::paragraphs::utils::ast::itemize_ast;
+    use crate::parser::ast::Ast;
+    use crate::parser::warning::WarningType;
+    use crate::units::ReportUnit;
+    use crate::Result;
+
+    fn build(config: &Config) -> Result<Vec<u8>> {
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+        document.render(&ast, &font_config, Pt(10.0));
+        document.to_bytes()
+    }
+
+    #[test]
+    fn test_appendix_numbering() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+
+        let title = |level| Ast::Title {
+            level,
+            children: vec![Ast::Text("Title".into())],
+        };
+
+        document.render(&title(0), &font_config, Pt(10.0));
+        assert_eq!(document.counters.to_string(), "1");
+
+        let mut numbers = vec![];
+        document.render(&Ast::AppendixMarker, &font_config, Pt(10.0));
+        for level in &[0, 1, 1, 0] {
+            document.render(&title(*level), &font_config, Pt(10.0));
+            numbers.push(document.counters.to_string());
+        }
+
+        assert_eq!(numbers, vec!["A", "A.1", "A.2", "B"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reproducible() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.reproducible = true;
+
+        assert_eq!(build(&config)?, build(&config)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_layout() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+        document.render(&ast, &font_config, Pt(10.0));
+
+        let path = env::temp_dir().join("spandex-test-dump-layout.json");
+        document.dump_layout(&path)?;
+        let json: serde_json::Value = serde_json::from_reader(File::open(&path)?)?;
+        fs::remove_file(&path)?;
+
+        let glyphs = json.as_array().unwrap();
+        assert_eq!(glyphs.len(), 10);
+        assert_eq!(glyphs[0]["glyph"], "H");
+        assert_eq!(glyphs[0]["page"], 1);
+        assert_eq!(glyphs[0]["size"], 10.0);
+
+        // The positions are written in the report units.
+        let mut config = Config::with_title("Test");
+        config.report_units = ReportUnit::Mm;
+        let (mut document, font_manager) = config.init()?;
+        document.render(&ast, &font_manager.default_config(), Pt(10.0));
+
+        document.dump_layout(&path)?;
+        let json: serde_json::Value = serde_json::from_reader(File::open(&path)?)?;
+        fs::remove_file(&path)?;
+
+        let x = glyphs[0]["x"].as_f64().unwrap();
+        let mm = json[0]["x"].as_f64().unwrap();
+        assert!((mm - ReportUnit::Mm.convert(Pt(x))).abs() < 1e-6);
+        assert_eq!(json[0]["size"], 10.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unnumbered_headings() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        document.set_number_headings(false);
+
+        let title = Ast::Title {
+            level: 0,
+            children: vec![Ast::Text("Title".into())],
+        };
+        document.render(&title, &font_config, Pt(10.0));
+
+        let text = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.glyph.as_str())
+            .collect::<String>();
+        assert_eq!(text, "Title");
+        assert_eq!(document.counters.counter(0), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_draft_watermark() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.draft = true;
+
+        let (mut document, _) = config.init()?;
+        document.new_page();
+        let bytes = document.to_bytes()?;
+
+        // Text in builtin fonts is written in hexadecimal.
+        let watermark = WATERMARK
+            .bytes()
+            .map(|b| format!("{:02X}", b))
+            .collect::<String>();
+        let watermark = format!("<{}>", watermark);
+
+        let mut stamps = 0;
+        let mut offset = 0;
+        while let Some(i) = find(&bytes, watermark.as_bytes(), offset) {
+            stamps += 1;
+            offset = i + 1;
+        }
+        assert_eq!(stamps, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let raw = |backend: &str, content: &str| Ast::Raw {
+            backend: backend.into(),
+            content: content.into(),
+        };
+        document.render(&raw("pdf", "0.25 0.5 0.75 RG\n"), &font_config, Pt(10.0));
+        document.render(&raw("svg", "<rect width=\"1\"/>\n"), &font_config, Pt(10.0));
+        document.render(&raw("pdf", "q 0.75 0.5 0.25 RG\n"), &font_config, Pt(10.0));
+
+        let warnings = document.warnings().to_vec();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].ty, WarningType::UnbalancedRaw);
+
+        // The content for the pdf is written verbatim, and the one for other backends dropped.
+        let bytes = document.to_bytes()?;
+        assert!(find(&bytes, b"q\n0.25 0.5 0.75 RG\nQ", 0).is_some());
+        assert!(find(&bytes, b"<rect", 0).is_none());
+        assert!(find(&bytes, b"0.75 0.5 0.25 RG", 0).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmyk_text_color() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.color_space = ColorSpace::Cmyk;
+        config.text_color = Some(Color::Rgb {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+        });
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+        document.render(&ast, &font_config, Pt(10.0));
+        let bytes = document.to_bytes()?;
+
+        assert!(find(&bytes, b"0.00 1.00 1.00 0.00 k", 0).is_some());
+        assert!(find(&bytes, b" rg", 0).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_numbers() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.line_numbers = true;
+        config.line_numbering.every = 2;
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let text = "Lorem ipsum dolor sit amet. ".repeat(20);
+        let ast = Ast::Paragraph(vec![Ast::Text(text)]);
+        document.render(&ast, &font_config, Pt(10.0));
+
+        let (numbers, glyphs): (Vec<_>, Vec<_>) = document
+            .layout()
+            .iter()
+            .partition(|glyph| glyph.x < document.window.x.0);
+
+        let mut baselines = glyphs.iter().map(|glyph| glyph.y).collect::<Vec<_>>();
+        baselines.dedup();
+
+        let expected = baselines
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (i + 1) % 2 == 0)
+            .map(|(i, y)| ((i + 1).to_string(), *y))
+            .collect::<Vec<_>>();
+        let numbers = numbers
+            .iter()
+            .map(|glyph| (glyph.glyph.clone(), glyph.y))
+            .collect::<Vec<_>>();
+
+        assert!(baselines.len() >= 4);
+        assert_eq!(numbers, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_height() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+
+        let mut advance = |ast: &Ast| {
+            let before = document.cursor.1;
+            document.write_paragraph::<LatexJustifier>(ast, &font_config, Pt(10.0), &en);
+            (before - document.cursor.1).0
+        };
+
+        let plain = advance(&Ast::Paragraph(vec![Ast::Text("Hello".into())]));
+        let title = advance(&Ast::Title {
+            level: 0,
+            children: vec![Ast::Text("Hello".into())],
+        });
+
+        assert!((plain - 10.0).abs() < 1e-9);
+        assert!(title > plain);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_height_ratio() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.line_height = Some(LineHeight::Ratio(1.5));
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+
+        let text = "Lorem ipsum dolor sit amet. ".repeat(10);
+        let ast = Ast::Paragraph(vec![Ast::Text(text)]);
+        document.write_paragraph::<LatexJustifier>(&ast, &font_config, Pt(10.0), &en);
+
+        let mut baselines: Vec<f64> = document.layout().iter().map(|glyph| glyph.y).collect();
+        baselines.dedup();
+
+        assert!(baselines.len() > 1);
+        for pair in baselines.windows(2) {
+            assert!((pair[0] - pair[1] - 15.0).abs() < 1e-9);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_title_overflow() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+
+        let title = |text: &str| Ast::Title {
+            level: 0,
+            children: vec![Ast::Text(text.into())],
+        };
+
+        document.render(&title("Short"), &font_config, Pt(10.0));
+        assert!(document.warnings().is_empty());
+
+        let long = "A title that goes on and on and on and on and on and on";
+        document.render(&title(long), &font_config, Pt(10.0));
+        let warnings = document.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].ty, WarningType::TitleOverflow);
+        assert!(warnings[0].content.ends_with(long));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tab_stops() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.typesetting.tab_width = Pt(50.0);
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let paragraph = Ast::Paragraph(vec![Ast::Text("a\tb\tlong enough text\tc".into())]);
+        document.render(&paragraph, &font_config, Pt(10.0));
+
+        // Each tab advances to the next multiple of the tab width, even past a longer text.
+        let x = |glyph: &str| {
+            let glyph = document.layout().iter().find(|x| x.glyph == glyph).unwrap();
+            glyph.x - config.left_margin.0
+        };
+        assert!((x("b") - 50.0).abs() < 1e-6);
+        assert!((x("l") - 100.0).abs() < 1e-6);
+        assert!(x("c") > 150.0);
+        assert!((x("c") % 50.0).abs() < 1e-6);
+
+        // The tab stops are measured from the beginning of the line of the tab, not of the
+        // paragraph.
+        let (mut document, font_manager) = config.init()?;
+        let paragraph = Ast::Paragraph(vec![Ast::Text(
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor \
+             incididunt ut labore et dolore magna aliqua. X\tY"
+                .into(),
+        )]);
+        document.render(&paragraph, &font_manager.default_config(), Pt(10.0));
+
+        let glyph = |glyph: &str| document.layout().iter().find(|x| x.glyph == glyph).unwrap();
+        assert!(glyph("Y").y < glyph("L").y);
+        let x = glyph("Y").x - config.left_margin.0;
+        assert!((x / 50.0 - (x / 50.0).round()).abs() < 1e-6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_units() -> Result<()> {
+        let title = Ast::Title {
+            level: 0,
+            children: vec![Ast::Text(
+                "A title that goes on and on and on and on and on".into(),
+            )],
+        };
+
+        let overflow = |units| -> Result<String> {
+            let mut config = Config::with_title("Test");
+            config.report_units = units;
+
+            let (mut document, font_manager) = config.init()?;
+            document.render(&title, &font_manager.default_config(), Pt(10.0));
+            Ok(document.warnings()[0].overflow.clone().unwrap())
+        };
+
+        let pt = overflow(ReportUnit::Pt)?;
+        let mm = overflow(ReportUnit::Mm)?;
+        assert!(pt.ends_with("pt"));
+        assert!(mm.ends_with("mm"));
+
+        let pt = pt.trim_end_matches("pt").parse::<f64>().unwrap();
+        let mm = mm.trim_end_matches("mm").parse::<f64>().unwrap();
+        assert!((mm - pt * 25.4 / 72.0).abs() < 0.01);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_tolerances() -> Result<()> {
+        // Spaces that can't shrink make some lines overflow, and others loose.
+        let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod \
+                    tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim \
+                    veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea \
+                    commodo consequat. Duis aute irure dolor in reprehenderit in voluptate.";
+        let paragraph = Ast::Paragraph(vec![Ast::Text(text.into())]);
+        let word_spacing = WordSpacing {
+            min: Pt(10.0),
+            max: Pt(20.0),
+        };
+
+        let warnings = |overfull, underfull_badness| -> Result<Vec<WarningType>> {
+            let mut config = Config::with_title("Test");
+            config.typesetting.min_word_space = word_spacing.min;
+            config.typesetting.max_word_space = word_spacing.max;
+            config.typesetting.overfull_tolerance = overfull;
+            config.typesetting.underfull_badness = underfull_badness;
+
+            let (mut document, font_manager) = config.init()?;
+            let font_config = font_manager.default_config();
+            document.render(&paragraph, &font_config, Pt(10.0));
+
+            Ok(document.warnings().iter().map(|x| x.ty).collect())
+        };
+
+        let config = Config::with_title("Test");
+        let (_, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let items = itemize_ast(
+            &paragraph,
+            &font_config,
+            Pt(10.0),
+            &en,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+        let lines = LatexJustifier::justify(&items, config.text_width, word_spacing);
+        let overflow = Pt(lines.iter().fold(0.0, |max: f64, x| max.max(x.overflow.0)));
+        let badness = lines.iter().fold(0.0, |max: f64, x| max.max(x.badness));
+        assert!(overflow.0 > 1.0);
+        assert!(badness > 1.0);
+
+        // At the thresholds, nothing is reported.
+        let huge = Pt(1000.0);
+        assert!(warnings(overflow, f64::INFINITY)?.is_empty());
+        assert!(warnings(huge, badness)?.is_empty());
+
+        // Beyond them, only the worst lines are.
+        let overfull = warnings(overflow - Pt(1.0), f64::INFINITY)?;
+        assert!(!overfull.is_empty());
+        assert!(overfull.iter().all(|x| *x == WarningType::OverfullLine));
+
+        let underfull = warnings(huge, badness - 1.0)?;
+        assert!(!underfull.is_empty());
+        assert!(underfull.iter().all(|x| *x == WarningType::UnderfullLine));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_numbers() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.page_numbers = true;
+        config.first_page_number = 45;
+
+        let paragraph = Ast::Paragraph(vec![Ast::Text("Hello".into())]);
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        document.render(&paragraph, &font_config, Pt(10.0));
+        document.new_page();
+        assert_eq!(
+            document.page_labels(),
+            vec![Some("45".into()), Some("46".into())]
+        );
+
+        // The front matter is numbered on its own, and the main matter starts a new page.
+        config.roman_front_matter = true;
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        document.render(&paragraph, &font_config, Pt(10.0));
+        document.new_page();
+        document.render(&paragraph, &font_config, Pt(10.0));
+        document.render(&Ast::MainMatterMarker, &font_config, Pt(10.0));
+        document.render(&paragraph, &font_config, Pt(10.0));
+
+        let labels = document.page_labels();
+        assert_eq!(
+            labels,
+            vec![Some("i".into()), Some("ii".into()), Some("45".into())]
+        );
+        document.to_bytes()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_stats() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        assert_eq!(document.line_stats(), LineStats::default());
+
+        let words = "The hallway smelt of boiled cabbage and old rag mats. At one end of it a \
+                     coloured poster, too large for indoor display, had been tacked to the wall. \
+                     It depicted simply an enormous face, more than a metre wide: the face of a \
+                     man of about forty-five, with a heavy black moustache and ruggedly handsome \
+                     features. Winston made for the stairs. It was no use trying the lift. Even \
+                     at the best of times it was seldom working, and at present the electric \
+                     current was cut off during daylight hours.";
+        let paragraph = Ast::Paragraph(vec![Ast::Text(words.into())]);
+        document.render(&paragraph, &font_config, Pt(10.0));
+
+        let stats = document.line_stats();
+        assert!(stats.lines > 1);
+        assert!(stats.hyphenated_lines < stats.lines);
+        assert!(stats.max_badness > 0.0);
+        assert!(stats.average_adjustment_ratio() > -1.0);
+        assert!(stats.average_adjustment_ratio() < 1.0);
+
+        // The capitalized words aren't hyphenated, so the lines broken after their dashes are
+        // not hyphenated.
+        let mut config = Config::with_title("Test");
+        config.text_width = Pt(80.0);
+        config.typesetting.hyphenate_capitalized = false;
+        let (mut document, font_manager) = config.init()?;
+        let words = vec!["Up-to-date"; 60].join(" ");
+        let paragraph = Ast::Paragraph(vec![Ast::Text(words)]);
+        document.render(&paragraph, &font_manager.default_config(), Pt(10.0));
+
+        let mut line_ends = HashMap::new();
+        for glyph in document.layout() {
+            line_ends.insert(glyph.y.to_bits(), glyph.glyph.as_str());
+        }
+        assert!(line_ends.values().any(|&glyph| glyph == "-"));
+        assert_eq!(document.line_stats().hyphenated_lines, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_math() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let math = |label: Option<&str>| Ast::DisplayMath {
+            content: String::from("x = 1"),
+            label: label.map(String::from),
+        };
+
+        document.render(&math(None), &font_config, Pt(10.0));
+        document.render(&math(Some("first")), &font_config, Pt(10.0));
+        document.render(&math(Some("second")), &font_config, Pt(10.0));
+
+        let glyphs = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.glyph.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(glyphs, vec!["x = 1", "x = 1", "(1)", "x = 1", "(2)"]);
+        assert_eq!(document.reference("first"), Some("1"));
+        assert_eq!(document.reference("second"), Some("2"));
+        assert_eq!(document.reference("third"), None);
+
+        // The math is centered, and its number ends at the right margin.
+        let window = document.window;
+        let font = font_config.regular;
+        let math = &document.layout()[1];
+        let width = font.text_width("x = 1", Pt(10.0)).0;
+        let margins = (
+            math.x - window.x.0,
+            window.x.0 + window.width.0 - math.x - width,
+        );
+        assert!((margins.0 - margins.1).abs() < 1e-9);
+        let number = &document.layout()[2];
+        let width = font.text_width("(1)", Pt(10.0)).0;
+        assert!((number.x + width - window.x.0 - window.width.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_lines_per_page() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let paragraph = Ast::Paragraph(vec![Ast::Text("Hello".into())]);
+        document.set_min_lines_per_page(Some(3));
+
+        for _ in 0..3 {
+            document.render(&paragraph, &font_config, Pt(10.0));
+        }
+        document.render(&Ast::ClearPage, &font_config, Pt(10.0));
+        assert!(document.warnings().is_empty());
+
+        // The document ends with a stray line.
+        document.render(&paragraph, &font_config, Pt(10.0));
+        document.check_last_page();
+        let warnings = document.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].ty, WarningType::SparsePage);
+        assert_eq!(warnings[0].content, "page 2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clear_page() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let paragraph = Ast::Paragraph(vec![Ast::Text("Hello".into())]);
+
+        // An empty page is not cleared again.
+        document.render(&Ast::ClearPage, &font_config, Pt(10.0));
+        document.render(&paragraph, &font_config, Pt(10.0));
+        document.render(&Ast::ClearPage, &font_config, Pt(10.0));
+        document.render(&Ast::ClearPage, &font_config, Pt(10.0));
+        document.render(&paragraph, &font_config, Pt(10.0));
+
+        assert_eq!(document.page_count(), 2);
+        let pages = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.page)
+            .collect::<Vec<_>>();
+        assert_eq!(pages, vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_pdf() -> Result<()> {
+        let config = Config::with_title("Test");
+        let (mut appendix, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let paragraph = Ast::Paragraph(vec![Ast::Text("Hello".into())]);
+
+        // An appendix of three pages, a page of text, a blank page, and the page after it.
+        appendix.render(&paragraph, &font_config, Pt(10.0));
+        appendix.insert_blank_page();
+        assert_eq!(appendix.page_count(), 3);
+        let path = env::temp_dir().join("spandex-test-append-pdf.pdf");
+        fs::write(&path, appendix.to_bytes()?)?;
+
+        let (mut document, _) = config.init()?;
+        document.render(&paragraph, &font_config, Pt(10.0));
+        assert_eq!(document.page_count(), 1);
+        document.append_pdf(&path)?;
+        fs::remove_file(&path)?;
+        assert_eq!(document.page_count(), 4);
+
+        let pdf = lopdf::Document::load_mem(&document.to_bytes()?)?;
+        assert_eq!(pdf.get_pages().len(), 4);
+        assert_eq!(layers(&pdf).len(), 4);
+
+        // A file that isn't a pdf is rejected.
+        let (mut document, _) = config.init()?;
+        assert!(document.append_pdf("Cargo.toml").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blank_page() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let paragraph = Ast::Paragraph(vec![Ast::Text("Hello".into())]);
+
+        // An empty page is left blank.
+        document.insert_blank_page();
+        assert_eq!(document.page_count(), 2);
+
+        // A page with content is followed by the blank page.
+        document.render(&paragraph, &font_config, Pt(10.0));
+        document.insert_blank_page();
+        assert_eq!(document.page_count(), 4);
+
+        document.render(&paragraph, &font_config, Pt(10.0));
+        let pages = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.page)
+            .collect::<Vec<_>>();
+        assert_eq!(pages.first(), Some(&2));
+        assert_eq!(pages.last(), Some(&4));
+        document.to_bytes()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_emergency_breaks() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+
+        let url = format!("https://example.com/{}", "0123456789".repeat(18));
+        let paragraph = Ast::Paragraph(vec![Ast::Text(format!("See {} for details.", url))]);
+        let right = config.left_margin.0 + config.text_width.0;
+
+        // By default, the url overflows the margin.
+        document.render(&paragraph, &font_config, Pt(10.0));
+        assert!(document.layout().iter().any(|glyph| glyph.x >= right));
+        assert!(document
+            .warnings()
+            .iter()
+            .all(|warning| warning.ty != WarningType::EmergencyBreak));
+
+        config.typesetting.emergency_breaks = true;
+        let (mut document, _) = config.init()?;
+        document.render(&paragraph, &font_config, Pt(10.0));
+
+        // The url wraps over several lines, all within the text.
+        let mut lines = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.y.to_bits())
+            .collect::<Vec<_>>();
+        lines.dedup();
+        assert!(lines.len() > 2);
+        assert!(document.layout().iter().all(|glyph| glyph.x < right));
+
+        let warnings = document.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].ty, WarningType::EmergencyBreak);
+        assert_eq!(warnings[0].content, url);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_sided() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.page_numbers = true;
+        let (document, _) = config.init()?;
+
+        // Without two sides, the numbers are centered.
+        let x = document.page_number_x(1, "1");
+        assert!((x.0 * 2.0 + 5.0 - config.page_width.0).abs() < 1e-9);
+        assert_eq!(x, document.page_number_x(2, "2"));
+
+        config.two_sided = true;
+        let (document, _) = config.init()?;
+
+        // The numbers of the even and odd pages are mirrored.
+        let even = document.page_number_x(2, "12");
+        let odd = document.page_number_x(3, "13");
+        assert!((even.0 - config.left_margin.0).abs() < 1e-9);
+        assert!((odd.0 + 10.0 + even.0 - config.page_width.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_running_headers() -> Result<()> {
+        let headers = |running_header| -> Result<Vec<Option<String>>> {
+            let mut config = Config::with_title("Test");
+            config.running_header = Some(running_header);
+
+            let (mut document, font_manager) = config.init()?;
+            let font_config = font_manager.default_config();
+
+            let title = |text: &str| Ast::Title {
+                level: 0,
+                children: vec![Ast::Text(text.into())],
+            };
+            let paragraph = Ast::Paragraph(vec![Ast::Text("Hello".into())]);
+
+            document.render(&title("One"), &font_config, Pt(10.0));
+            document.render(&paragraph, &font_config, Pt(10.0));
+            document.render(&title("Two"), &font_config, Pt(10.0));
+            document.new_page();
+            document.render(&paragraph, &font_config, Pt(10.0));
+
+            let headers = document.running_headers();
+            document.to_bytes()?;
+            Ok(headers)
+        };
+
+        let first = headers(RunningHeader::First)?;
+        assert_eq!(first, vec![Some("1  One".into()), Some("2  Two".into())]);
+
+        let last = headers(RunningHeader::Last)?;
+        assert_eq!(last, vec![Some("2  Two".into()), Some("2  Two".into())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_annotations() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.show_annotations = true;
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+
+        let paragraph = Ast::Paragraph(vec![
+            Ast::Text("Hello ".into()),
+            Ast::Annotation("Check this".into()),
+            Ast::Text("world".into()),
+        ]);
+        document.render(&paragraph, &font_config, Pt(10.0));
+
+        let w = document
+            .layout()
+            .iter()
+            .find(|glyph| glyph.glyph == "w")
+            .cloned()
+            .unwrap();
+
+        let annotations = document.annotations().to_vec();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].page, 1);
+        assert_eq!(annotations[0].x, w.x);
+        assert_eq!(annotations[0].y, w.y);
+        assert_eq!(annotations[0].note, "Check this");
+
+        let bytes = document.to_bytes()?;
+        let contents = b"/Contents<FEFF0043006800650063006B00200074006800690073>";
+        assert!(find(&bytes, b"/Annots[", 0).is_some());
+        assert!(find(&bytes, contents, 0).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_skips() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.paragraph_skip = Pt(7.0);
+        config.list_item_skip = Pt(3.0);
+        config.list_skip = Pt(5.0);
+
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+
+        let mut advance = |ast: &Ast| {
+            let before = document.cursor.1;
+            document.render(ast, &font_config, Pt(10.0));
+            (before - document.cursor.1).0
+        };
+
+        let text = || vec![Ast::Text("Hello".into())];
+        let item = || Ast::UnorderedListItem {
+            level: 0,
+            children: text(),
+        };
+
+        assert!((advance(&Ast::Paragraph(text())) - 17.0).abs() < 1e-9);
+        assert!((advance(&item()) - 13.0).abs() < 1e-9);
+        assert!((advance(&Ast::UnorderedList(vec![item(), item()])) - 31.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hanging_list_item() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+
+        let text = "Lorem ipsum dolor sit amet. ".repeat(10);
+        let item = Ast::UnorderedListItem {
+            level: 1,
+            children: vec![Ast::Text(text)],
+        };
+        document.render(&item, &font_config, Pt(10.0));
+
+        // The first glyph of each line.
+        let mut starts: Vec<&PositionedGlyph> = vec![];
+        for glyph in document.layout() {
+            if starts.last().map_or(true, |start| start.y != glyph.y) {
+                starts.push(glyph);
+            }
+        }
+
+        let marker = starts[0];
+        let text_start = document.layout()[1].x;
+        assert_eq!(marker.glyph, LIST_MARKER.to_string());
+        assert!((marker.x - (document.window.x + LIST_INDENT).0).abs() < 1e-9);
+        assert!(text_start > marker.x);
+
+        assert!(starts.len() > 2);
+        for start in &starts[1..] {
+            assert!((start.x - text_start).abs() < 1e-9);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_media_box_width() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.page_width = Mm(210.0).into();
+
+        let (document, _) = config.init()?;
+        let bytes = document.to_bytes()?;
+        let start = find(&bytes, b"/MediaBox[0 0 ", 0).unwrap() + 14;
+        let end = find(&bytes, b" ", start).unwrap();
+        let width: f64 = String::from_utf8_lossy(&bytes[start..end]).parse().unwrap();
+
+        assert!((width - 210.0 / 25.4 * 72.0).abs() < 0.01);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_code_block_whitespace() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::CodeBlock {
+            language: None,
+            content: String::from("a    b\n  c"),
+            line_numbers: false,
+        };
+        document.render(&ast, &font_config, Pt(10.0));
+
+        // The glyphs of a monospace font all have the same advance, and the spaces keep it.
+        let advance = font_config.monospace.char_width(' ', Pt(10.0)).0;
+        let x = |c: &str| {
+            let glyph = document.layout().iter().find(|g| g.glyph == c).unwrap();
+            assert_eq!(glyph.font, font_config.monospace.name());
+            glyph.x
+        };
+        assert!((x("b") - x("a") - 5.0 * advance).abs() < 1e-6);
+        assert!((x("c") - x("a") - 2.0 * advance).abs() < 1e-6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_code_block_wrapping() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::CodeBlock {
+            language: None,
+            content: vec!["hyphenation"; 20].join(" "),
+            line_numbers: false,
+        };
+        document.render(&ast, &font_config, Pt(10.0));
+
+        // The line wraps between its words, which are neither hyphenated nor spread apart.
+        let advance = font_config.monospace.char_width(' ', Pt(10.0)).0;
+        let layout = document.layout();
+        let lines = layout
+            .iter()
+            .map(|glyph| glyph.y.to_bits())
+            .collect::<HashSet<_>>();
+        assert!(lines.len() > 1);
+        assert!(layout.iter().all(|glyph| glyph.glyph != "-"));
+        assert!(layout.windows(2).all(|pair| pair[0].y != pair[1].y
+            || (pair[1].x - pair[0].x - advance).abs() < 1e-6
+            || (pair[1].x - pair[0].x - 2.0 * advance).abs() < 1e-6));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_windows() -> Result<()> {
+        let window = |left_margin, text_width| PageWindow {
+            top_margin: Pt(100.0),
+            left_margin: Pt(left_margin),
+            text_width: Pt(text_width),
+            text_height: Pt(600.0),
+        };
+
+        let mut config = Config::with_title("Test");
+        config.page_windows = vec![window(50.0, 495.0), window(120.0, 355.0)];
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+
+        for _ in 0..3 {
+            document.render(&ast, &font_config, Pt(10.0));
+            document.new_page();
+        }
+
+        // The last window is used for the pages after the list.
+        let first_x = |page| {
+            let glyph = document.layout().iter().find(|g| g.page == page).unwrap();
+            glyph.x
+        };
+        assert_eq!(first_x(1), 50.0);
+        assert_eq!(first_x(2), 120.0);
+        assert_eq!(first_x(3), 120.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_baseline() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.first_baseline = Pt(7.5);
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+
+        document.render(&ast, &font_config, Pt(10.0));
+        document.new_page();
+        document.render(&ast, &font_config, Pt(10.0));
+
+        let first_y = |page| {
+            let glyph = document.layout().iter().find(|g| g.page == page).unwrap();
+            glyph.y
+        };
+        let top = config.top_margin.0 + config.text_height.0;
+        assert_eq!(first_y(1), top - 7.5);
+        assert_eq!(first_y(2), first_y(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_and_bottom_skips() -> Result<()> {
+        let config = Config::from_toml(
+            &toml::to_string(&Config::with_title("Test"))
+                .unwrap()
+                .replace("first_baseline = 0.0", "top_skip = 12.0"),
+        )?;
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let top = config.top_margin.0 + config.text_height.0;
+        assert_eq!(document.cursor.1 .0, top - 12.0);
+
+        let lines = document.lines_below(document.top(), Pt(10.0));
+        document.set_bottom_skip(Pt(100.0));
+        assert!(document.lines_below(document.top(), Pt(10.0)) < lines);
+
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+        document.render(&ast, &font_config, Pt(10.0));
+        assert_eq!(document.layout()[0].y, top - 12.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_list_marker() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.list_marker = '\u{1F600}';
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        assert!(!font_config.regular.has_glyph(config.list_marker));
+
+        let item = Ast::UnorderedListItem {
+            level: 0,
+            children: vec![Ast::Text("Item".into())],
+        };
+        document.render(&item, &font_config, Pt(10.0));
+        document.render(&item, &font_config, Pt(10.0));
+
+        let markers = document
+            .layout()
+            .iter()
+            .filter(|glyph| glyph.glyph == FALLBACK_LIST_MARKER.to_string())
+            .count();
+        assert_eq!(markers, 2);
+
+        let warnings = document.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].ty, WarningType::MissingListMarker);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_document_title() -> Result<()> {
+        let (mut document, _) = Config::with_title("My thesis").init()?;
+        document.new_page();
+        let bytes = document.to_bytes()?;
+
+        let contains = |pattern: &[u8]| bytes.windows(pattern.len()).any(|x| x == pattern);
+        assert!(contains(b"(My thesis)"));
+        assert!(contains(b"(Page 2)"));
+        assert!(!contains(b"(Hello)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fit_text_in_box() -> Result<()> {
+        let (document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let text = "Lorem ipsum dolor sit amet. ".repeat(20);
+        let ast = Ast::Paragraph(vec![Ast::Text(text)]);
+
+        let fit = |width, height| {
+            let rect = Region {
+                x: Pt(0.0),
+                y: Pt(0.0),
+                width: Pt(width),
+                height: Pt(height),
+            };
+            document.fit_text_in_box(&ast, &font_config, rect, Pt(20.0), Pt(4.0))
+        };
+
+        let small = fit(150.0, 100.0);
+        let large = fit(400.0, 300.0);
+        assert!(small < large);
+        assert!(small > Pt(4.0));
+        assert_eq!(large, Pt(20.0));
+
+        let height = document.measure_paragraph(&ast, &font_config, small, Pt(150.0));
+        assert!(height <= Pt(100.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_synthetic_styles() -> Result<()> {
+        // This family has a single face, which is neither italic nor bold.
+        let family = "CMU Sans Serif Demi Condensed";
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        assert!(font_manager.family_config(family).is_err());
+
+        let mut config = Config::with_title("Test");
+        config.synthetic_styles = true;
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.family_config(family)?;
+        let ast = Ast::Paragraph(vec![
+            Ast::Text("Upright ".into()),
+            Ast::Italic(vec![Ast::Text("slanted".into())]),
+        ]);
+        document.render(&ast, &font_config, Pt(10.0));
+
+        let glyph = |c| document.layout().iter().find(|g| g.glyph == c).unwrap();
+        assert_eq!(glyph("U").shear, 0.0);
+        assert_eq!(glyph("s").shear, SYNTHETIC_SLANT);
+        assert_eq!(glyph("s").stroke, 0.0);
+        assert!(font_config.bold.synthetic().bold);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kern_overrides() -> Result<()> {
+        let gap = |kern_overrides: KernOverrides| -> Result<f64> {
+            let mut config = Config::with_title("Test");
+            config.typesetting.kern_overrides = kern_overrides;
+            let (mut document, font_manager) = config.init()?;
+            let font_config = font_manager.default_config();
+            let ast = Ast::Paragraph(vec![Ast::Text("AVA".into())]);
+            document.render(&ast, &font_config, Pt(10.0));
+
+            let x = |c| document.layout().iter().find(|g| g.glyph == c).unwrap().x;
+            Ok(x("V") - x("A"))
+        };
+
+        let mut pairs = HashMap::new();
+        pairs.insert(('A', 'V'), Pt(-1.5));
+        let kerned = gap(KernOverrides::new(pairs))?;
+        assert!((kerned - (gap(KernOverrides::default())? - 1.5)).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parfill_stretch() -> Result<()> {
+        let last_line_end = |parfill_stretch| -> Result<f64> {
+            let mut config = Config::with_title("Test");
+            config.typesetting.parfill_stretch = parfill_stretch;
+            let (mut document, font_manager) = config.init()?;
+            let font_config = font_manager.default_config();
+
+            let text = "Lorem ipsum dolor sit amet. ".repeat(6);
+            let ast = Ast::Paragraph(vec![Ast::Text(text)]);
+            document.render(&ast, &font_config, Pt(10.0));
+
+            let last_y = document.layout().last().unwrap().y;
+            Ok(document
+                .layout()
+                .iter()
+                .filter(|glyph| glyph.y == last_y)
+                .fold(0.0, |max, glyph| glyph.x.max(max)))
+        };
+
+        let ragged = last_line_end(None)?;
+        let justified = last_line_end(Some(0.0))?;
+        assert!(justified > ragged);
+        assert_eq!(last_line_end(Some(1e6))?, ragged);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_pages() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.max_pages = Some(2);
+        let (mut document, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+
+        for _ in 0..4 {
+            document.render(&ast, &font_config, Pt(10.0));
+            document.new_page();
+        }
+
+        assert_eq!(document.page_count(), 2);
+        assert!(document.clipped());
+        assert!(document.layout().iter().all(|glyph| glyph.page <= 2));
+        assert_eq!(document.layout().len(), 2 * "Helloworld".len());
+
+        let clipped = document
+            .warnings()
+            .iter()
+            .filter(|w| w.ty == WarningType::ContentClipped)
+            .count();
+        assert_eq!(clipped, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_hooks() -> Result<()> {
+        let (mut document, _) = Config::with_title("Test").init()?;
+        document.new_page();
+        document.new_page();
+
+        let pages = Rc::new(RefCell::new(vec![]));
+        let seen = Rc::clone(&pages);
+        document.on_page(move |_, page| seen.borrow_mut().push(page));
+        document.to_bytes()?;
+
+        assert_eq!(*pages.borrow(), vec![1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crop_page() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let ast = Ast::Paragraph(vec![Ast::Text("Hello world".into())]);
+        document.render(&ast, &font_config, Pt(10.0));
+        document.new_page();
+
+        let region = Region {
+            x: Pt(50.0),
+            y: Pt(600.0),
+            width: Pt(200.0),
+            height: Pt(150.25),
+        };
+        document.crop_page(1, region);
+        let bytes = document.to_bytes()?;
+
+        // The first page is replaced by a cropped one, the second one is left as it is.
+        let update = bytes.windows(5).position(|w| w == b"%%EOF").unwrap();
+        let cropped = find(&bytes, b"/MediaBox[", update).unwrap();
+        let boxes = String::from_utf8_lossy(&bytes[cropped..]);
+        assert!(boxes.starts_with("/MediaBox[50.00 600.00 250.00 750.25]"));
+        assert!(boxes.contains("/CropBox[50.00 600.00 250.00 750.25]"));
+        assert_eq!(boxes.matches("/MediaBox[").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_break_penalty() -> Result<()> {
+        let config = Config::with_title("Test");
+        let text = "Lorem ipsum dolor sit amet. ".repeat(15);
+        let ast = Ast::Paragraph(vec![Ast::Text(text)]);
+
+        let write = |penalty, lines_left| -> Result<Pt> {
+            let (mut document, font_manager) = config.init()?;
+            let font_config = font_manager.default_config();
+            document.set_page_break_penalty(penalty);
+            if let Some(lines) = lines_left {
+                document.cursor.1 = document.window.y + Pt(10.0) * lines;
+            }
+            document.render(&ast, &font_config, Pt(10.0));
+            Ok(document.cursor.1)
+        };
+
+        let on_empty_page = write(0.0, None)?;
+        assert_eq!(write(MAX_PAGE_BADNESS, Some(2.5))?, on_empty_page);
+        assert_ne!(write(0.0, Some(2.5))?, on_empty_page);
+
+        Ok(())
     }
 }
@@ -0,0 +1,90 @@
+//! This module contains the checks of the raw content passed verbatim to the pdf backend.
+
+/// The backend of the raw content written in the pdf, the only backend for now.
+pub const PDF_BACKEND: &str = "pdf";
+
+/// Returns the operators of some pdf content, skipping its strings and comments.
+fn operators(content: &str) -> Vec<String> {
+    let mut operators = vec![];
+    let mut token = String::new();
+    let mut chars = content.chars();
+    let mut flush = |token: &mut String| {
+        if !token.is_empty() {
+            operators.push(std::mem::take(token));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => {
+                flush(&mut token);
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('\\') => {
+                            chars.next();
+                        }
+                        Some('(') => depth += 1,
+                        Some(')') => depth -= 1,
+                        Some(_) => (),
+                        None => break,
+                    }
+                }
+            }
+            '%' => {
+                flush(&mut token);
+                for c in chars.by_ref() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() || "[]<>{}/".contains(c) => flush(&mut token),
+            c => token.push(c),
+        }
+    }
+    flush(&mut token);
+
+    operators
+}
+
+/// Returns whether the graphics states and the text objects of some pdf content are balanced,
+/// so that the content can't leak its state to the rest of the page.
+///
+/// Each `q` must be closed by a `Q` and each `BT` by an `ET`, in order, and the content must not
+/// close what it didn't open.
+pub fn is_balanced(content: &str) -> bool {
+    let mut open = vec![];
+
+    for operator in operators(content) {
+        match operator.as_str() {
+            "q" | "BT" => open.push(operator),
+            "Q" if open.last().map(String::as_str) == Some("q") => {
+                open.pop();
+            }
+            "ET" if open.last().map(String::as_str) == Some("BT") => {
+                open.pop();
+            }
+            "Q" | "ET" => return false,
+            _ => (),
+        }
+    }
+
+    open.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::document::raw::is_balanced;
+
+    #[test]
+    fn test_is_balanced() {
+        assert!(is_balanced("q 1 0 0 RG 0 0 m 100 0 l S Q"));
+        assert!(is_balanced(
+            "BT /F1 12 Tf (a q in a string) Tj ET % Q in a comment"
+        ));
+        assert!(!is_balanced("q 0 0 m"));
+        assert!(!is_balanced("Q"));
+        assert!(!is_balanced("q BT Q ET"));
+    }
+}
@@ -1,7 +1,39 @@
 //! Configuration for a `Font` used to typeset a document.
 
+use printpdf::Pt;
+
 use crate::fonts::styles::FontStyle;
 use crate::fonts::Font;
+use crate::layout::constants::{TITLE_SIZE_BOOST, TITLE_SIZE_RATIO};
+
+/// The sizes of the titles, relative to the size of the body text.
+///
+/// The boost over the body size decreases geometrically with the level, so level 0 is the
+/// largest and deeper levels get closer to the body size without ever reaching it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TitleSizes {
+    /// How much larger than the body text a title of level 0 is, relative to the body size.
+    pub boost: f64,
+
+    /// The ratio between the size boosts of two consecutive title levels.
+    pub ratio: f64,
+}
+
+impl TitleSizes {
+    /// Returns the size of a title of some level, for a body text of some size.
+    pub fn size(&self, size: Pt, level: u8) -> Pt {
+        size * (1.0 + self.boost * self.ratio.powi(i32::from(level)))
+    }
+}
+
+impl Default for TitleSizes {
+    fn default() -> TitleSizes {
+        TitleSizes {
+            boost: TITLE_SIZE_BOOST,
+            ratio: TITLE_SIZE_RATIO,
+        }
+    }
+}
 
 /// A font configuration for a document.
 pub struct FontConfig<'a> {
@@ -16,11 +48,20 @@ pub struct FontConfig<'a> {
 
     /// The bold italic font.
     pub bold_italic: &'a Font,
+
+    /// The monospace font, for code.
+    pub monospace: &'a Font,
+
+    /// The char drawn instead of the chars that the fonts can't draw.
+    pub missing_glyph: char,
+
+    /// The sizes of the titles.
+    pub title_sizes: TitleSizes,
 }
 
 impl<'a> FontConfig<'a> {
     /// Returns the font corresponding to the style.
-    pub fn for_style(&self, style: FontStyle) -> &Font {
+    pub fn for_style(&self, style: FontStyle) -> &'a Font {
         match (style.bold, style.italic) {
             (false, false) => self.regular,
             (true, false) => self.bold,
@@ -28,4 +69,17 @@ impl<'a> FontConfig<'a> {
             (true, true) => self.bold_italic,
         }
     }
+
+    /// Returns a config that uses the monospace font for every style, to render code.
+    pub fn monospace(&self) -> FontConfig<'a> {
+        FontConfig {
+            regular: self.monospace,
+            bold: self.monospace,
+            italic: self.monospace,
+            bold_italic: self.monospace,
+            monospace: self.monospace,
+            missing_glyph: self.missing_glyph,
+            title_sizes: self.title_sizes,
+        }
+    }
 }
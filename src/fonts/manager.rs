@@ -1,13 +1,96 @@
 //! Font manager that detects, loads and handles the different fonts
 //! available on the system.
 
+use crate::document::configuration::FontFamily;
 use crate::document::Document;
-use crate::fonts::configuration::FontConfig;
+use crate::fonts::configuration::{FontConfig, TitleSizes};
+use crate::fonts::metrics::{Metrics, METRICS_EXTENSION};
+use crate::fonts::styles::{FontStyle, Style, Weight};
 use crate::fonts::Font;
+use crate::layout::constants::DEFAULT_MISSING_GLYPH;
 use crate::{Error, Result};
 use freetype::Library;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Pairs the file name of a bundled font with its bytes.
+macro_rules! bundled_font {
+    ($file: literal) => {
+        ($file, include_bytes!(concat!("../../assets/fonts/", $file)))
+    };
+}
+
+/// The fonts bundled with spandex, with their file names.
+const BUNDLED_FONTS: &[(&str, &[u8])] = &[
+    bundled_font!("cmunbi.ttf"),
+    bundled_font!("cmunbl.ttf"),
+    bundled_font!("cmunbmo.ttf"),
+    bundled_font!("cmunbmr.ttf"),
+    bundled_font!("cmunbso.ttf"),
+    bundled_font!("cmunbsr.ttf"),
+    bundled_font!("cmunbtl.ttf"),
+    bundled_font!("cmunbto.ttf"),
+    bundled_font!("cmunbx.ttf"),
+    bundled_font!("cmunci.ttf"),
+    bundled_font!("cmunit.ttf"),
+    bundled_font!("cmunobi.ttf"),
+    bundled_font!("cmunobx.ttf"),
+    bundled_font!("cmunorm.ttf"),
+    bundled_font!("cmunoti.ttf"),
+    bundled_font!("cmunrm.ttf"),
+    bundled_font!("cmunsi.ttf"),
+    bundled_font!("cmunsl.ttf"),
+    bundled_font!("cmunso.ttf"),
+    bundled_font!("cmunssdc.ttf"),
+    bundled_font!("cmunss.ttf"),
+    bundled_font!("cmunsx.ttf"),
+    bundled_font!("cmuntb.ttf"),
+    bundled_font!("cmunti.ttf"),
+    bundled_font!("cmuntt.ttf"),
+    bundled_font!("cmuntx.ttf"),
+    bundled_font!("cmunui.ttf"),
+    bundled_font!("cmunvi.ttf"),
+    bundled_font!("cmunvt.ttf"),
+];
+
+/// The regular, bold, italic and bold italic fonts of the default font config.
+const DEFAULT_FONTS: [&str; 4] = [
+    "CMU Serif Roman",
+    "CMU Serif Bold",
+    "CMU Serif Italic",
+    "CMU Serif BoldItalic",
+];
+
+/// The regular, bold, italic and bold italic fonts of the default font config of the sans
+/// family.
+const SANS_FONTS: [&str; 4] = [
+    "CMU Sans Serif Medium",
+    "CMU Sans Serif Bold",
+    "CMU Sans Serif Oblique",
+    "CMU Sans Serif BoldOblique",
+];
+
+/// The regular, bold, italic and bold italic fonts of the default font config of the typewriter
+/// family.
+const TYPEWRITER_FONTS: [&str; 4] = [
+    "CMU Typewriter Text Regular",
+    "CMU Typewriter Text Bold",
+    "CMU Typewriter Text Italic",
+    "CMU Typewriter Text BoldItalic",
+];
+
+/// Returns the regular, bold, italic and bold italic fonts of a bundled family.
+fn family_fonts(family: FontFamily) -> [&'static str; 4] {
+    match family {
+        FontFamily::Serif => DEFAULT_FONTS,
+        FontFamily::Sans => SANS_FONTS,
+        FontFamily::Typewriter => TYPEWRITER_FONTS,
+    }
+}
+
+/// The family of the monospace font of the font configs, for code.
+const MONOSPACE_FAMILY: &str = "CMU Typewriter Text";
 
 /// This struct holds the different fonts.
 pub struct FontManager {
@@ -16,46 +99,68 @@ pub struct FontManager {
 
     /// The hashmap that associates names of fonts with fonts.
     fonts: HashMap<String, Font>,
+
+    /// The hashmap that associates names of fonts with their family, weight and style.
+    descriptors: HashMap<String, FontDescriptor>,
+
+    /// The char drawn instead of the chars that the fonts can't draw.
+    missing_glyph: char,
+
+    /// The sizes of the titles of the font configs.
+    title_sizes: TitleSizes,
+
+    /// The file names of the fonts that failed to load, with the reason why.
+    skipped: Vec<(String, Error)>,
+
+    /// The bundled family of the default font config.
+    family: FontFamily,
+}
+
+/// Returns the path of the cached metrics of a font in a directory.
+fn metrics_path(dir: &Path, font: &Font) -> PathBuf {
+    dir.join(format!("{:016x}.{}", font.hash(), METRICS_EXTENSION))
+}
+
+/// The high level description of a font, parsed from its freetype family and style names.
+#[derive(Clone, Debug)]
+struct FontDescriptor {
+    /// The family of the font, e.g. `"CMU Serif"`.
+    family: String,
+
+    /// The weight of the font.
+    weight: Weight,
+
+    /// The style of the font.
+    style: Style,
 }
 
 impl FontManager {
-    /// Creates a new font manager, with the default fonts.
+    /// Creates a new font manager, with the bundled fonts.
     pub fn init(document: &mut Document) -> Result<FontManager> {
+        FontManager::with_fonts(BUNDLED_FONTS, document)
+    }
+
+    /// Creates a new font manager from fonts given by their file names and their bytes.
+    ///
+    /// The fonts that fail to load are skipped, unless they are one of the default fonts.
+    pub fn with_fonts(fonts: &[(&str, &[u8])], document: &mut Document) -> Result<FontManager> {
         let mut font_manager = FontManager {
             library: Library::init()?,
             fonts: HashMap::new(),
+            descriptors: HashMap::new(),
+            missing_glyph: DEFAULT_MISSING_GLYPH,
+            title_sizes: TitleSizes::default(),
+            skipped: vec![],
+            family: FontFamily::Serif,
         };
 
-        // Insert the default fonts
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbi.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbl.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbmo.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbmr.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbso.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbsr.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbtl.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbto.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunbx.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunci.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunit.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunobi.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunobx.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunorm.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunoti.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunrm.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunsi.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunsl.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunso.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunssdc.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunss.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunsx.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmuntb.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunti.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmuntt.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmuntx.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunui.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunvi.ttf"), document)?;
-        font_manager.add_font(include_bytes!("../../assets/fonts/cmunvt.ttf"), document)?;
+        font_manager.load_fonts(fonts, document);
+
+        for name in &DEFAULT_FONTS {
+            if !font_manager.fonts.contains_key(*name) {
+                return Err(Error::DefaultFontMissing(String::from(*name)));
+            }
+        }
 
         Ok(font_manager)
     }
@@ -63,23 +168,230 @@ impl FontManager {
     /// Adds a new font to the font manager.
     pub fn add_font(&mut self, bytes: &[u8], document: &mut Document) -> Result<()> {
         let font = Font::from_bytes(bytes, &self.library, document)?;
-        let name = match (font.freetype.family_name(), font.freetype.style_name()) {
-            (Some(family), Some(style)) => format!("{} {}", family, style),
-            _ => {
-                eprintln!("Failed to create a built in font, this is a implementation error");
-                unreachable!();
+        let (name, descriptor) = match (font.freetype.family_name(), font.freetype.style_name()) {
+            (Some(family), Some(style)) => (
+                format!("{} {}", family, style),
+                FontDescriptor {
+                    family,
+                    weight: Weight::from_style_name(&style),
+                    style: Style::from_style_name(&style),
+                },
+            ),
+            _ => return Err(Error::FontWithoutName(PathBuf::new())),
+        };
+        self.fonts.insert(name.clone(), font);
+        self.descriptors.insert(name, descriptor);
+        Ok(())
+    }
+
+    /// Adds synthesized faces to the families that miss some styles, so that their emphasis
+    /// stays visible: an italic face slanted from the regular one if a family has no italic,
+    /// and a bold face stroked from the regular one if it has a single upright weight.
+    ///
+    /// The synthesized faces are named after their family and style, with a `(synthetic)`
+    /// suffix.
+    pub fn synthesize_styles(&mut self) {
+        let mut families = self
+            .descriptors
+            .values()
+            .map(|d| d.family.clone())
+            .collect::<Vec<_>>();
+        families.sort();
+        families.dedup();
+
+        for family in families {
+            let faces = self
+                .descriptors
+                .values()
+                .filter(|d| d.family == family)
+                .collect::<Vec<_>>();
+            let weights = faces
+                .iter()
+                .filter(|d| d.style == Style::Normal)
+                .map(|d| d.weight)
+                .collect::<Vec<_>>();
+            let has_italic = faces.iter().any(|d| d.style == Style::Italic);
+            let has_bold = weights.iter().any(|w| *w != weights[0]);
+
+            if !has_bold {
+                self.add_synthetic(
+                    &family,
+                    Weight::Regular,
+                    Style::Normal,
+                    "Bold",
+                    FontStyle::regular().bold(),
+                );
+            }
+
+            if !has_italic {
+                self.add_synthetic(
+                    &family,
+                    Weight::Regular,
+                    Style::Normal,
+                    "Italic",
+                    FontStyle::regular().italic(),
+                );
+                self.add_synthetic(
+                    &family,
+                    Weight::Bold,
+                    Style::Normal,
+                    "BoldItalic",
+                    FontStyle::regular().italic(),
+                );
+            } else if !has_bold {
+                self.add_synthetic(
+                    &family,
+                    Weight::Regular,
+                    Style::Italic,
+                    "BoldItalic",
+                    FontStyle::regular().bold(),
+                );
             }
+        }
+    }
+
+    /// Adds a face to a family, synthesized from the face that is the closest to a weight and a
+    /// style.
+    fn add_synthetic(
+        &mut self,
+        family: &str,
+        weight: Weight,
+        style: Style,
+        style_name: &str,
+        synthetic: FontStyle,
+    ) {
+        let font = match self.select(family, weight, style) {
+            Some(font) => font.synthesize(synthetic),
+            None => return,
         };
-        self.fonts.insert(name, font);
+
+        let name = format!("{} {} (synthetic)", family, style_name);
+        self.fonts.insert(name.clone(), font);
+        self.descriptors.insert(
+            name,
+            FontDescriptor {
+                family: String::from(family),
+                weight: Weight::from_style_name(style_name),
+                style: Style::from_style_name(style_name),
+            },
+        );
+    }
+
+    /// Adds fonts given by their file names and their bytes, skipping the ones that fail to
+    /// load.
+    fn load_fonts(&mut self, fonts: &[(&str, &[u8])], document: &mut Document) {
+        for (file, bytes) in fonts {
+            match self.add_font(bytes, document) {
+                Ok(()) => (),
+                Err(Error::FontWithoutName(_)) => self.skipped.push((
+                    file.to_string(),
+                    Error::FontWithoutName(PathBuf::from(file)),
+                )),
+                Err(e) => self.skipped.push((file.to_string(), e)),
+            }
+        }
+    }
+
+    /// Reads the metrics of the fonts cached in a directory, so that the chars they cover are not
+    /// measured by freetype again.
+    ///
+    /// The metrics are keyed by a hash of the bytes of each font, so they are ignored once a
+    /// font changes, as well as when they are missing or corrupted.
+    pub fn load_metrics<P: AsRef<Path>>(&self, dir: P) {
+        for font in self.fonts.values() {
+            let path = metrics_path(dir.as_ref(), font);
+            if let Some(metrics) = fs::read(path).ok().and_then(|b| Metrics::from_bytes(&b)) {
+                font.set_metrics(metrics);
+            }
+        }
+    }
+
+    /// Writes the metrics measured so far for each font to a directory, for the next builds.
+    pub fn save_metrics<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        fs::create_dir_all(dir.as_ref())?;
+
+        for font in self.fonts.values() {
+            let metrics = font.metrics();
+            if !metrics.advances.is_empty() {
+                fs::write(metrics_path(dir.as_ref(), font), metrics.to_bytes())?;
+            }
+        }
+
         Ok(())
     }
 
+    /// Returns the file names of the fonts that failed to load, with the reason why.
+    pub fn skipped(&self) -> &[(String, Error)] {
+        &self.skipped
+    }
+
+    /// Returns the font of a family that matches a style and is the closest to a weight.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use spandex::document::configuration::Config;
+    /// # use spandex::fonts::styles::{Style, Weight};
+    /// # fn main() -> spandex::Result<()> {
+    /// let (_, font_manager) = Config::with_title("Test").init()?;
+    /// assert!(font_manager.select("CMU Serif", Weight::Regular, Style::Normal).is_some());
+    /// assert!(font_manager.select("Comic Sans", Weight::Regular, Style::Normal).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn select(&self, family: &str, weight: Weight, style: Style) -> Option<&Font> {
+        let distance = |w: Weight| (w as i32 - weight as i32).abs();
+
+        self.descriptors
+            .iter()
+            .filter(|(_, d)| d.family == family && d.style == style)
+            .min_by(|(n1, d1), (n2, d2)| {
+                distance(d1.weight)
+                    .cmp(&distance(d2.weight))
+                    .then_with(|| n1.cmp(n2))
+            })
+            .and_then(|(name, _)| self.fonts.get(name))
+    }
+
     /// Returns a reference font if it is present in the font manager.
     pub fn get(&self, font_name: &str) -> Option<&Font> {
         self.fonts.get(font_name)
     }
 
+    /// Sets the char drawn instead of the chars that the fonts can't draw.
+    pub fn set_missing_glyph(&mut self, missing_glyph: char) {
+        self.missing_glyph = missing_glyph;
+    }
+
+    /// Sets the sizes of the titles of the font configs.
+    pub fn set_title_sizes(&mut self, title_sizes: TitleSizes) {
+        self.title_sizes = title_sizes;
+    }
+
+    /// Sets the bundled family of the default font config.
+    ///
+    /// Fails if one of the fonts of the family couldn't be loaded.
+    pub fn set_family(&mut self, family: FontFamily) -> Result<()> {
+        for name in &family_fonts(family) {
+            if !self.fonts.contains_key(*name) {
+                return Err(Error::DefaultFontMissing(String::from(*name)));
+            }
+        }
+
+        self.family = family;
+        Ok(())
+    }
+
+    /// Returns the monospace font for code, or a fallback font if it is missing.
+    fn monospace<'a>(&'a self, fallback: &'a Font) -> &'a Font {
+        self.select(MONOSPACE_FAMILY, Weight::Regular, Style::Normal)
+            .unwrap_or(fallback)
+    }
+
     /// Creates a font config.
+    ///
+    /// The monospace font of the config is the one of the bundled fonts, or the regular font if
+    /// it is missing.
     pub fn config<'a>(
         &'a self,
         regular: &str,
@@ -87,11 +399,13 @@ impl FontManager {
         italic: &str,
         bold_italic: &str,
     ) -> Result<FontConfig<'a>> {
+        let regular = self
+            .fonts
+            .get(regular)
+            .ok_or_else(|| Error::FontNotFound(PathBuf::from(regular)))?;
+
         Ok(FontConfig {
-            regular: self
-                .fonts
-                .get(regular)
-                .ok_or_else(|| Error::FontNotFound(PathBuf::from(regular)))?,
+            regular,
             bold: self
                 .fonts
                 .get(bold)
@@ -104,20 +418,175 @@ impl FontManager {
                 .fonts
                 .get(bold_italic)
                 .ok_or_else(|| Error::FontNotFound(PathBuf::from(bold_italic)))?,
+            monospace: self.monospace(regular),
+            missing_glyph: self.missing_glyph,
+            title_sizes: self.title_sizes,
         })
     }
 
-    /// Returns the default configuration for computer modern fonts.
+    /// Creates a font config from the name of a family.
+    pub fn family_config<'a>(&'a self, family: &str) -> Result<FontConfig<'a>> {
+        let select = |weight, style| {
+            self.select(family, weight, style)
+                .ok_or_else(|| Error::FontNotFound(PathBuf::from(family)))
+        };
+
+        let regular = select(Weight::Regular, Style::Normal)?;
+
+        Ok(FontConfig {
+            regular,
+            bold: select(Weight::Bold, Style::Normal)?,
+            italic: select(Weight::Regular, Style::Italic)?,
+            bold_italic: select(Weight::Bold, Style::Italic)?,
+            monospace: self.monospace(regular),
+            missing_glyph: self.missing_glyph,
+            title_sizes: self.title_sizes,
+        })
+    }
+
+    /// Returns the default configuration for computer modern fonts, in the family set by
+    /// `set_family`, serif by default.
     pub fn default_config(&self) -> FontConfig {
-        let regular = "CMU Serif Roman";
-        let bold = "CMU Serif Bold";
-        let italic = "CMU Serif Italic";
-        let bold_italic = "CMU Serif BoldItalic";
+        let [regular, bold, italic, bold_italic] = family_fonts(self.family);
 
-        // This should never fail.
+        // This should never fail, since the manager can't be created without the serif fonts,
+        // and its family can't be set without its fonts.
         match self.config(regular, bold, italic, bold_italic) {
             Ok(c) => c,
             Err(_) => unreachable!("Default font not found, this should never happen"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use printpdf::Pt;
+
+    use crate::document::configuration::{Config, FontFamily};
+    use crate::fonts::manager::{FontManager, BUNDLED_FONTS};
+    use crate::fonts::styles::{Style, Weight};
+    use crate::{Error, Result};
+
+    #[test]
+    fn test_skip_corrupt_font() -> Result<()> {
+        let (mut document, _) = Config::with_title("Test").init()?;
+
+        let mut fonts = BUNDLED_FONTS.to_vec();
+        fonts.insert(0, ("corrupt.ttf", b"not a font"));
+
+        let font_manager = FontManager::with_fonts(&fonts, &mut document)?;
+        let skipped = font_manager.skipped();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0, "corrupt.ttf");
+        assert!(font_manager.get("CMU Serif Roman").is_some());
+
+        // Without a default font, the manager can't be created.
+        let fonts = [("cmunrm.ttf", &b"not a font"[..])];
+        match FontManager::with_fonts(&fonts, &mut document) {
+            Err(Error::DefaultFontMissing(name)) => assert_eq!(name, "CMU Serif Roman"),
+            _ => panic!("the manager shouldn't be created without its default fonts"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_metrics_cache() -> Result<()> {
+        let dir = env::temp_dir().join(format!("spandex-metrics-{}", process::id()));
+        let mut config = Config::with_title("Test");
+        config.font_cache = Some(dir.to_string_lossy().into_owned());
+
+        let (_, font_manager) = config.init()?;
+        let font = font_manager.default_config().regular;
+        let width = font.text_width("Hello world", Pt(10.0));
+        assert!(font.freetype_loads() > 0);
+        font_manager.save_metrics(&dir)?;
+
+        // A warm cache measures the same text without freetype.
+        let (_, font_manager) = config.init()?;
+        let font = font_manager.default_config().regular;
+        assert_eq!(font.text_width("Hello world", Pt(10.0)).0, width.0);
+        assert_eq!(font.freetype_loads(), 0);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_glyph_bbox() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font = font_manager.get("CMU Serif Roman").unwrap();
+
+        let (_, tall_bottom, _, tall_top) = font.glyph_bbox('l', Pt(10.0));
+        let (short_left, short_bottom, short_right, short_top) = font.glyph_bbox('x', Pt(10.0));
+
+        // Both glyphs sit on the baseline, but the ascender of l makes it taller than x.
+        assert!(tall_bottom.0.abs() < 0.5 && short_bottom.0.abs() < 0.5);
+        assert!(tall_top.0 > short_top.0 + 1.0);
+        assert!(tall_top.0 < 10.0);
+
+        // The ink of a glyph is narrower than its advance.
+        assert!(short_right.0 - short_left.0 > 0.0);
+        assert!(short_right.0 - short_left.0 <= font.char_width('x', Pt(10.0)).0);
+
+        // A space has no ink.
+        let (left, bottom, right, top) = font.glyph_bbox(' ', Pt(10.0));
+        assert_eq!((left.0, bottom.0, right.0, top.0), (0.0, 0.0, 0.0, 0.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_bold_italic() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+
+        let selected = font_manager.select("CMU Serif", Weight::Bold, Style::Italic);
+        let expected = font_manager.get("CMU Serif BoldItalic");
+
+        assert!(selected.is_some());
+        assert!(std::ptr::eq(selected.unwrap(), expected.unwrap()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_font_family() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.font_family = FontFamily::Sans;
+        let (_, font_manager) = config.init()?;
+
+        let font_config = font_manager.default_config();
+        let sans = font_manager.family_config("CMU Sans Serif")?;
+        assert!(std::ptr::eq(font_config.regular, sans.regular));
+        assert!(std::ptr::eq(font_config.bold, sans.bold));
+        assert!(std::ptr::eq(font_config.italic, sans.italic));
+        assert!(std::ptr::eq(font_config.bold_italic, sans.bold_italic));
+        assert_eq!(font_config.regular.name(), "CMUSansSerif");
+
+        config.font_family = FontFamily::Typewriter;
+        let (_, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        assert_eq!(font_config.bold_italic.name(), "CMUTypewriter-BoldItalic");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_family_config() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+
+        let config = font_manager.family_config("CMU Serif")?;
+        let expected = font_manager.default_config();
+
+        assert!(std::ptr::eq(config.regular, expected.regular));
+        assert!(std::ptr::eq(config.bold, expected.bold));
+        assert!(std::ptr::eq(config.italic, expected.italic));
+        assert!(std::ptr::eq(config.bold_italic, expected.bold_italic));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+//! This module contains the metrics of the fonts measured by freetype, which can be cached on
+//! disk between builds.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+/// The extension of the files of the cache.
+pub const METRICS_EXTENSION: &str = "metrics";
+
+/// The metrics of a font, in font units.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// The number of font units in an em, measured as the vertical advance of the space.
+    pub units: Option<i64>,
+
+    /// The horizontal advance of the chars measured so far, zero for the chars without glyph.
+    pub advances: HashMap<char, i64>,
+}
+
+impl Metrics {
+    /// Encodes the metrics compactly, as the units followed by each char and its advance, in
+    /// little endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 12 * self.advances.len());
+        bytes.extend_from_slice(&self.units.unwrap_or(0).to_le_bytes());
+
+        let mut advances = self.advances.iter().collect::<Vec<_>>();
+        advances.sort();
+        for (c, advance) in advances {
+            bytes.extend_from_slice(&(*c as u32).to_le_bytes());
+            bytes.extend_from_slice(&advance.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Decodes metrics encoded by `to_bytes`, or returns `None` if the bytes are corrupted.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Metrics> {
+        if bytes.len() < 8 || (bytes.len() - 8) % 12 != 0 {
+            return None;
+        }
+
+        let units = i64::from_le_bytes(bytes[..8].try_into().ok()?);
+        let mut advances = HashMap::new();
+        for record in bytes[8..].chunks(12) {
+            let c = char::from_u32(u32::from_le_bytes(record[..4].try_into().ok()?))?;
+            advances.insert(c, i64::from_le_bytes(record[4..].try_into().ok()?));
+        }
+
+        Some(Metrics {
+            units: if units == 0 { None } else { Some(units) },
+            advances,
+        })
+    }
+}
+
+/// Hashes the bytes of a font file, to key its metrics in the cache.
+///
+/// This is FNV-1a, which, unlike the hasher of the standard library, is the same across
+/// versions of Rust, so that the cache survives them.
+pub fn font_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fonts::metrics::{font_hash, Metrics};
+
+    #[test]
+    fn test_round_trip() {
+        let mut metrics = Metrics {
+            units: Some(1000),
+            ..Default::default()
+        };
+        metrics.advances.insert('a', 500);
+        metrics.advances.insert('\u{1F600}', 0);
+
+        let bytes = metrics.to_bytes();
+        assert_eq!(bytes.len(), 8 + 2 * 12);
+        assert_eq!(Metrics::from_bytes(&bytes), Some(metrics));
+        assert_eq!(Metrics::from_bytes(&bytes[1..]), None);
+        assert_ne!(font_hash(b"font"), font_hash(b"fonts"));
+    }
+}
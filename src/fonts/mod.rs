@@ -2,17 +2,22 @@
 
 pub mod configuration;
 pub mod manager;
+pub mod metrics;
 pub mod styles;
 
-use std::fs::File;
+use std::cell::{Cell, RefCell};
+use std::fs;
 use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use freetype::{face, Face, Library};
 use printpdf::types::plugins::graphics::two_dimensional::font::IndirectFontRef;
 use printpdf::Pt;
 
 use crate::document::Document;
+use crate::fonts::metrics::{font_hash, Metrics};
+use crate::fonts::styles::FontStyle;
 use crate::{Error, Result};
 
 /// A font that contains the printpdf object font needed to render text and the freetype font
@@ -24,6 +29,19 @@ pub struct Font {
 
     /// The printpdf font.
     printpdf: IndirectFontRef,
+
+    /// The hash of the bytes of the font, which keys its metrics in the cache.
+    hash: u64,
+
+    /// The metrics measured so far, or read from the cache, shared with the faces synthesized
+    /// from this one.
+    metrics: Rc<RefCell<Metrics>>,
+
+    /// The number of chars loaded by freetype to measure them.
+    loads: Cell<usize>,
+
+    /// The styles synthesized when drawing the font, regular for the faces of a font file.
+    synthetic: FontStyle,
 }
 
 impl Font {
@@ -33,12 +51,9 @@ impl Font {
         library: &Library,
         document: &mut Document,
     ) -> Result<Font> {
-        let file = File::open(path.as_ref())
+        let bytes = fs::read(path.as_ref())
             .map_err(|_| Error::FontNotFound(PathBuf::from(path.as_ref())))?;
-        Ok(Font {
-            freetype: library.new_face(path.as_ref(), 0)?,
-            printpdf: document.inner_mut().add_external_font(file)?,
-        })
+        Font::from_bytes(&bytes, library, document)
     }
 
     /// Creates a font from a byte array.
@@ -49,73 +64,142 @@ impl Font {
             // this...
             freetype: library.new_memory_face(bytes.to_vec(), 0)?,
             printpdf: document.inner_mut().add_external_font(cursor)?,
+            hash: font_hash(bytes),
+            metrics: Rc::new(RefCell::new(Metrics::default())),
+            loads: Cell::new(0),
+            synthetic: FontStyle::regular(),
         })
     }
 
-    /// Computes the width of a char of the font at a specified size.
-    pub fn char_width(&self, c: char, scale: Pt) -> Pt {
-        let scale = scale.0;
-
-        // vertical scale for the space character
-        let vert_scale = {
-            if self
-                .freetype
-                .load_char(0x0020, face::LoadFlag::NO_SCALE)
-                .is_ok()
-            {
-                self.freetype.glyph().metrics().vertAdvance
-            } else {
-                1000
-            }
-        };
+    /// Creates a face that draws this one with some more synthesized styles: italics are slanted
+    /// and bold is stroked. The synthesized face has the same metrics.
+    pub fn synthesize(&self, style: FontStyle) -> Font {
+        Font {
+            freetype: self.freetype.clone(),
+            printpdf: self.printpdf.clone(),
+            hash: self.hash,
+            metrics: Rc::clone(&self.metrics),
+            loads: Cell::new(0),
+            synthetic: FontStyle {
+                bold: self.synthetic.bold || style.bold,
+                italic: self.synthetic.italic || style.italic,
+            },
+        }
+    }
 
-        // calculate the width of the text in unscaled units
-        let is_ok = self
-            .freetype
+    /// Returns the styles synthesized when drawing the font.
+    pub fn synthetic(&self) -> FontStyle {
+        self.synthetic
+    }
+
+    /// Loads a char in the freetype face, and returns whether it succeeded.
+    fn load_char(&self, c: char) -> bool {
+        self.loads.set(self.loads.get() + 1);
+        self.freetype
             .load_char(c as usize, face::LoadFlag::NO_SCALE)
-            .is_ok();
+            .is_ok()
+    }
+
+    /// Returns the number of font units in an em, measured as the vertical advance of the space.
+    fn units(&self) -> i64 {
+        if let Some(units) = self.metrics.borrow().units {
+            return units;
+        }
+
+        let units = if self.load_char(' ') {
+            self.freetype.glyph().metrics().vertAdvance
+        } else {
+            1000
+        };
+        self.metrics.borrow_mut().units = Some(units);
+        units
+    }
+
+    /// Returns the horizontal advance of a char in font units, zero if it has no glyph.
+    fn advance(&self, c: char) -> i64 {
+        if let Some(advance) = self.metrics.borrow().advances.get(&c) {
+            return *advance;
+        }
 
-        let width = if is_ok {
+        let advance = if self.load_char(c) {
             self.freetype.glyph().metrics().horiAdvance
         } else {
             0
         };
+        self.metrics.borrow_mut().advances.insert(c, advance);
+        advance
+    }
+
+    /// Converts a length in font units to pt, at a specified size.
+    fn to_pt(&self, units: i64, scale: Pt) -> Pt {
+        Pt(units as f64 / (self.units() as f64 / scale.0))
+    }
 
-        Pt(width as f64 / (vert_scale as f64 / scale))
+    /// Computes the width of a char of the font at a specified size.
+    pub fn char_width(&self, c: char, scale: Pt) -> Pt {
+        self.to_pt(self.advance(c), scale)
     }
 
     /// Computes the text width of the font at a specified size.
     pub fn text_width(&self, text: &str, scale: Pt) -> Pt {
-        let scale = scale.0;
-
-        // vertical scale for the space character
-        let vert_scale = {
-            if self
-                .freetype
-                .load_char(0x0020, face::LoadFlag::NO_SCALE)
-                .is_ok()
-            {
-                self.freetype.glyph().metrics().vertAdvance
-            } else {
-                1000
-            }
-        };
+        self.to_pt(text.chars().map(|c| self.advance(c)).sum(), scale)
+    }
+
+    /// Returns the hash of the bytes of the font.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the metrics measured so far.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.borrow().clone()
+    }
+
+    /// Sets the metrics of the font, e.g. read from the cache, so that the chars they cover are
+    /// not measured again.
+    pub fn set_metrics(&self, metrics: Metrics) {
+        *self.metrics.borrow_mut() = metrics;
+    }
+
+    /// Returns the number of chars that freetype loaded to measure them.
+    pub fn freetype_loads(&self) -> usize {
+        self.loads.get()
+    }
+
+    /// Computes the ink bounding box of a char of the font at a specified size.
+    ///
+    /// The box is returned as `(x_min, y_min, x_max, y_max)`, relative to the origin of the glyph
+    /// on the baseline, with y pointing up. A char without a glyph has an empty box.
+    pub fn glyph_bbox(&self, c: char, scale: Pt) -> (Pt, Pt, Pt, Pt) {
+        // The units are measured first, since measuring them loads the space.
+        self.units();
+
+        if !self.load_char(c) {
+            return (Pt(0.0), Pt(0.0), Pt(0.0), Pt(0.0));
+        }
+
+        let metrics = self.freetype.glyph().metrics();
+        let x_min = metrics.horiBearingX;
+        let y_max = metrics.horiBearingY;
+        let x_max = x_min + metrics.width;
+        let y_min = y_max - metrics.height;
+
+        (
+            self.to_pt(x_min, scale),
+            self.to_pt(y_min, scale),
+            self.to_pt(x_max, scale),
+            self.to_pt(y_max, scale),
+        )
+    }
+
+    /// Returns whether the font has a glyph for a char.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.freetype.get_char_index(c as usize) != 0
+    }
 
-        // calculate the width of the text in unscaled units
-        let sum_width = text.chars().fold(0, |acc, ch| {
-            let is_ok = self
-                .freetype
-                .load_char(ch as usize, face::LoadFlag::NO_SCALE)
-                .is_ok();
-
-            if is_ok {
-                acc + self.freetype.glyph().metrics().horiAdvance
-            } else {
-                acc
-            }
-        });
-
-        Pt(sum_width as f64 / (vert_scale as f64 / scale))
+    /// Returns the postscript name of the font, or an empty string if it has none.
+    pub fn name(&self) -> String {
+        self.freetype.postscript_name().unwrap_or_default()
     }
 
     /// Returns a reference to the printpdf font.
@@ -51,3 +51,66 @@ impl FontStyle {
         }
     }
 }
+
+/// The weight of a font face.
+///
+/// The variants are ordered from the lightest to the heaviest, so that weights can be compared to
+/// find the closest available face.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weight {
+    /// A light face.
+    Light,
+
+    /// A regular face, also called roman or book.
+    Regular,
+
+    /// A medium face.
+    Medium,
+
+    /// A semi bold face, also called demi bold.
+    SemiBold,
+
+    /// A bold face.
+    Bold,
+}
+
+impl Weight {
+    /// Guesses the weight of a font from its style name, e.g. `"BoldItalic"`.
+    pub fn from_style_name(style_name: &str) -> Weight {
+        if style_name.contains("Light") {
+            Weight::Light
+        } else if style_name.contains("SemiBold") || style_name.contains("Demi") {
+            Weight::SemiBold
+        } else if style_name.contains("Bold") {
+            Weight::Bold
+        } else if style_name.contains("Medium") {
+            Weight::Medium
+        } else {
+            Weight::Regular
+        }
+    }
+}
+
+/// The slope of a font face.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// An upright face.
+    Normal,
+
+    /// An italic, oblique or slanted face.
+    Italic,
+}
+
+impl Style {
+    /// Guesses the style of a font from its style name, e.g. `"BoldItalic"`.
+    pub fn from_style_name(style_name: &str) -> Style {
+        if style_name.contains("Italic")
+            || style_name.contains("Oblique")
+            || style_name.contains("Slanted")
+        {
+            Style::Italic
+        } else {
+            Style::Normal
+        }
+    }
+}
@@ -8,8 +8,40 @@ use std::f64;
 // FIXME: replace this with an instance of `Glyph`.
 pub const DASH_GLYPH: char = '-';
 
-/// The width a whitespace.
-pub const SPACE_WIDTH: Pt = Pt(5.0);
+/// The stretchability of a whitespace, relative to the width of the space of the font.
+pub const SPACE_STRETCH: f64 = 1.0;
+
+/// The shrinkability of a whitespace, relative to the width of the space of the font.
+pub const SPACE_SHRINK: f64 = 0.5;
+
+/// The chars that end a sentence, after which a whitespace is wider, like after TeX's space
+/// factor of 3000.
+pub const SENTENCE_ENDS: &[char] = &['.', '!', '?'];
+
+/// The width added to a whitespace that ends a sentence, relative to the width of the space of
+/// the font, like TeX's `\fontdimen7`.
+pub const SENTENCE_EXTRA_SPACE: f64 = 1.0 / 3.0;
+
+/// How much more a whitespace that ends a sentence stretches, and less it shrinks.
+pub const SENTENCE_SPACE_FACTOR: f64 = 3.0;
+
+/// The chars that end a clause, after which a whitespace is scaled by the clause space factor.
+pub const CLAUSE_ENDS: &[char] = &[':', ';'];
+
+/// The default space factor after the end of a clause, between the one of an interword space
+/// and the one of a sentence space, like TeX's space factor of 2000 after a colon.
+pub const CLAUSE_SPACE_FACTOR: f64 = 2.0;
+
+/// The penalty of a break at a line break of the source, when they are kept as breakpoints,
+/// which is negative so that the lines prefer them without being forced to break there.
+pub const SOURCE_BREAK_PENALTY: f64 = -50.0;
+
+/// The default abbreviations, whose dots don't end a sentence.
+pub const DEFAULT_ABBREVIATIONS: &[&str] = &["e.g.", "i.e.", "e. g.", "i. e.", "cf.", "vs."];
+
+/// The default distance between two tab stops, and the width of a tabulation until it is
+/// aligned to them, stretching and shrinking like a whitespace.
+pub const TAB_WIDTH: Pt = Pt(20.0);
 
 /// The default length of a line if no desired length is specified.
 pub const DEFAULT_LINE_LENGTH: Pt = Pt(680.0);
@@ -30,8 +62,74 @@ pub const MIN_ADJUSTMENT_RATIO: f64 = -1.0;
 /// Maximal adjustment ratio to consider a breakpoint is legal.
 pub const MAX_ADJUSTMENT_RATIO: f64 = 10.0;
 
+/// The badness of a line whose glue can't stretch enough, which is also the maximal badness
+/// of a line.
+pub const MAX_LINE_BADNESS: f64 = 10000.0;
+
 /// An infinite length in points.
 pub const PLUS_INFINITY: Pt = Pt(f64::INFINITY);
 
 /// The ideal spacing between two words.
 pub const IDEAL_SPACING: Pt = Pt(5.0);
+
+/// The default minimal width of a space between two words of a justified line.
+pub const DEFAULT_MIN_WORD_SPACE: Pt = Pt(2.5);
+
+/// The default maximal width of a space between two words of a justified line.
+pub const DEFAULT_MAX_WORD_SPACE: Pt = Pt(20.0);
+
+/// The default length by which a line can exceed the text without any warning, like TeX's
+/// `\hfuzz`.
+pub const DEFAULT_OVERFULL_TOLERANCE: Pt = Pt(0.1);
+
+/// The default badness above which a loose line raises a warning, like TeX's `\hbadness`.
+pub const DEFAULT_UNDERFULL_BADNESS: f64 = 1000.0;
+
+/// The precision of the font size found to fit a text in a box.
+pub const FIT_SIZE_PRECISION: Pt = Pt(0.01);
+
+// Title constants.
+/// How much larger than the body text a title of level 0 is, relative to the body size.
+pub const TITLE_SIZE_BOOST: f64 = 1.2;
+
+/// The ratio between the size boosts of two consecutive title levels.
+pub const TITLE_SIZE_RATIO: f64 = 0.75;
+
+// Vertical spacing constants.
+/// The default space after a paragraph.
+pub const DEFAULT_PARAGRAPH_SKIP: Pt = Pt(20.0);
+
+/// The default space after an item of a list.
+pub const DEFAULT_LIST_ITEM_SKIP: Pt = Pt(0.0);
+
+/// The default space after a list.
+pub const DEFAULT_LIST_SKIP: Pt = Pt(10.0);
+
+// Font constants.
+/// The size of the font of the text of a document.
+pub const DEFAULT_FONT_SIZE: Pt = Pt(10.0);
+
+// Glyph constants.
+/// The default char drawn instead of the chars that the fonts can't draw.
+pub const DEFAULT_MISSING_GLYPH: char = '?';
+
+/// The glyph of the empty boxes that mark where annotations are attached to the text.
+pub const ANCHOR_GLYPH: char = '\u{FFFC}';
+
+// List constants.
+/// The indentation added by each level of nesting of a list.
+pub const LIST_INDENT: Pt = Pt(15.0);
+
+/// The default marker drawn before the items of an unordered list.
+pub const LIST_MARKER: char = '•';
+
+/// The marker drawn before the items of an unordered list when the font can't draw the
+/// configured one.
+pub const FALLBACK_LIST_MARKER: char = '-';
+
+// Pagination constants.
+/// The default penalty for breaking a page inside a paragraph.
+pub const DEFAULT_PAGE_BREAK_PENALTY: f64 = 150.0;
+
+/// The badness of an empty page, which is also the maximal badness of a page.
+pub const MAX_PAGE_BADNESS: f64 = 10000.0;
@@ -3,8 +3,17 @@
 pub mod constants;
 pub mod paragraphs;
 
+use crate::fonts::configuration::{FontConfig, TitleSizes};
 use crate::fonts::Font;
+use crate::layout::constants::DEFAULT_MISSING_GLYPH;
+use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+use crate::layout::paragraphs::spacing::Spacing;
+use crate::layout::paragraphs::utils::ast::itemize_ast;
+use crate::layout::paragraphs::Paragraph;
+use crate::parser::ast::Ast;
+use crate::Result;
 use printpdf::Pt;
+use spandex_hyphenation::{Language, Load, Standard};
 
 /// A glyph with its font style.
 #[derive(Debug, Clone)]
@@ -25,3 +34,92 @@ impl<'a> Glyph<'a> {
         Glyph { glyph, font, scale }
     }
 }
+
+/// The options of the itemization of a text.
+#[derive(Copy, Clone, Debug)]
+pub struct ItemizeOptions {
+    /// The language used to hyphenate the words.
+    pub language: Language,
+
+    /// The indentation of the first line.
+    pub indent: Pt,
+
+    /// The char drawn instead of the chars that the font can't draw.
+    pub missing_glyph: char,
+}
+
+impl Default for ItemizeOptions {
+    fn default() -> ItemizeOptions {
+        ItemizeOptions {
+            language: Language::EnglishUS,
+            indent: Pt(0.0),
+            missing_glyph: DEFAULT_MISSING_GLYPH,
+        }
+    }
+}
+
+/// Turns a text into a paragraph, ready to be broken into lines.
+///
+/// The paragraph is made of the following items:
+///   - an empty glue of the width of the indentation, if any,
+///   - a bounding box for each character of the text,
+///   - a glue, that can stretch and shrink, for each whitespace, wider after the end of a
+///     sentence and for tabulations, and empty for zero-width spaces,
+///   - a flagged penalty, as wide as the dash drawn there, at each point where a word can be
+///     hyphenated, and an empty one after each dash of the text,
+///   - a glue of infinite stretchability followed by a penalty of infinite negative cost, that
+///     end the paragraph by forcing a line break.
+///
+/// # Example
+///
+/// ```
+/// # use printpdf::Pt;
+/// # use spandex::document::configuration::Config;
+/// # use spandex::layout::{itemize, ItemizeOptions};
+/// # use spandex::layout::paragraphs::items::Content;
+/// # fn main() -> spandex::Result<()> {
+/// let (_, font_manager) = Config::with_title("Example").init()?;
+/// let font = font_manager.get("CMU Serif Roman").unwrap();
+///
+/// let paragraph = itemize("Hello world", font, Pt(10.0), &ItemizeOptions::default())?;
+///
+/// let boxes = paragraph
+///     .iter()
+///     .filter(|item| matches!(item.content, Content::BoundingBox(_)))
+///     .count();
+/// assert_eq!(boxes, 10);
+///
+/// let last = paragraph.items.last().unwrap();
+/// assert!(matches!(last.content, Content::Penalty { value, .. } if value == f64::NEG_INFINITY));
+/// # Ok(())
+/// # }
+/// ```
+pub fn itemize<'a>(
+    text: &str,
+    font: &'a Font,
+    size: Pt,
+    options: &ItemizeOptions,
+) -> Result<Paragraph<'a>> {
+    let dictionary = Standard::from_embedded(options.language)?;
+
+    let font_config = FontConfig {
+        regular: font,
+        bold: font,
+        italic: font,
+        bold_italic: font,
+        monospace: font,
+        missing_glyph: options.missing_glyph,
+        title_sizes: TitleSizes::default(),
+    };
+
+    let ast = Ast::Paragraph(vec![Ast::Text(text.to_owned())]);
+    Ok(itemize_ast(
+        &ast,
+        &font_config,
+        size,
+        &dictionary,
+        &HyphenationExceptions::default(),
+        &Spacing::default(),
+        options.indent,
+    ))
+}
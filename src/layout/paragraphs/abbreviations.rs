@@ -0,0 +1,71 @@
+//! This module contains the abbreviations, whose dots don't end a sentence.
+
+use std::ops::Range;
+
+use crate::layout::constants::DEFAULT_ABBREVIATIONS;
+
+/// The abbreviations of a document, like "e.g.", which are typeset as a single word.
+///
+/// The whitespace after an abbreviation is an interword space rather than a sentence space, and
+/// the whitespaces within one, as in "e. g.", can't break nor stretch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Abbreviations {
+    /// The abbreviations, with their dots and whitespaces.
+    words: Vec<String>,
+}
+
+impl Abbreviations {
+    /// Creates a list of abbreviations.
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Abbreviations {
+        Abbreviations {
+            words: words
+                .iter()
+                .map(|word| word.as_ref().to_string())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Returns the byte ranges of the abbreviations of a text.
+    ///
+    /// Abbreviations are only recognized as whole words, so "vs." is not found in "canvs.".
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+
+        for word in &self.words {
+            for (start, _) in text.match_indices(word.as_str()) {
+                let end = start + word.len();
+                let before = text[..start].chars().next_back();
+                let after = text[end..].chars().next();
+
+                if !before.map_or(false, char::is_alphanumeric)
+                    && !after.map_or(false, char::is_alphanumeric)
+                {
+                    ranges.push(start..end);
+                }
+            }
+        }
+
+        ranges
+    }
+}
+
+impl Default for Abbreviations {
+    fn default() -> Abbreviations {
+        Abbreviations::new(DEFAULT_ABBREVIATIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::paragraphs::abbreviations::Abbreviations;
+
+    #[test]
+    fn test_find() {
+        let abbreviations = Abbreviations::new(&["e.g.", "vs."]);
+        assert_eq!(abbreviations.find("see e.g. this"), vec![4..8]);
+        assert!(abbreviations.find("a canvs. b").is_empty());
+        assert_eq!(abbreviations.find("one vs. two"), vec![4..7]);
+        assert!(Abbreviations::new(&[""]).find("text").is_empty());
+    }
+}
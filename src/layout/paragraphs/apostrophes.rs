@@ -0,0 +1,41 @@
+//! This module contains the functions related to apostrophes.
+
+/// The typographic apostrophe.
+pub const APOSTROPHE: char = '’';
+
+/// Replaces the straight apostrophes between two letters, as in contractions, by typographic
+/// ones.
+///
+/// The other straight quotes are left untouched, so quoted text and code don't get mangled.
+pub fn curl_apostrophes(input: &str) -> String {
+    let chars = input.chars().collect::<Vec<_>>();
+
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let between_letters = i > 0
+                && chars[i - 1].is_alphabetic()
+                && chars.get(i + 1).map_or(false, |next| next.is_alphabetic());
+
+            if c == '\'' && between_letters {
+                APOSTROPHE
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::paragraphs::apostrophes::curl_apostrophes;
+
+    #[test]
+    fn test_curl_apostrophes() {
+        assert_eq!(curl_apostrophes("don't"), "don’t");
+        assert_eq!(curl_apostrophes("'quoted'"), "'quoted'");
+        assert_eq!(curl_apostrophes("the dogs' bones"), "the dogs' bones");
+        assert_eq!(curl_apostrophes("l'été"), "l’été");
+    }
+}
@@ -11,11 +11,14 @@ use petgraph::visit::Dfs;
 use petgraph::visit::IntoNodeIdentifiers;
 use printpdf::Pt;
 
-use crate::layout::paragraphs::items::{Content, Item, PositionedItem};
+use crate::layout::paragraphs::items::{Content, Item, PositionedItem, PositionedLine};
 use crate::layout::paragraphs::Paragraph;
 use crate::layout::Glyph;
 
-use crate::layout::constants::{MAX_ADJUSTMENT_RATIO, MIN_ADJUSTMENT_RATIO};
+use crate::layout::constants::{
+    DEFAULT_MAX_WORD_SPACE, DEFAULT_MIN_WORD_SPACE, MAX_ADJUSTMENT_RATIO, MAX_LINE_BADNESS,
+    MIN_ADJUSTMENT_RATIO,
+};
 use crate::layout::paragraphs::graph::Node;
 use crate::layout::paragraphs::utils::linebreak::{
     compute_adjustment_ratio, compute_adjustment_ratios_with_breakpoints,
@@ -23,6 +26,37 @@ use crate::layout::paragraphs::utils::linebreak::{
 };
 use crate::layout::paragraphs::utils::paragraphs::get_line_length;
 
+/// The limits of the width of the spaces between words, once a line is justified.
+///
+/// A glue whose natural width is outside of the limits keeps its natural width, but never
+/// stretches or shrinks further away from them.
+#[derive(Copy, Clone, Debug)]
+pub struct WordSpacing {
+    /// The minimal width of a space.
+    pub min: Pt,
+
+    /// The maximal width of a space.
+    pub max: Pt,
+}
+
+impl WordSpacing {
+    /// Clamps the adjusted width of a glue of some natural width.
+    pub fn clamp(self, natural: Pt, adjusted: Pt) -> Pt {
+        let min = natural.0.min(self.min.0);
+        let max = natural.0.max(self.max.0);
+        Pt(adjusted.0.max(min).min(max))
+    }
+}
+
+impl Default for WordSpacing {
+    fn default() -> WordSpacing {
+        WordSpacing {
+            min: DEFAULT_MIN_WORD_SPACE,
+            max: DEFAULT_MAX_WORD_SPACE,
+        }
+    }
+}
+
 /// Finds the optimal sequence of breakpoints that minimize
 /// the amount of demerits while breaking a paragraph down
 /// into lines.
@@ -86,16 +120,32 @@ pub fn algorithm<'a>(paragraph: &'a Paragraph<'a>, lines_length: &[Pt]) -> Vec<u
             continue;
         }
 
+        // A line that ends at a penalty also holds its width, e.g. a hyphen.
+        let break_width = match item.content {
+            Content::Penalty { .. } => item.width,
+            _ => Pt(0.0),
+        };
+
         // Update the set of active nodes.
 
         let mut feasible_breakpoints: Vec<(Node, NodeIndex)> = Vec::new();
         let mut node_to_remove: Vec<NodeIndex> = Vec::new();
 
+        // Whether the line from every active node to b is wider than its length even once
+        // shrunk, and the most recent of these nodes.
+        let mut all_overfull = true;
+        let mut latest_node: Option<NodeIndex> = None;
+
         for node in graph.node_identifiers() {
             if let Some(a) = graph.node_weight(node) {
                 let line_shrink = sum_shrink - a.total_shrink;
                 let line_stretch = sum_stretch - a.total_stretch;
-                let actual_width = sum_width - a.total_width;
+                let actual_width = sum_width - a.total_width + break_width;
+
+                all_overfull &= actual_width - line_shrink > get_line_length(lines_length, a.line);
+                if latest_node.map_or(true, |latest| graph[latest].index < a.index) {
+                    latest_node = Some(node);
+                }
 
                 let adjustment_ratio = compute_adjustment_ratio(
                     actual_width,
@@ -135,13 +185,41 @@ pub fn algorithm<'a>(paragraph: &'a Paragraph<'a>, lines_length: &[Pt]) -> Vec<u
             }
         }
 
+        // If no line can fit anymore, e.g. because a word is wider than the line, the line from
+        // the most recent node is allowed to overflow, so that the paragraph can still be broken.
+        if let (true, true, Some(node)) =
+            (feasible_breakpoints.is_empty(), all_overfull, latest_node)
+        {
+            let measures_sum = Measures {
+                width: sum_width,
+                shrinkability: sum_shrink,
+                stretchability: sum_stretch,
+            };
+
+            let new_node = create_node_for_feasible_breakpoint(
+                b,
+                &graph[node],
+                MIN_ADJUSTMENT_RATIO,
+                item,
+                &paragraph.items,
+                &measures_sum,
+            );
+
+            feasible_breakpoints.push((new_node, node));
+        }
+
         // If there is a feasible break at b, then append the best such break
         // as an active node.
         if !feasible_breakpoints.is_empty() {
             let (mut last_best_node, mut last_node_parent_id) = feasible_breakpoints[0];
 
+            // Breaks with equal demerits are ordered by the index of their parent, so that the
+            // earliest one is kept whatever the order in which the active nodes are visited.
+            let key =
+                |node: &Node, parent_id: NodeIndex| (node.total_demerits, graph[parent_id].index);
+
             for (node, parent_id) in feasible_breakpoints.iter() {
-                if node.total_demerits < last_best_node.total_demerits {
+                if key(node, *parent_id) < key(&last_best_node, last_node_parent_id) {
                     last_best_node = *node;
                     last_node_parent_id = *parent_id;
                 }
@@ -208,16 +286,40 @@ pub fn positionate_items<'a>(
     items: &[Item<'a>],
     line_lengths: &[Pt],
     breakpoints: &[usize],
-) -> Vec<Vec<PositionedItem<'a>>> {
+    word_spacing: WordSpacing,
+) -> Vec<PositionedLine<'a>> {
     let adjustment_ratios =
         compute_adjustment_ratios_with_breakpoints(items, line_lengths, breakpoints);
-    let mut lines_breakdown: Vec<Vec<PositionedItem>> = Vec::new();
+    let mut lines_breakdown: Vec<PositionedLine> = Vec::new();
 
     for breakpoint_line in 0..(breakpoints.len() - 1) {
         let mut positioned_items: Vec<PositionedItem> = Vec::new();
 
         let breakpoint_index = breakpoints[breakpoint_line];
-        let adjustment_ratio = adjustment_ratios[breakpoint_line].max(MIN_ADJUSTMENT_RATIO);
+        let mut adjustment_ratio = adjustment_ratios[breakpoint_line].max(MIN_ADJUSTMENT_RATIO);
+
+        // The last line of a paragraph, ended by a forced break, is set with its natural
+        // spacing instead of being stretched to the full width, unless the glue that fills it
+        // can't stretch to the full width, like a finite TeX's `\parfillskip`.
+        let end = breakpoints[breakpoint_line + 1];
+        let is_last_line = matches!(
+            items[end].content,
+            Content::Penalty { value, .. } if value == f64::NEG_INFINITY
+        );
+        let fills_line = match items[end.saturating_sub(1)].content {
+            Content::Glue { stretchability, .. } => {
+                stretchability.0 >= get_line_length(line_lengths, breakpoint_line).0
+            }
+            _ => true,
+        };
+        if is_last_line && fills_line {
+            adjustment_ratio = adjustment_ratio.min(0.0);
+        }
+        // A break after a dash of the text is empty, only the ones inserted by the hyphenation
+        // draw a dash.
+        let hyphenated = matches!(items[end].content, Content::Penalty { flagged: true, .. })
+            && items[end].width > Pt(0.0)
+            && !is_last_line;
         let mut horizontal_offset = Pt(0.0);
         let beginning = if breakpoint_line == 0 {
             breakpoint_index
@@ -230,7 +332,7 @@ pub fn positionate_items<'a>(
         let range = items
             .iter()
             .enumerate()
-            .take(breakpoints[breakpoint_line + 1])
+            .take(breakpoints[breakpoint_line + 1] + 1)
             .skip(beginning);
 
         for (p, item) in range {
@@ -261,7 +363,7 @@ pub fn positionate_items<'a>(
 
                         // TODO: add an option to handle the inclusion of glue.
 
-                        horizontal_offset += gap;
+                        horizontal_offset += word_spacing.clamp(width, gap);
                     }
                 }
                 Content::Penalty { .. } => {
@@ -283,7 +385,17 @@ pub fn positionate_items<'a>(
             }
         }
 
-        lines_breakdown.push(positioned_items);
+        let line_end = positioned_items
+            .last()
+            .map_or(Pt(0.0), |item| item.horizontal_offset + item.width);
+
+        lines_breakdown.push(PositionedLine {
+            items: positioned_items,
+            overflow: line_end - get_line_length(line_lengths, breakpoint_line),
+            badness: (100.0 * adjustment_ratio.max(0.0).powi(3)).min(MAX_LINE_BADNESS),
+            adjustment_ratio,
+            hyphenated,
+        });
     }
 
     lines_breakdown
@@ -297,8 +409,11 @@ mod tests {
     use spandex_hyphenation::*;
 
     use crate::document::configuration::Config;
-    use crate::layout::paragraphs::engine::algorithm;
-    use crate::layout::paragraphs::items::Content;
+    use crate::layout::constants::TAB_WIDTH;
+    use crate::layout::paragraphs::engine::{algorithm, positionate_items, WordSpacing};
+    use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+    use crate::layout::paragraphs::items::{Content, Item};
+    use crate::layout::paragraphs::spacing::Spacing;
     use crate::layout::paragraphs::utils::ast::itemize_ast;
     use crate::layout::paragraphs::utils::linebreak::{
         compute_adjustment_ratios_with_breakpoints, find_legal_breakpoints,
@@ -312,33 +427,350 @@ mod tests {
         let ast = Ast::Paragraph(vec![Ast::Text(words.into())]);
 
         let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
 
         let (_, font_manager) = Config::with_title("Test").init()?;
         let config = font_manager.default_config();
 
         // No indentation, meaning no leading empty box.
-        let paragraph = itemize_ast(&ast, &config, Pt(10.0), &en_us, Pt(0.0));
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
         assert_eq!(paragraph.items.len(), 31);
 
         // Indentated paragraph, implying the presence of a leading empty box.
-        let paragraph = itemize_ast(&ast, &config, Pt(10.0), &en_us, Pt(7.5));
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(7.5),
+        );
         assert_eq!(paragraph.items.len(), 32);
 
         Ok(())
     }
 
+    #[test]
+    fn test_tab_and_zero_width_space_itemization() -> Result<()> {
+        let words = "Lorem\tipsum\u{200B}dolor\u{200D}sit\u{0C}am\u{07}et.";
+        let ast = Ast::Paragraph(vec![Ast::Text(words.into())]);
+
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+
+        // The zero-width joiner and the bell are not laid out.
+        let boxes = paragraph
+            .items
+            .iter()
+            .filter(|item| matches!(item.content, Content::BoundingBox(_)))
+            .count();
+        assert_eq!(boxes, 23);
+
+        // The tab, the zero-width space and the form feed split the words, the joiner doesn't.
+        let glues = paragraph
+            .items
+            .iter()
+            .filter(|item| matches!(item.content, Content::Glue { .. }))
+            .map(|item| item.width.0)
+            .collect::<Vec<_>>();
+        let space = config.regular.char_width(' ', Pt(10.0));
+        assert_eq!(glues, vec![TAB_WIDTH.0, 0.0, space.0, 0.0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_spacing_clamp() -> Result<()> {
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        // A line broken after two short words is very loose.
+        let ast = Ast::Text("ab cd ef".into());
+        let mut paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+        paragraph.push(Item::penalty(Pt(0.0), f64::NEG_INFINITY, false));
+        let breakpoints = vec![0, 5, paragraph.items.len() - 1];
+
+        let word_spacing = WordSpacing {
+            min: Pt(2.0),
+            max: Pt(12.0),
+        };
+        let lines = positionate_items(&paragraph.items, &[Pt(400.0)], &breakpoints, word_spacing);
+
+        let b = &lines[0].items[1];
+        let c = &lines[0].items[2];
+        let gap = c.horizontal_offset - b.horizontal_offset - b.width;
+        assert!((gap.0 - 12.0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_break_at_explicit_hyphen() -> Result<()> {
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        let dashes = |text: &str, break_after: &str| {
+            let ast = Ast::Paragraph(vec![Ast::Text(text.into())]);
+            let paragraph = itemize_ast(
+                &ast,
+                &config,
+                Pt(10.0),
+                &en_us,
+                &exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+
+            // Breaks at the first flagged penalty after some letters.
+            let glyphs = break_after.chars().count();
+            let mut seen = 0;
+            let index = paragraph
+                .items
+                .iter()
+                .position(|item| match item.content {
+                    Content::BoundingBox(_) => {
+                        seen += 1;
+                        false
+                    }
+                    Content::Penalty { flagged, .. } => flagged && seen == glyphs,
+                    _ => false,
+                })
+                .unwrap();
+
+            let breakpoints = vec![0, index, paragraph.items.len() - 1];
+            let word_spacing = WordSpacing {
+                min: Pt(2.0),
+                max: Pt(12.0),
+            };
+            let lines =
+                positionate_items(&paragraph.items, &[Pt(400.0)], &breakpoints, word_spacing);
+            assert_eq!(lines.len(), 2);
+
+            lines
+                .iter()
+                .map(|line| line.items.iter().filter(|x| x.glyph.glyph == '-').count())
+                .collect::<Vec<_>>()
+        };
+
+        // The dash of the text is drawn once, at the end of the first line.
+        assert_eq!(dashes("mother-in-law", "mother-"), vec![1, 1]);
+
+        // A break inserted by hyphenation draws a dash.
+        assert_eq!(dashes("hyphenation", "hy"), vec![1, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_algorithm_is_deterministic() -> Result<()> {
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        // Identical words give many breaks with equal demerits.
+        let ast = Ast::Paragraph(vec![Ast::Text("word ".repeat(60))]);
+        let lines_length = vec![Pt(100.0)];
+
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+        let expected = algorithm(&paragraph, &lines_length);
+        assert!(expected.len() > 2);
+
+        for _ in 0..10 {
+            let paragraph = itemize_ast(
+                &ast,
+                &config,
+                Pt(10.0),
+                &en_us,
+                &exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+            assert_eq!(algorithm(&paragraph, &lines_length), expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_last_line_natural_spacing() -> Result<()> {
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        // The last line is much shorter than the line, without any glue to fill it.
+        let ast = Ast::Text("ab cd ef".into());
+        let mut paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+        paragraph.push(Item::penalty(Pt(0.0), f64::NEG_INFINITY, false));
+        let breakpoints = vec![0, paragraph.items.len() - 1];
+
+        let lines = positionate_items(
+            &paragraph.items,
+            &[Pt(400.0)],
+            &breakpoints,
+            WordSpacing::default(),
+        );
+
+        let space = config.regular.char_width(' ', Pt(10.0));
+        let items = &lines[0].items;
+        for (before, after) in [(1, 2), (3, 4)] {
+            let gap = items[after].horizontal_offset
+                - items[before].horizontal_offset
+                - items[before].width;
+            assert!((gap.0 - space.0).abs() < 1e-9);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> Result<()> {
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        // An unbreakable token is much wider than the line.
+        let ast = Ast::Paragraph(vec![Ast::Text("0123456789".repeat(10))]);
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+        let breakpoints = vec![0, paragraph.items.len() - 1];
+        let lines_length = [Pt(100.0)];
+
+        let lines = positionate_items(
+            &paragraph.items,
+            &lines_length,
+            &breakpoints,
+            WordSpacing::default(),
+        );
+
+        let width = paragraph.items[..100]
+            .iter()
+            .fold(Pt(0.0), |acc, item| acc + item.width);
+        assert!(lines[0].overflow.0 > 0.0);
+        assert!((lines[0].overflow.0 - (width - lines_length[0]).0).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_space_width_scales_with_size() -> Result<()> {
+        let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let config = font_manager.default_config();
+
+        let ast = Ast::Text("a b".into());
+        let space = |size| {
+            let paragraph = itemize_ast(
+                &ast,
+                &config,
+                size,
+                &en_us,
+                &exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+            paragraph
+                .items
+                .iter()
+                .find(|item| matches!(item.content, Content::Glue { .. }))
+                .unwrap()
+                .width
+                .0
+        };
+
+        assert!(space(Pt(10.0)) > 0.0);
+        assert!((space(Pt(20.0)) - 2.0 * space(Pt(10.0))).abs() < 1e-9);
+
+        Ok(())
+    }
+
     #[test]
     fn test_legal_breakpoints() -> Result<()> {
         let words = "Lorem ipsum dolor sit amet.";
         let ast = Ast::Paragraph(vec![Ast::Text(words.into())]);
 
         let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
 
         let (_, font_manager) = Config::with_title("Test").init()?;
         let config = font_manager.default_config();
 
         // Indentated paragraph, implying the presence of a leading empty box.
-        let paragraph = itemize_ast(&ast, &config, Pt(10.0), &en_us, Pt(7.5));
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(10.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            Pt(7.5),
+        );
 
         let legal_breakpoints = find_legal_breakpoints(&paragraph);
         // [ ] Lorem ip-sum do-lor sit amet.
@@ -386,13 +818,22 @@ mod tests {
         let ast = Ast::Paragraph(vec![Ast::Text(words.into())]);
 
         let en_us = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
 
         let (_, font_manager) = Config::with_title("Test").init()?;
         let config = font_manager.default_config();
 
         let indentation = Pt(18.0);
 
-        let paragraph = itemize_ast(&ast, &config, Pt(12.0), &en_us, indentation);
+        let paragraph = itemize_ast(
+            &ast,
+            &config,
+            Pt(12.0),
+            &en_us,
+            &exceptions,
+            &Spacing::default(),
+            indentation,
+        );
 
         let lines_length = vec![Pt(400.0)];
         let breakpoints = algorithm(&paragraph, &lines_length);
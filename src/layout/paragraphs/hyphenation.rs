@@ -0,0 +1,86 @@
+//! This module contains the exceptions to the hyphenation dictionaries.
+
+use std::collections::HashMap;
+
+/// Words whose hyphenation is given by the user instead of the dictionary, like TeX's
+/// `\hyphenation{...}`.
+#[derive(Clone, Debug, Default)]
+pub struct HyphenationExceptions {
+    /// The indices of the chars before which each word can be broken, by lowercase word.
+    breaks: HashMap<String, Vec<usize>>,
+
+    /// Whether the capitalized words, often proper nouns, are left unbroken unless they are
+    /// exceptions.
+    skip_capitalized: bool,
+}
+
+impl HyphenationExceptions {
+    /// Creates exceptions from words whose breaks are marked with dashes, e.g. `"data-base"`.
+    pub fn new<S: AsRef<str>>(words: &[S]) -> HyphenationExceptions {
+        let mut breaks = HashMap::new();
+
+        for word in words {
+            let mut letters = String::new();
+            let mut indices = vec![];
+            let mut count = 0;
+
+            for c in word.as_ref().chars() {
+                if c == '-' {
+                    indices.push(count);
+                } else {
+                    letters.push(c);
+                    count += 1;
+                }
+            }
+
+            breaks.insert(letters.to_lowercase(), indices);
+        }
+
+        HyphenationExceptions {
+            breaks,
+            skip_capitalized: false,
+        }
+    }
+
+    /// Sets whether the words starting with an uppercase letter are hyphenated by the
+    /// dictionary.
+    pub fn set_hyphenate_capitalized(&mut self, hyphenate_capitalized: bool) {
+        self.skip_capitalized = !hyphenate_capitalized;
+    }
+
+    /// Returns whether a word that is not an exception is hyphenated by the dictionary.
+    pub fn uses_dictionary(&self, word: &str) -> bool {
+        !self.skip_capitalized || !word.chars().next().map_or(false, char::is_uppercase)
+    }
+
+    /// Returns the indices of the chars before which a word can be broken, if it is an
+    /// exception.
+    pub fn breaks(&self, word: &str) -> Option<&[usize]> {
+        self.breaks.get(&word.to_lowercase()).map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+
+    #[test]
+    fn test_exceptions() {
+        let exceptions = HyphenationExceptions::new(&["data-base", "Hy-phen-ation"]);
+
+        assert_eq!(exceptions.breaks("database"), Some(&[4][..]));
+        assert_eq!(exceptions.breaks("DataBase"), Some(&[4][..]));
+        assert_eq!(exceptions.breaks("hyphenation"), Some(&[2, 6][..]));
+        assert_eq!(exceptions.breaks("data"), None);
+    }
+
+    #[test]
+    fn test_capitalized() {
+        let mut exceptions = HyphenationExceptions::default();
+        assert!(exceptions.uses_dictionary("Paris"));
+
+        exceptions.set_hyphenate_capitalized(false);
+        assert!(!exceptions.uses_dictionary("Paris"));
+        assert!(exceptions.uses_dictionary("paris"));
+    }
+}
@@ -3,11 +3,13 @@
 
 use printpdf::Pt;
 
+use crate::fonts::Font;
+use crate::layout::constants::ANCHOR_GLYPH;
 use crate::layout::Glyph;
 
 /// Top abstraction of an item, which is a specification for a box, a glue
 /// or a penalty.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Item<'a> {
     /// The width of the item in scaled units.
     pub width: Pt,
@@ -17,7 +19,7 @@ pub struct Item<'a> {
 }
 
 /// Possible available types for an item.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Content<'a> {
     /// A bounding box refers to something that is meant to be typeset.
     ///
@@ -57,6 +59,14 @@ impl<'a> Item<'a> {
         }
     }
 
+    /// Creates an empty box that marks where an annotation is attached to the text.
+    pub fn anchor(font: &'a Font, scale: Pt) -> Item<'a> {
+        Item {
+            width: Pt(0.0),
+            content: Content::BoundingBox(Glyph::new(ANCHOR_GLYPH, font, scale)),
+        }
+    }
+
     /// Creates some glue.
     pub fn glue(ideal_spacing: Pt, stretchability: Pt, shrinkability: Pt) -> Item<'a> {
         Item {
@@ -96,3 +106,24 @@ pub struct PositionedItem<'a> {
     /// The glyph that should be layed out within this item.
     pub glyph: Glyph<'a>,
 }
+
+/// Holds the items of a line that are ready to be rendered.
+#[derive(Debug)]
+pub struct PositionedLine<'a> {
+    /// The items of the line.
+    pub items: Vec<PositionedItem<'a>>,
+
+    /// How much the line exceeds its length once justified, which is negative or zero if the
+    /// line fits.
+    pub overflow: Pt,
+
+    /// How much the glue of the line is stretched, from 0 for a line with its natural spacing
+    /// to `MAX_LINE_BADNESS` for a line that can't be filled.
+    pub badness: f64,
+
+    /// The ratio by which the glue of the line is stretched, or shrunk if it is negative.
+    pub adjustment_ratio: f64,
+
+    /// Whether the line is broken inside a word, after a hyphen.
+    pub hyphenated: bool,
+}
@@ -1,24 +1,54 @@
 //! This module contains the trait and implementation of justification algorithms.
 
+use std::f64;
+
 use printpdf::Pt;
 
 use crate::layout::constants::IDEAL_SPACING;
-use crate::layout::paragraphs::engine::{algorithm, positionate_items};
+use crate::layout::paragraphs::engine::{algorithm, positionate_items, WordSpacing};
 use crate::layout::paragraphs::items::Content;
 use crate::layout::paragraphs::Paragraph;
 use crate::layout::Glyph;
 
+/// A line of a justified paragraph.
+#[derive(Debug)]
+pub struct JustifiedLine<'a> {
+    /// The glyphs of the line, with their horizontal offsets.
+    pub glyphs: Vec<(Glyph<'a>, Pt)>,
+
+    /// How much the line exceeds the text width, which is negative or zero if the line fits.
+    pub overflow: Pt,
+
+    /// How much the glue of the line is stretched, as computed by the line breaking engine.
+    pub badness: f64,
+
+    /// The ratio by which the glue of the line is stretched, or shrunk if it is negative.
+    pub adjustment_ratio: f64,
+
+    /// Whether the line is broken inside a word, after a hyphen.
+    pub hyphenated: bool,
+}
+
 /// An algorithm that justifies a paragraph.
 pub trait Justifier {
-    /// Justifies the paragraph passed as parameter.
-    fn justify<'a>(paragraph: &'a Paragraph<'a>, text_width: Pt) -> Vec<Vec<(Glyph<'a>, Pt)>>;
+    /// Justifies the paragraph passed as parameter, keeping the spaces between words within
+    /// some limits.
+    fn justify<'a>(
+        paragraph: &'a Paragraph<'a>,
+        text_width: Pt,
+        word_spacing: WordSpacing,
+    ) -> Vec<JustifiedLine<'a>>;
 }
 
 /// A naive justifier, that goes to the next line once a word overtakes the text width.
 pub struct NaiveJustifier;
 
 impl Justifier for NaiveJustifier {
-    fn justify<'a>(paragraph: &'a Paragraph<'a>, text_width: Pt) -> Vec<Vec<(Glyph<'a>, Pt)>> {
+    fn justify<'a>(
+        paragraph: &'a Paragraph<'a>,
+        text_width: Pt,
+        word_spacing: WordSpacing,
+    ) -> Vec<JustifiedLine<'a>> {
         let mut ret = vec![];
         let mut current_line = vec![];
         let mut current_word = vec![];
@@ -53,7 +83,8 @@ impl Justifier for NaiveJustifier {
                 let available_space = text_width - occupied_width;
 
                 let word_space = if current_line.len() > 1 {
-                    available_space / (current_line.len() - 1) as f64
+                    let word_space = available_space / (current_line.len() - 1) as f64;
+                    word_spacing.clamp(IDEAL_SPACING, word_space)
                 } else {
                     IDEAL_SPACING
                 };
@@ -73,7 +104,14 @@ impl Justifier for NaiveJustifier {
                     current_x += word_space;
                 }
 
-                ret.push(final_line);
+                // The naive justifier doesn't measure how loose its lines are.
+                ret.push(JustifiedLine {
+                    glyphs: final_line,
+                    overflow: current_x - word_space - text_width,
+                    badness: 0.0,
+                    adjustment_ratio: 0.0,
+                    hyphenated: false,
+                });
 
                 current_line = vec![last_word];
             }
@@ -93,7 +131,13 @@ impl Justifier for NaiveJustifier {
             current_x += IDEAL_SPACING;
         }
 
-        ret.push(final_line);
+        ret.push(JustifiedLine {
+            glyphs: final_line,
+            overflow: current_x - IDEAL_SPACING - text_width,
+            badness: 0.0,
+            adjustment_ratio: 0.0,
+            hyphenated: false,
+        });
 
         ret
     }
@@ -103,21 +147,119 @@ impl Justifier for NaiveJustifier {
 pub struct LatexJustifier;
 
 impl Justifier for LatexJustifier {
-    fn justify<'a>(paragraph: &Paragraph<'a>, text_width: Pt) -> Vec<Vec<(Glyph<'a>, Pt)>> {
+    fn justify<'a>(
+        paragraph: &Paragraph<'a>,
+        text_width: Pt,
+        word_spacing: WordSpacing,
+    ) -> Vec<JustifiedLine<'a>> {
         let lines_length = vec![text_width];
         let breakpoints = algorithm(paragraph, &lines_length);
-        let positioned_items = positionate_items(&paragraph.items, &lines_length, &breakpoints);
+        let items = paragraph.align_tabs(&breakpoints);
+        let positioned_items = positionate_items(&items, &lines_length, &breakpoints, word_spacing);
 
         let mut output = vec![];
 
-        for items in positioned_items {
+        for positioned_line in positioned_items {
             let mut line = vec![];
-            for item in items {
+            for item in positioned_line.items {
                 line.push((item.glyph.clone(), item.horizontal_offset));
             }
-            output.push(line);
+            output.push(JustifiedLine {
+                glyphs: line,
+                overflow: positioned_line.overflow,
+                badness: positioned_line.badness,
+                adjustment_ratio: positioned_line.adjustment_ratio,
+                hyphenated: positioned_line.hyphenated,
+            });
+        }
+
+        output
+    }
+}
+
+/// A justifier that leaves the lines ragged on the right, like the lines of some code.
+///
+/// A line is broken at its last whitespace that fits in the text width, and keeps its natural
+/// spacing. The lines are never broken at a penalty unless it is forced, so the words are never
+/// hyphenated.
+pub struct RaggedJustifier;
+
+impl Justifier for RaggedJustifier {
+    fn justify<'a>(
+        paragraph: &Paragraph<'a>,
+        text_width: Pt,
+        _word_spacing: WordSpacing,
+    ) -> Vec<JustifiedLine<'a>> {
+        let mut output = vec![];
+        let mut line: Vec<(Glyph<'a>, Pt)> = vec![];
+        let mut current_x = Pt(0.0);
+        let mut line_end = Pt(0.0);
+
+        // The number of glyphs before the last breakpoint of the line, the end of the text
+        // before it, and the start of the text after it.
+        let mut breakpoint: Option<(usize, Pt, Pt)> = None;
+        let mut after_box = false;
+
+        for item in &paragraph.items {
+            match item.content {
+                Content::BoundingBox(ref glyph) => {
+                    if let (true, Some((count, end, start))) =
+                        (current_x + item.width > text_width, breakpoint)
+                    {
+                        let rest = line.split_off(count);
+                        output.push(ragged_line(line, end, text_width));
+                        line = rest
+                            .into_iter()
+                            .map(|(glyph, x)| (glyph, x - start))
+                            .collect();
+                        current_x -= start;
+                        breakpoint = None;
+                    }
+
+                    line.push((glyph.clone(), current_x));
+                    current_x += item.width;
+                    line_end = current_x;
+                }
+                Content::Glue { .. } => {
+                    // The whitespaces that follow a breakpoint are dropped with it.
+                    match breakpoint {
+                        _ if after_box => {
+                            breakpoint = Some((line.len(), current_x, current_x + item.width))
+                        }
+                        Some((count, _, ref mut start)) if count == line.len() => {
+                            *start = current_x + item.width
+                        }
+                        _ => (),
+                    }
+                    current_x += item.width;
+                }
+                Content::Penalty { value, .. } if value == f64::NEG_INFINITY => {
+                    output.push(ragged_line(std::mem::take(&mut line), line_end, text_width));
+                    current_x = Pt(0.0);
+                    line_end = Pt(0.0);
+                    breakpoint = None;
+                }
+                Content::Penalty { .. } => (),
+            }
+
+            after_box = matches!(item.content, Content::BoundingBox(_));
+        }
+
+        if !line.is_empty() {
+            output.push(ragged_line(line, line_end, text_width));
         }
 
         output
     }
 }
+
+/// Returns a line with its natural spacing, whose text ends at some position.
+fn ragged_line(glyphs: Vec<(Glyph, Pt)>, end: Pt, text_width: Pt) -> JustifiedLine {
+    JustifiedLine {
+        glyphs,
+        overflow: end - text_width,
+        badness: 0.0,
+        adjustment_ratio: 0.0,
+        hyphenated: false,
+    }
+}
@@ -0,0 +1,102 @@
+//! This module contains the kerning of specific pairs of chars, set by hand.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use printpdf::Pt;
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The adjustments of the gap between some pairs of adjacent chars, which correct the spacing
+/// that the fonts get wrong.
+///
+/// They are written as a table of two chars and a length in pt, negative to tighten the pair,
+/// e.g. `AV = -1.5`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KernOverrides {
+    /// The adjustment of each pair of chars.
+    pairs: HashMap<(char, char), Pt>,
+}
+
+impl KernOverrides {
+    /// Creates the kerning overrides of some pairs of chars.
+    pub fn new(pairs: HashMap<(char, char), Pt>) -> KernOverrides {
+        KernOverrides { pairs }
+    }
+
+    /// Returns the adjustment of the gap between two chars, if they have one.
+    pub fn get(&self, left: char, right: char) -> Option<Pt> {
+        self.pairs.get(&(left, right)).copied()
+    }
+
+    /// Returns whether no pair has an adjustment.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl Serialize for KernOverrides {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The pairs are sorted, so that the config is written the same way every time.
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|((left, right), adjustment)| (format!("{}{}", left, right), adjustment.0))
+            .collect::<BTreeMap<_, _>>();
+        pairs.serialize(serializer)
+    }
+}
+
+impl<'a> Deserialize<'a> for KernOverrides {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<KernOverrides, D::Error> {
+        deserializer.deserialize_map(KernOverridesVisitor)
+    }
+}
+
+/// Visitor for the `KernOverrides` structure.
+pub struct KernOverridesVisitor;
+
+impl<'a> Visitor<'a> for KernOverridesVisitor {
+    type Value = KernOverrides;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a table of pairs of chars, like \"AV\", and their adjustment in pt")
+    }
+
+    fn visit_map<M: MapAccess<'a>>(self, mut map: M) -> Result<KernOverrides, M::Error> {
+        let mut pairs = HashMap::new();
+
+        while let Some((key, adjustment)) = map.next_entry::<String, f64>()? {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(left), Some(right), None) => {
+                    pairs.insert((left, right), Pt(adjustment));
+                }
+                _ => return Err(de::Error::invalid_value(de::Unexpected::Str(&key), &self)),
+            }
+        }
+
+        Ok(KernOverrides { pairs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use printpdf::Pt;
+
+    use crate::layout::paragraphs::kerning::KernOverrides;
+
+    #[test]
+    fn test_round_trip() {
+        let mut pairs = HashMap::new();
+        pairs.insert(('A', 'V'), Pt(-1.5));
+        let overrides = KernOverrides::new(pairs);
+
+        let value = toml::Value::try_from(&overrides).unwrap();
+        assert_eq!(value.to_string(), "AV = -1.5\n");
+        assert_eq!(value.try_into::<KernOverrides>().unwrap(), overrides);
+        assert!(toml::from_str::<KernOverrides>("AVA = 1.0").is_err());
+    }
+}
@@ -1,109 +1,143 @@
 //! This module contains the functions related to ligatures.
 
-/// Ligates a string.
+use serde::{Deserialize, Serialize};
+
+/// The ligatures, with the letters they replace.
+///
+/// Longer ligatures come first so that they take precedence over the shorter ones.
 ///
 /// From https://en.wikipedia.org/wiki/List_of_precomposed_Latin_characters_in_Unicode#Digraphs_and_ligatures
+const LIGATURES: &[(&str, char)] = &[
+    ("ffi", 'ﬃ'),
+    ("ffl", 'ﬄ'),
+    ("ff", 'ﬀ'),
+    ("fi", 'ﬁ'),
+    ("fl", 'ﬂ'),
+    ("IJ", 'Ĳ'),
+    ("ij", 'ĳ'),
+    ("LJ", 'Ǉ'),
+    ("Lj", 'ǈ'),
+    ("lj", 'ǉ'),
+    ("NJ", 'Ǌ'),
+    ("Nj", 'ǋ'),
+    ("nj", 'ǌ'),
+];
+
+/// The char that joins two letters into their ligature.
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// The set of ligatures applied to the text of a document.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ligatures {
+    /// All the ligatures.
+    All,
+
+    /// No ligature at all.
+    None,
+
+    /// All the ligatures but some, given by the letters they replace, e.g. `"fi"`.
+    Except(Vec<String>),
+}
+
+impl Default for Ligatures {
+    fn default() -> Ligatures {
+        Ligatures::All
+    }
+}
+
+impl Ligatures {
+    /// Returns whether the ligature replacing some letters is applied.
+    pub fn applies(&self, letters: &str) -> bool {
+        match self {
+            Ligatures::All => true,
+            Ligatures::None => false,
+            Ligatures::Except(excluded) => excluded.iter().all(|x| x != letters),
+        }
+    }
+}
+
+/// Returns the letters replaced by a ligature, or `None` if the char is not a ligature.
+pub fn letters(ligature: char) -> Option<&'static str> {
+    LIGATURES
+        .iter()
+        .find(|(_, c)| *c == ligature)
+        .map(|(letters, _)| *letters)
+}
+
+/// Ligates a string with all the ligatures.
 pub fn ligature(input: &str) -> String {
+    ligature_with(input, &Ligatures::All)
+}
+
+/// Ligates a string with a set of ligatures.
+///
+/// A zero-width joiner between the letters of a ligature applies it even if it is not in the
+/// set, while a zero-width non-joiner keeps the letters apart.
+pub fn ligature_with(input: &str, ligatures: &Ligatures) -> String {
     let mut output = String::new();
+    let mut rest = input;
 
-    let mut first = input.chars();
-    let mut second = input.chars().skip(1);
-    let mut third = input.chars().skip(2);
-
-    loop {
-        let f = first.next();
-        let s = second.next();
-        let t = third.next();
-
-        match (f, s, t) {
-            (Some('f'), Some('f'), Some('i')) => {
-                output.push('ﬃ');
-                first.next();
-                second.next();
-                third.next();
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('f'), Some('f'), Some('l')) => {
-                output.push('ﬄ');
-                first.next();
-                second.next();
-                third.next();
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('f'), Some('f'), _) => {
-                output.push('ﬀ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('f'), Some('i'), _) => {
-                output.push('ﬁ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('f'), Some('l'), _) => {
-                output.push('ﬂ');
-                first.next();
-                second.next();
-                third.next();
+    'chars: while let Some(c) = rest.chars().next() {
+        for (letters, ligature) in LIGATURES {
+            match joined_letters(rest, letters) {
+                Some((len, joined)) if joined || ligatures.applies(letters) => {
+                    output.push(*ligature);
+                    rest = &rest[len..];
+                    continue 'chars;
+                }
+                _ => (),
             }
-            (Some('I'), Some('J'), _) => {
-                output.push('Ĳ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('i'), Some('j'), _) => {
-                output.push('ĳ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('L'), Some('J'), _) => {
-                output.push('Ǉ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('L'), Some('j'), _) => {
-                output.push('ǈ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('l'), Some('j'), _) => {
-                output.push('ǉ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('N'), Some('J'), _) => {
-                output.push('Ǌ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('N'), Some('j'), _) => {
-                output.push('ǋ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some('n'), Some('j'), _) => {
-                output.push('ǌ');
-                first.next();
-                second.next();
-                third.next();
-            }
-            (Some(c), _, _) => output.push(c),
-            _ => break,
         }
+
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
     }
 
     output
 }
+
+/// Returns the length of the letters at the start of a string, which may be separated by
+/// zero-width joiners, and whether they are.
+fn joined_letters(input: &str, letters: &str) -> Option<(usize, bool)> {
+    let mut len = 0;
+    let mut joined = false;
+
+    for (i, letter) in letters.chars().enumerate() {
+        if i > 0 && input[len..].starts_with(ZERO_WIDTH_JOINER) {
+            len += ZERO_WIDTH_JOINER.len_utf8();
+            joined = true;
+        }
+
+        if !input[len..].starts_with(letter) {
+            return None;
+        }
+        len += letter.len_utf8();
+    }
+
+    Some((len, joined))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::paragraphs::ligatures::{letters, ligature, ligature_with, Ligatures};
+
+    #[test]
+    fn test_ligatures() {
+        assert_eq!(ligature("office flow"), "oﬃce ﬂow");
+        assert_eq!(
+            ligature_with("office flow", &Ligatures::None),
+            "office flow"
+        );
+
+        let except = Ligatures::Except(vec![String::from("fi")]);
+        assert_eq!(ligature_with("fine flow", &except), "fine ﬂow");
+        assert_eq!(ligature_with("office", &except), "oﬃce");
+
+        let joined = "f\u{200D}ine f\u{200C}low";
+        assert_eq!(ligature_with(joined, &Ligatures::None), "ﬁne f\u{200C}low");
+
+        assert_eq!(letters('ﬃ'), Some("ffi"));
+        assert_eq!(letters('f'), None);
+    }
+}
@@ -1,27 +1,52 @@
 //! Logic for laying out a paragraph.
 
+pub mod abbreviations;
+pub mod apostrophes;
 pub mod engine;
 pub mod graph;
+pub mod hyphenation;
 pub mod items;
 pub mod justification;
+pub mod kerning;
 pub mod ligatures;
+pub mod spacing;
 pub mod utils;
 
+use std::collections::BTreeSet;
+use std::f64;
 use std::slice::Iter;
 
-use crate::layout::paragraphs::items::Item;
+use printpdf::Pt;
+
+use crate::layout::paragraphs::items::{Content, Item};
+use crate::layout::paragraphs::kerning::KernOverrides;
 
 /// Holds a list of items describing a paragraph.
 #[derive(Debug, Default)]
 pub struct Paragraph<'a> {
     /// Sequence of items representing the structure of the paragraph.
     pub items: Vec<Item<'a>>,
+
+    /// The annotations of the paragraph, in the order of their anchors.
+    pub annotations: Vec<String>,
+
+    /// The indices of the glues laid out for tabulations.
+    pub tabs: BTreeSet<usize>,
+
+    /// The distance between two tab stops, to which the tabulations are aligned once the
+    /// paragraph is broken into lines. The tabulations keep their width if unset.
+    pub tab_width: Option<Pt>,
 }
 
 impl<'a> Paragraph<'a> {
     /// Instantiates a new paragraph.
     pub fn new() -> Paragraph<'a> {
-        Paragraph { items: Vec::new() }
+        Paragraph {
+            items: Vec::new(),
+            annotations: Vec::new(),
+            tabs: BTreeSet::new(),
+            tab_width: None,
+        }
     }
 
     /// Pushes an item at the end of the paragraph.
@@ -33,4 +58,179 @@ impl<'a> Paragraph<'a> {
     pub fn iter(&self) -> Iter<Item> {
         self.items.iter()
     }
+
+    /// Forces line breaks inside the words that are wider than a line, so that they wrap between
+    /// their characters instead of overflowing, like a last resort `\emergencystretch`.
+    ///
+    /// A broken word starts on a new line, and every line it fills is left with its natural
+    /// spacing. Returns the words that were broken.
+    pub fn break_wide_words(&mut self, line_length: Pt) -> Vec<String> {
+        let items = std::mem::take(&mut self.items);
+        let tabs = std::mem::take(&mut self.tabs);
+        let mut broken = vec![];
+        let mut start = 0;
+
+        while start < items.len() {
+            // A word is a sequence of boxes and of the hyphenation penalties between them.
+            let end = items[start..]
+                .iter()
+                .position(|item| match item.content {
+                    Content::BoundingBox(_) => false,
+                    Content::Penalty { value, .. } => value == f64::NEG_INFINITY,
+                    Content::Glue { .. } => true,
+                })
+                .map_or(items.len(), |len| start + len)
+                .max(start + 1);
+
+            let word = &items[start..end];
+            let width = word
+                .iter()
+                .filter(|item| matches!(item.content, Content::BoundingBox(_)))
+                .fold(Pt(0.0), |acc, item| acc + item.width);
+
+            if width <= line_length {
+                if tabs.contains(&start) {
+                    self.tabs.insert(self.items.len());
+                }
+                self.items.extend_from_slice(word);
+                start = end;
+                continue;
+            }
+
+            let has_boxes_before = self
+                .items
+                .iter()
+                .any(|item| matches!(item.content, Content::BoundingBox(_)));
+            if has_boxes_before {
+                self.push_forced_break(line_length);
+            }
+
+            let mut text = String::new();
+            let mut line_width = Pt(0.0);
+
+            for item in word {
+                if let Content::BoundingBox(ref glyph) = item.content {
+                    if line_width > Pt(0.0) && line_width + item.width > line_length {
+                        self.push_forced_break(line_length);
+                        line_width = Pt(0.0);
+                    }
+
+                    text.push(glyph.glyph);
+                    line_width += item.width;
+                    self.items.push(item.clone());
+                }
+            }
+
+            broken.push(text);
+            start = end;
+        }
+
+        broken
+    }
+
+    /// Returns the items of the paragraph, with the width of the tabulations set so that each
+    /// one reaches the next multiple of the tab width, from the beginning of its line.
+    ///
+    /// The lines are given by the breakpoints of the paragraph, as returned by the line breaking
+    /// algorithm. The tabulations, and the glues before the last one of a line, can't stretch or
+    /// shrink, to keep the text after them aligned.
+    pub fn align_tabs(&self, breakpoints: &[usize]) -> Vec<Item<'a>> {
+        let mut items = self.items.clone();
+        let tab_width = match self.tab_width {
+            Some(tab_width) => tab_width,
+            None => return items,
+        };
+
+        for (line, bounds) in breakpoints.windows(2).enumerate() {
+            let start = if line == 0 { bounds[0] } else { bounds[0] + 1 };
+            let mut tabs = self.tabs.range(start..bounds[1]).peekable();
+            let last = match self.tabs.range(start..bounds[1]).next_back() {
+                Some(&last) => last,
+                None => continue,
+            };
+
+            let mut position = Pt(0.0);
+            for (i, item) in items.iter_mut().enumerate().take(last + 1).skip(start) {
+                let is_tab = tabs.next_if_eq(&&i).is_some();
+
+                // The glue at the beginning of a line is not laid out.
+                if i == start && matches!(item.content, Content::Glue { .. }) {
+                    continue;
+                }
+
+                if is_tab {
+                    let stops = (position.0 / tab_width.0).floor() + 1.0;
+                    *item = Item::glue(tab_width * stops - position, Pt(0.0), Pt(0.0));
+                }
+
+                match item.content {
+                    Content::BoundingBox(_) => position += item.width,
+                    Content::Glue { .. } => {
+                        *item = Item::glue(item.width, Pt(0.0), Pt(0.0));
+                        position += item.width;
+                    }
+                    Content::Penalty { .. } => (),
+                }
+            }
+        }
+
+        items
+    }
+
+    /// Adjusts the gap between the pairs of adjacent chars that have a kerning override, by
+    /// widening or narrowing the first char of each pair.
+    ///
+    /// The chars on both sides of a hyphenation point are still adjacent, unlike the ones on
+    /// both sides of a glue.
+    pub fn kern(&mut self, overrides: &KernOverrides) {
+        if overrides.is_empty() {
+            return;
+        }
+
+        let mut previous: Option<(usize, char)> = None;
+
+        for i in 0..self.items.len() {
+            match self.items[i].content {
+                Content::BoundingBox(ref glyph) => {
+                    let c = glyph.glyph;
+                    if let Some((j, left)) = previous {
+                        if let Some(adjustment) = overrides.get(left, c) {
+                            self.items[j].width += adjustment;
+                        }
+                    }
+                    previous = Some((i, c));
+                }
+                Content::Penalty { .. } => (),
+                Content::Glue { .. } => previous = None,
+            }
+        }
+    }
+
+    /// Sets the stretch of the glue that ends the paragraph, infinite by default, like TeX's
+    /// `\parfillskip`.
+    ///
+    /// A finite stretch makes short last lines loose, so that they get longer, and no stretch
+    /// at all justifies them.
+    pub fn set_fill(&mut self, stretch: Pt) {
+        for item in &mut self.items {
+            if let Content::Glue {
+                ref mut stretchability,
+                ..
+            } = item.content
+            {
+                if stretchability.0.is_infinite() {
+                    *stretchability = stretch;
+                }
+            }
+        }
+    }
+
+    /// Pushes a forced line break, after a glue that can fill the rest of the line.
+    ///
+    /// The glue can't stretch infinitely like the one ending a paragraph, since the stretch of
+    /// the lines that follow it would be undefined.
+    fn push_forced_break(&mut self, line_length: Pt) {
+        self.push(Item::glue(Pt(0.0), line_length, Pt(0.0)));
+        self.push(Item::penalty(Pt(0.0), f64::NEG_INFINITY, false));
+    }
 }
@@ -0,0 +1,34 @@
+//! This module contains the settings of the whitespaces between the words of a paragraph.
+
+use crate::layout::constants::CLAUSE_SPACE_FACTOR;
+use crate::layout::paragraphs::abbreviations::Abbreviations;
+
+/// How the whitespaces of a text are laid out, beyond the space of its font.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spacing {
+    /// The space factor of the whitespaces after colons and semicolons, from 1 for an interword
+    /// space to `SENTENCE_SPACE_FACTOR` for a sentence space.
+    pub clause_space_factor: f64,
+
+    /// Whether the whitespaces after the end of a sentence or of a clause are interword spaces,
+    /// like TeX's `\frenchspacing`.
+    pub french_spacing: bool,
+
+    /// The abbreviations, whose dots don't end a sentence.
+    pub abbreviations: Abbreviations,
+
+    /// Whether the line breaks of the source are breakpoints that the lines prefer, rather than
+    /// plain whitespaces.
+    pub source_breaks: bool,
+}
+
+impl Default for Spacing {
+    fn default() -> Spacing {
+        Spacing {
+            clause_space_factor: CLAUSE_SPACE_FACTOR,
+            french_spacing: false,
+            abbreviations: Abbreviations::default(),
+            source_breaks: false,
+        }
+    }
+}
@@ -3,9 +3,15 @@
 
 use crate::fonts::configuration::FontConfig;
 use crate::fonts::styles::FontStyle;
-use crate::layout::constants::{IDEAL_SPACING, PLUS_INFINITY};
+use crate::layout::constants::{
+    PLUS_INFINITY, SOURCE_BREAK_PENALTY, SPACE_SHRINK, SPACE_STRETCH, TAB_WIDTH,
+};
+use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
 use crate::layout::paragraphs::items::Item;
-use crate::layout::paragraphs::utils::paragraphs::{add_word_to_paragraph, glue_from_context};
+use crate::layout::paragraphs::spacing::Spacing;
+use crate::layout::paragraphs::utils::paragraphs::{
+    add_word_to_paragraph, glue_from_context, interword_glue,
+};
 use crate::layout::paragraphs::Paragraph;
 use crate::layout::Glyph;
 use crate::parser::ast::Ast;
@@ -13,12 +19,91 @@ use printpdf::Pt;
 use spandex_hyphenation::*;
 use std::f64;
 
+/// How a character of a text is laid out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CharKind {
+    /// A visible character, laid out as a box.
+    Glyph,
+
+    /// A whitespace, laid out as a glue.
+    Space,
+
+    /// A tabulation, laid out as a wider glue.
+    Tab,
+
+    /// A zero-width space, that allows a line break without adding any space.
+    ZeroWidthSpace,
+
+    /// A zero-width joiner or non-joiner, or another invisible character, that is kept out of
+    /// the layout without splitting the word it's in.
+    Invisible,
+}
+
+impl CharKind {
+    /// Classifies a character of a text.
+    fn of(c: char) -> CharKind {
+        match c {
+            '\t' => CharKind::Tab,
+            '\u{200B}' => CharKind::ZeroWidthSpace,
+            '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => CharKind::Invisible,
+            c if c.is_whitespace() => CharKind::Space,
+            c if c.is_control() => CharKind::Invisible,
+            _ => CharKind::Glyph,
+        }
+    }
+}
+
+/// Parses a verbatim text, like a line of code, into a sequence of items.
+///
+/// Its whitespaces keep the width of the space of the font without stretching or shrinking, its
+/// tabulations advance to the next multiple of the tab width, and its words are never
+/// hyphenated.
+pub fn itemize_verbatim<'a>(
+    text: &str,
+    font_config: &FontConfig<'a>,
+    size: Pt,
+    tab_width: Pt,
+) -> Paragraph<'a> {
+    let mut p = Paragraph::new();
+    let font = font_config.regular;
+    let mut position = Pt(0.0);
+
+    for c in text.chars() {
+        let item = match CharKind::of(c) {
+            CharKind::Glyph if !font.has_glyph(c) => {
+                let c = font_config.missing_glyph;
+                Item::from_glyph(Glyph::new(c, font, size))
+            }
+            CharKind::Glyph => Item::from_glyph(Glyph::new(c, font, size)),
+            CharKind::Space => Item::glue(font.char_width(' ', size), Pt(0.0), Pt(0.0)),
+            CharKind::Tab => {
+                let stops = (position.0 / tab_width.0).floor() + 1.0;
+                Item::glue(tab_width * stops - position, Pt(0.0), Pt(0.0))
+            }
+            CharKind::ZeroWidthSpace => Item::glue(Pt(0.0), Pt(0.0), Pt(0.0)),
+            CharKind::Invisible => continue,
+        };
+
+        position += item.width;
+        p.push(item);
+    }
+
+    p.push(Item::glue(Pt(0.0), PLUS_INFINITY, Pt(0.0)));
+    p.push(Item::penalty(Pt(0.0), f64::NEG_INFINITY, false));
+    p
+}
+
 /// Parses an AST into a sequence of items.
+///
+/// The whitespaces are laid out with the spacing settings, e.g. widened after colons and
+/// semicolons, see `glue_from_context`.
 pub fn itemize_ast<'a>(
     ast: &Ast,
-    font_config: &'a FontConfig,
+    font_config: &FontConfig<'a>,
     size: Pt,
     dictionary: &Standard,
+    exceptions: &HyphenationExceptions,
+    spacing: &Spacing,
     indent: Pt,
 ) -> Paragraph<'a> {
     let mut p = Paragraph::new();
@@ -28,28 +113,42 @@ pub fn itemize_ast<'a>(
         p.push(Item::glue(indent, Pt(0.0), Pt(0.0)));
     }
 
-    itemize_ast_aux(ast, font_config, size, dictionary, current_style, &mut p);
+    itemize_ast_aux(
+        ast,
+        font_config,
+        size,
+        dictionary,
+        exceptions,
+        spacing,
+        current_style,
+        &mut p,
+    );
     p
 }
 
 /// Parses an AST into a sequence of items.
+#[allow(clippy::too_many_arguments)]
 pub fn itemize_ast_aux<'a>(
     ast: &Ast,
-    font_config: &'a FontConfig,
+    font_config: &FontConfig<'a>,
     size: Pt,
     dictionary: &Standard,
+    exceptions: &HyphenationExceptions,
+    spacing: &Spacing,
     current_style: FontStyle,
     buffer: &mut Paragraph<'a>,
 ) {
     match ast {
         Ast::Title { level, children } => {
-            let size = size + Pt(3.0 * ((4 - *level as isize).max(1)) as f64);
+            let size = font_config.title_sizes.size(size, *level);
             for child in children {
                 itemize_ast_aux(
                     child,
                     font_config,
                     size,
                     dictionary,
+                    exceptions,
+                    spacing,
                     current_style.bold(),
                     buffer,
                 );
@@ -65,6 +164,8 @@ pub fn itemize_ast_aux<'a>(
                     font_config,
                     size,
                     dictionary,
+                    exceptions,
+                    spacing,
                     current_style.bold(),
                     buffer,
                 );
@@ -78,6 +179,8 @@ pub fn itemize_ast_aux<'a>(
                     font_config,
                     size,
                     dictionary,
+                    exceptions,
+                    spacing,
                     current_style.italic(),
                     buffer,
                 );
@@ -86,19 +189,55 @@ pub fn itemize_ast_aux<'a>(
 
         Ast::Text(content) => {
             let font = font_config.for_style(current_style);
-            let ideal_spacing = IDEAL_SPACING;
+            let space = font.char_width(' ', size);
             let mut previous_glyph = None;
             let mut current_word = vec![];
+            let abbreviations = spacing.abbreviations.find(content);
 
             // Turn each word of the paragraph into a sequence of boxes for the caracters of the
-            // word. This includes potential punctuation marks.
-            for c in content.chars() {
-                if c.is_whitespace() {
-                    add_word_to_paragraph(current_word, dictionary, buffer);
-                    buffer.push(glue_from_context(previous_glyph, ideal_spacing));
+            // word. This includes potential punctuation marks. Control characters and zero-width
+            // joiners never become boxes, since they would corrupt the layout invisibly.
+            for (i, c) in content.char_indices() {
+                let within_abbreviation = abbreviations.iter().any(|x| x.start < i && i < x.end);
+
+                let glue = match CharKind::of(c) {
+                    CharKind::Glyph if !font.has_glyph(c) => {
+                        let c = font_config.missing_glyph;
+                        current_word.push(Glyph::new(c, font, size));
+                        None
+                    }
+                    CharKind::Glyph => {
+                        current_word.push(Glyph::new(c, font, size));
+                        None
+                    }
+                    CharKind::Invisible => None,
+                    CharKind::Space if within_abbreviation => {
+                        Some(Item::glue(space, Pt(0.0), Pt(0.0)))
+                    }
+                    CharKind::Space if abbreviations.iter().any(|x| x.end == i) => {
+                        Some(interword_glue(font, size))
+                    }
+                    CharKind::Space => Some(glue_from_context(previous_glyph, font, size, spacing)),
+                    CharKind::Tab => Some(Item::glue(
+                        TAB_WIDTH,
+                        space * SPACE_STRETCH,
+                        space * SPACE_SHRINK,
+                    )),
+                    CharKind::ZeroWidthSpace => Some(Item::glue(Pt(0.0), Pt(0.0), Pt(0.0))),
+                };
+
+                if let Some(glue) = glue {
+                    add_word_to_paragraph(current_word, dictionary, exceptions, buffer);
+                    if within_abbreviation {
+                        buffer.push(Item::penalty(Pt(0.0), f64::INFINITY, false));
+                    } else if c == '\n' && spacing.source_breaks {
+                        buffer.push(Item::penalty(Pt(0.0), SOURCE_BREAK_PENALTY, false));
+                    }
+                    if CharKind::of(c) == CharKind::Tab {
+                        buffer.tabs.insert(buffer.items.len());
+                    }
+                    buffer.push(glue);
                     current_word = vec![];
-                } else {
-                    current_word.push(Glyph::new(c, font, size));
                 }
 
                 previous_glyph = Some(Glyph::new(c, font, size));
@@ -107,19 +246,43 @@ pub fn itemize_ast_aux<'a>(
             // Current word is empty if content ends with a whitespace.
 
             if !current_word.is_empty() {
-                add_word_to_paragraph(current_word, dictionary, buffer);
+                add_word_to_paragraph(current_word, dictionary, exceptions, buffer);
             }
         }
 
+        Ast::Annotation(note) => {
+            let font = font_config.for_style(current_style);
+            buffer.push(Item::anchor(font, size));
+            buffer.annotations.push(note.clone());
+        }
+
         Ast::File(_, children) => {
             for child in children {
-                itemize_ast_aux(child, font_config, size, dictionary, current_style, buffer);
+                itemize_ast_aux(
+                    child,
+                    font_config,
+                    size,
+                    dictionary,
+                    exceptions,
+                    spacing,
+                    current_style,
+                    buffer,
+                );
             }
         }
 
         Ast::Paragraph(children) => {
             for child in children {
-                itemize_ast_aux(child, font_config, size, dictionary, current_style, buffer);
+                itemize_ast_aux(
+                    child,
+                    font_config,
+                    size,
+                    dictionary,
+                    exceptions,
+                    spacing,
+                    current_style,
+                    buffer,
+                );
             }
 
             // Appends two items to ensure the end of any paragraph is treated properly: a glue
@@ -132,3 +295,412 @@ pub fn itemize_ast_aux<'a>(
         _ => (),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use printpdf::Pt;
+    use spandex_hyphenation::load::Load;
+    use spandex_hyphenation::{Language, Standard};
+
+    use crate::document::configuration::Config;
+    use crate::fonts::configuration::TitleSizes;
+    use crate::layout::constants::{
+        CLAUSE_SPACE_FACTOR, DEFAULT_MISSING_GLYPH, SOURCE_BREAK_PENALTY,
+    };
+    use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
+    use crate::layout::paragraphs::items::Content;
+    use crate::layout::paragraphs::ligatures::ligature;
+    use crate::layout::paragraphs::spacing::Spacing;
+    use crate::layout::paragraphs::utils::ast::itemize_ast;
+    use crate::layout::paragraphs::utils::linebreak::find_legal_breakpoints;
+    use crate::parser::ast::Ast;
+    use crate::parser::combinators::parse_paragraph;
+    use crate::parser::Span;
+    use crate::Result;
+
+    #[test]
+    fn test_title_sizes() {
+        let base = Pt(10.0);
+        let sizes = (0..=10)
+            .map(|level| TitleSizes::default().size(base, level).0)
+            .collect::<Vec<_>>();
+
+        assert!(sizes.windows(2).all(|w| w[0] > w[1]));
+        assert!(sizes.iter().all(|&size| size > base.0));
+    }
+
+    #[test]
+    fn test_missing_glyph() -> Result<()> {
+        let mut config = Config::with_title("Test");
+        config.missing_glyph = '#';
+
+        let (_, font_manager) = config.init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let ast = Ast::Paragraph(vec![Ast::Text("a\u{1F600}b".into())]);
+        let paragraph = itemize_ast(
+            &ast,
+            &font_config,
+            Pt(10.0),
+            &en,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+        let glyphs: String = paragraph
+            .items
+            .iter()
+            .filter_map(|item| match &item.content {
+                Content::BoundingBox(glyph) => Some(glyph.glyph),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(glyphs, "a#b");
+        assert!(font_config.regular.has_glyph(DEFAULT_MISSING_GLYPH));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyphenation_exceptions() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+
+        // Returns the number of chars before each hyphenation point of a word.
+        let breaks = |exceptions: &HyphenationExceptions| {
+            let ast = Ast::Paragraph(vec![Ast::Text("project".into())]);
+            let paragraph = itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+
+            let mut chars = 0;
+            let mut breaks = vec![];
+            for item in &paragraph.items {
+                match item.content {
+                    Content::BoundingBox(_) => chars += 1,
+                    Content::Penalty { flagged: true, .. } => breaks.push(chars),
+                    _ => (),
+                }
+            }
+            breaks
+        };
+
+        // The dictionary breaks the word as pro-ject.
+        assert_eq!(breaks(&HyphenationExceptions::default()), vec![3]);
+        assert_eq!(breaks(&HyphenationExceptions::new(&["proj-ect"])), vec![4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyphenate_ligatures() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+
+        // Returns the number of glyphs before each hyphenation point of a ligated word.
+        let breaks = |exceptions: &HyphenationExceptions| {
+            let ast = Ast::Paragraph(vec![Ast::Text(ligature("reflection"))]);
+            let paragraph = itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+
+            let mut glyphs = 0;
+            let mut breaks = vec![];
+            for item in &paragraph.items {
+                match item.content {
+                    Content::BoundingBox(_) => glyphs += 1,
+                    Content::Penalty { flagged: true, .. } => breaks.push(glyphs),
+                    _ => (),
+                }
+            }
+            breaks
+        };
+
+        // The word is broken as re-flec-tion, where "fl" is a single glyph.
+        assert_eq!(breaks(&HyphenationExceptions::default()), vec![2, 5]);
+        // A break within a ligature is lost.
+        assert!(breaks(&HyphenationExceptions::new(&["ref-lection"])).is_empty());
+        assert_eq!(
+            breaks(&HyphenationExceptions::new(&["reflec-tion"])),
+            vec![5]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyphenate_capitalized() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+
+        // Returns the number of hyphenation points of a word.
+        let breaks = |word: &str, exceptions: &HyphenationExceptions| {
+            let ast = Ast::Paragraph(vec![Ast::Text(word.into())]);
+            let paragraph = itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+            paragraph
+                .items
+                .iter()
+                .filter(|item| matches!(item.content, Content::Penalty { flagged: true, .. }))
+                .count()
+        };
+
+        let mut exceptions = HyphenationExceptions::default();
+        assert!(breaks("Hyphenation", &exceptions) > 0);
+
+        exceptions.set_hyphenate_capitalized(false);
+        assert_eq!(breaks("Hyphenation", &exceptions), 0);
+        assert!(breaks("hyphenation", &exceptions) > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_emphasis() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+        let bold = font_manager.get("CMU Serif Bold").unwrap();
+        let italic = font_manager.get("CMU Serif Italic").unwrap();
+        let bold_italic = font_manager.get("CMU Serif BoldItalic").unwrap();
+
+        for (input, outer) in [("*a /b/ a*", bold), ("/a *b* a/", italic)] {
+            let (_, ast) = parse_paragraph(Span::new(input)).unwrap();
+            let paragraph = itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                &exceptions,
+                &Spacing::default(),
+                Pt(0.0),
+            );
+
+            for item in &paragraph.items {
+                if let Content::BoundingBox(glyph) = &item.content {
+                    let expected = if glyph.glyph == 'b' {
+                        bold_italic
+                    } else {
+                        outer
+                    };
+                    assert!(
+                        std::ptr::eq(glyph.font, expected),
+                        "{} in {}",
+                        glyph.glyph,
+                        input
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_breaks() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        let ast = Ast::Paragraph(vec![Ast::Text("One two\nthree".into())]);
+        let itemize = |source_breaks| {
+            itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                &exceptions,
+                &Spacing {
+                    source_breaks,
+                    ..Default::default()
+                },
+                Pt(0.0),
+            )
+        };
+
+        // The newline is preceded by a penalty that is a legal breakpoint, cheaper than a glue.
+        let paragraph = itemize(true);
+        let is_source_break = |content: &Content| match content {
+            Content::Penalty { value, .. } => *value == SOURCE_BREAK_PENALTY,
+            _ => false,
+        };
+        let breakpoint = paragraph
+            .items
+            .iter()
+            .position(|item| is_source_break(&item.content))
+            .unwrap();
+        assert!(find_legal_breakpoints(&paragraph).contains(&breakpoint));
+        assert!(matches!(
+            paragraph.items[breakpoint + 1].content,
+            Content::Glue { .. }
+        ));
+
+        // Otherwise, the newline is a plain whitespace.
+        let paragraph = itemize(false);
+        assert_eq!(paragraph.items.len() + 1, itemize(true).items.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clause_space_factor() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+
+        // The stretchabilities of the glues after a comma, a colon and a semicolon.
+        let stretches = |factor| {
+            let ast = Ast::Paragraph(vec![Ast::Text("One, two: three; four".into())]);
+            let paragraph = itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                &exceptions,
+                &Spacing {
+                    clause_space_factor: factor,
+                    ..Default::default()
+                },
+                Pt(0.0),
+            );
+            paragraph
+                .items
+                .iter()
+                .filter_map(|item| match item.content {
+                    Content::Glue { stretchability, .. } => Some(stretchability.0),
+                    _ => None,
+                })
+                .take(3)
+                .collect::<Vec<_>>()
+        };
+
+        let glues = stretches(CLAUSE_SPACE_FACTOR);
+        assert!(glues[1] > glues[0]);
+        assert_eq!(glues[1], glues[2]);
+
+        // A factor of 1 treats colons like commas.
+        let glues = stretches(1.0);
+        assert_eq!(glues[0], glues[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_abbreviations() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+        let space = font_config.regular.char_width(' ', Pt(10.0));
+
+        let ast = Ast::Paragraph(vec![Ast::Text("End. Then e.g. so, i. e. that".into())]);
+        let paragraph = itemize_ast(
+            &ast,
+            &font_config,
+            Pt(10.0),
+            &en,
+            &exceptions,
+            &Spacing::default(),
+            Pt(0.0),
+        );
+
+        // The widths and stretchabilities of the glues, and whether a line can break at them.
+        let items = &paragraph.items;
+        let glues = (0..items.len() - 2)
+            .filter_map(|i| match items[i].content {
+                Content::Glue { stretchability, .. } => {
+                    let breakable = matches!(items[i - 1].content, Content::BoundingBox(_));
+                    Some((items[i].width, stretchability, breakable))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(glues.len(), 6);
+
+        // The end of a sentence is wider, but not the dots of an abbreviation.
+        assert!(glues[0].0 > space);
+        assert_eq!(glues[1].0, space);
+        assert_eq!(glues[2].0, space);
+        assert_eq!(glues[5].0, space);
+
+        // The space between the dots of an abbreviation can't break nor stretch.
+        assert_eq!(glues[4].0, space);
+        assert_eq!(glues[4].1 .0, 0.0);
+        assert!(glues.iter().enumerate().all(|(i, glue)| glue.2 == (i != 4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_french_spacing() -> Result<()> {
+        let (_, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+        let en = Standard::from_embedded(Language::EnglishUS)?;
+        let exceptions = HyphenationExceptions::default();
+        let space = font_config.regular.char_width(' ', Pt(10.0));
+
+        // The widths of the glues after a word, a sentence and a clause.
+        let widths = |french_spacing| {
+            let ast = Ast::Paragraph(vec![Ast::Text("One two. Three: four".into())]);
+            let paragraph = itemize_ast(
+                &ast,
+                &font_config,
+                Pt(10.0),
+                &en,
+                &exceptions,
+                &Spacing {
+                    french_spacing,
+                    ..Default::default()
+                },
+                Pt(0.0),
+            );
+            paragraph
+                .items
+                .iter()
+                .filter_map(|item| match item.content {
+                    Content::Glue { .. } => Some(item.width),
+                    _ => None,
+                })
+                .take(3)
+                .collect::<Vec<_>>()
+        };
+
+        let glues = widths(false);
+        assert_eq!(glues[0], space);
+        assert!(glues[1] > space);
+        assert!(glues[2] > space);
+
+        assert_eq!(widths(true), vec![space; 3]);
+
+        Ok(())
+    }
+}
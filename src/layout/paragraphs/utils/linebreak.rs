@@ -74,7 +74,7 @@ pub fn compute_adjustment_ratios_with_breakpoints<'a>(
         let range = items
             .iter()
             .enumerate()
-            .take(next_breakpoint)
+            .take(next_breakpoint + 1)
             .skip(beginning);
 
         for (p, item) in range {
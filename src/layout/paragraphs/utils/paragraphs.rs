@@ -1,7 +1,14 @@
 //! Utility functions for manipulating and typesetting a `Paragraph`.
 
-use crate::layout::constants::{DASH_GLYPH, DEFAULT_LINE_LENGTH, SPACE_WIDTH};
+use crate::fonts::Font;
+use crate::layout::constants::{
+    CLAUSE_ENDS, DASH_GLYPH, DEFAULT_LINE_LENGTH, SENTENCE_ENDS, SENTENCE_EXTRA_SPACE,
+    SENTENCE_SPACE_FACTOR, SPACE_SHRINK, SPACE_STRETCH,
+};
+use crate::layout::paragraphs::hyphenation::HyphenationExceptions;
 use crate::layout::paragraphs::items::Item;
+use crate::layout::paragraphs::ligatures::letters;
+use crate::layout::paragraphs::spacing::Spacing;
 use crate::layout::paragraphs::Paragraph;
 use crate::layout::Glyph;
 use printpdf::Pt;
@@ -11,21 +18,42 @@ use spandex_hyphenation::*;
 pub fn add_word_to_paragraph<'a>(
     word: Vec<Glyph<'a>>,
     dictionary: &Standard,
+    exceptions: &HyphenationExceptions,
     buffer: &mut Paragraph<'a>,
 ) {
-    // Reached end of current word, handle hyphenation.
-    let to_hyphenate = word
-        .iter()
-        .map(|x: &Glyph| x.glyph.to_string())
-        .collect::<Vec<_>>()
-        .join("");
-
-    let hyphenated = dictionary.hyphenate(&to_hyphenate);
-    let break_indices = &hyphenated.breaks;
-
-    for (i, g) in word.iter().enumerate() {
-        if break_indices.contains(&i) {
-            buffer.push(Item::penalty(Pt(0.0), 50.0, true));
+    // Reached end of current word, handle hyphenation. The ligatures are looked up with the
+    // letters they replace, so that the patterns and the exceptions still match them, and the
+    // index of the first letter of each glyph is kept to place the breaks back.
+    let mut to_hyphenate = String::new();
+    let mut starts = vec![];
+    for glyph in &word {
+        starts.push(to_hyphenate.chars().count());
+        match letters(glyph.glyph) {
+            Some(letters) => to_hyphenate.push_str(letters),
+            None => to_hyphenate.push(glyph.glyph),
+        }
+    }
+
+    // The exceptions given by the user take precedence over the dictionary, whose breaks are
+    // byte offsets rather than indices of chars.
+    let break_indices = match exceptions.breaks(&to_hyphenate) {
+        Some(breaks) => breaks.to_vec(),
+        None if exceptions.uses_dictionary(&to_hyphenate) => dictionary
+            .hyphenate(&to_hyphenate)
+            .breaks
+            .iter()
+            .map(|&offset| to_hyphenate[..offset].chars().count())
+            .collect(),
+        None => vec![],
+    };
+
+    for (g, start) in word.iter().zip(starts) {
+        // A break inserted by hyphenation is as wide as the dash drawn there, while a break
+        // after a dash of the text is empty, so that the dash isn't doubled. The breaks within
+        // a ligature are lost.
+        if break_indices.contains(&start) {
+            let dash = g.font.char_width(DASH_GLYPH, g.scale);
+            buffer.push(Item::penalty(dash, 50.0, true));
         }
 
         buffer.push(Item::from_glyph(g.clone()));
@@ -37,9 +65,45 @@ pub fn add_word_to_paragraph<'a>(
 }
 
 /// Returns the glue based on the spatial context of the cursor.
-pub fn glue_from_context(_previous_glyph: Option<Glyph>, ideal_spacing: Pt) -> Item {
-    // Todo: make this glue context dependent.
-    Item::glue(ideal_spacing, SPACE_WIDTH, SPACE_WIDTH * 0.5)
+///
+/// Its natural width is the width of the space of the font, and it stretches and shrinks
+/// proportionally to it. After the end of a sentence, it is wider and more stretchable, like
+/// in TeX. After a colon or a semicolon, it is scaled by the clause space factor instead, from
+/// 1 for an interword space to `SENTENCE_SPACE_FACTOR` for a sentence space. With french
+/// spacing, all of them are interword spaces.
+pub fn glue_from_context<'a>(
+    previous_glyph: Option<Glyph>,
+    font: &Font,
+    size: Pt,
+    spacing: &Spacing,
+) -> Item<'a> {
+    let factor = match previous_glyph {
+        _ if spacing.french_spacing => 1.0,
+        Some(glyph) if SENTENCE_ENDS.contains(&glyph.glyph) => SENTENCE_SPACE_FACTOR,
+        Some(glyph) if CLAUSE_ENDS.contains(&glyph.glyph) => spacing.clause_space_factor,
+        _ => 1.0,
+    };
+
+    spaced_glue(font, size, factor)
+}
+
+/// Returns the glue of a whitespace with a space factor, whose extra width grows with the
+/// factor up to the one of a sentence space.
+fn spaced_glue<'a>(font: &Font, size: Pt, factor: f64) -> Item<'a> {
+    let space = font.char_width(' ', size);
+    let extra = SENTENCE_EXTRA_SPACE * (factor - 1.0) / (SENTENCE_SPACE_FACTOR - 1.0);
+
+    Item::glue(
+        space * (1.0 + extra),
+        space * SPACE_STRETCH * factor,
+        space * SPACE_SHRINK / factor,
+    )
+}
+
+/// Returns the glue between two words of a sentence.
+pub fn interword_glue<'a>(font: &Font, size: Pt) -> Item<'a> {
+    let space = font.char_width(' ', size);
+    Item::glue(space, space * SPACE_STRETCH, space * SPACE_SHRINK)
 }
 
 /// Returns the length of the line of given index, from a list of
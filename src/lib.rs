@@ -8,17 +8,23 @@ pub mod document;
 pub mod fonts;
 pub mod layout;
 pub mod parser;
+pub mod units;
 
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
+use std::borrow::Borrow;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::{error, fmt, io, result};
 
-use printpdf::Pt;
+use colored::*;
+use printpdf::OffsetDateTime;
 
-use crate::document::configuration::Config;
+use crate::document::configuration::{Config, PageOverflow};
+use crate::document::{build_date, Document};
+use crate::fonts::configuration::FontConfig;
+use crate::layout::constants::DEFAULT_FONT_SIZE;
+use crate::parser::ast::{Ast, BuildMode};
 use crate::parser::error::Errors;
-use crate::parser::parse;
+use crate::parser::{parse, position, Position, Span};
 
 macro_rules! impl_from_error {
     ($type: ty, $variant: path, $from: ty) => {
@@ -39,31 +45,70 @@ pub enum Error {
     /// No spandex.toml was found.
     NoConfigFile,
 
+    /// The input file specified in the configuration was not found.
+    InputNotFound(PathBuf),
+
+    /// A source file is not valid UTF-8, the first invalid byte being at the given offset.
+    InvalidUtf8(PathBuf, usize),
+
     /// Error while dealing with freetype.
     FreetypeError(freetype::Error),
 
     /// Error while dealing with printpdf.
     PrintpdfError(printpdf::errors::Error),
 
+    /// Error while reading or writing a pdf file with lopdf.
+    LopdfError(lopdf::Error),
+
     /// The specified font was not found.
     FontNotFound(PathBuf),
 
     /// The specified font has no name or no style.
     FontWithoutName(PathBuf),
 
+    /// One of the fonts of the default font config couldn't be loaded.
+    DefaultFontMissing(String),
+
+    /// The page geometry of a config is impossible, e.g. margins wider than the page.
+    InvalidGeometry(String),
+
+    /// A setting of a config is impossible, e.g. a minimal word space above the maximal one.
+    InvalidConfig(String),
+
+    /// A config has a key that isn't a setting, likely a typo.
+    UnknownConfigKey(String),
+
+    /// The content of a document doesn't fit in its maximum number of pages.
+    TooManyPages(usize),
+
+    /// Some warnings were raised while warnings are denied.
+    DeniedWarnings(usize),
+
     /// An error occured while loading an hyphenation dictionnary.
     HyphenationLoadError(spandex_hyphenation::load::Error),
 
+    /// An error occured while writing JSON.
+    JsonError(serde_json::Error),
+
+    /// An error occured while reading TOML.
+    TomlError(toml::de::Error),
+
     /// Another io error occured.
     IoError(io::Error),
 
     /// Some error occured while parsing a dex file.
     DexError(Errors),
+
+    /// A combinator of the parser failed, at a position of its input if it is known.
+    CombinatorError(Option<Position>, nom::error::ErrorKind),
 }
 
 impl_from_error!(Error, Error::FreetypeError, freetype::Error);
 impl_from_error!(Error, Error::PrintpdfError, printpdf::errors::Error);
+impl_from_error!(Error, Error::LopdfError, lopdf::Error);
 impl_from_error!(Error, Error::IoError, io::Error);
+impl_from_error!(Error, Error::JsonError, serde_json::Error);
+impl_from_error!(Error, Error::TomlError, toml::de::Error);
 impl_from_error!(
     Error,
     Error::HyphenationLoadError,
@@ -71,20 +116,65 @@ impl_from_error!(
 );
 impl_from_error!(Error, Error::DexError, Errors);
 
+impl<'a> From<nom::Err<nom::error::Error<Span<'a>>>> for Error {
+    fn from(e: nom::Err<nom::error::Error<Span<'a>>>) -> Error {
+        match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                Error::CombinatorError(Some(position(&e.input)), e.code)
+            }
+            nom::Err::Incomplete(_) => {
+                Error::CombinatorError(None, nom::error::ErrorKind::Complete)
+            }
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::CannotReadCurrentDir => write!(fmt, "cannot read current directory"),
             Error::NoConfigFile => write!(fmt, "no spandex.toml was found"),
+            Error::InputNotFound(path) => write!(
+                fmt,
+                "couldn't find input file \"{}\", check the `input` field of spandex.toml",
+                path.display()
+            ),
+            Error::InvalidUtf8(path, offset) => write!(
+                fmt,
+                "\"{}\" is not valid UTF-8, invalid byte at offset {}",
+                path.display(),
+                offset
+            ),
             Error::FreetypeError(e) => write!(fmt, "freetype error: {}", e),
             Error::PrintpdfError(e) => write!(fmt, "printpdf error: {}", e),
+            Error::LopdfError(e) => write!(fmt, "lopdf error: {}", e),
             Error::FontNotFound(path) => write!(fmt, "couldn't find font \"{}\"", path.display()),
             Error::FontWithoutName(path) => {
                 write!(fmt, "font has no name or style \"{}\"", path.display())
             }
+            Error::DefaultFontMissing(name) => {
+                write!(fmt, "couldn't load the default font \"{}\"", name)
+            }
+            Error::InvalidGeometry(reason) => write!(fmt, "invalid page geometry: {}", reason),
+            Error::InvalidConfig(reason) => write!(fmt, "invalid config: {}", reason),
+            Error::UnknownConfigKey(key) => write!(fmt, "unknown key \"{}\" in the config", key),
+            Error::TooManyPages(max) => {
+                write!(fmt, "the document doesn't fit in {} pages", max)
+            }
+            Error::DeniedWarnings(count) => {
+                write!(fmt, "{} warning(s) raised while warnings are denied", count)
+            }
             Error::HyphenationLoadError(e) => write!(fmt, "Problem with hyphenation: {}", e),
+            Error::JsonError(e) => write!(fmt, "json error: {}", e),
+            Error::TomlError(e) => write!(fmt, "toml error: {}", e),
             Error::IoError(e) => write!(fmt, "an io error occured: {}", e),
             Error::DexError(e) => write!(fmt, "{}", e),
+            Error::CombinatorError(Some(position), kind) => write!(
+                fmt,
+                "parse error: {:?} at line {}, column {}",
+                kind, position.line, position.column
+            ),
+            Error::CombinatorError(None, kind) => write!(fmt, "parse error: {:?}", kind),
         }
     }
 }
@@ -94,23 +184,393 @@ impl error::Error for Error {}
 /// The result type of the library.
 pub type Result<T> = result::Result<T, Error>;
 
+/// Reads a source file, without its byte order mark if it starts with one.
+pub fn read_source<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    let content = String::from_utf8(fs::read(path)?)
+        .map_err(|e| Error::InvalidUtf8(PathBuf::from(path), e.utf8_error().valid_up_to()))?;
+
+    Ok(match content.strip_prefix('\u{FEFF}') {
+        Some(content) => content.to_owned(),
+        None => content,
+    })
+}
+
+/// Reads and checks the content of a spandex.toml, without loading its fonts nor its input.
+///
+/// This is meant for tools, like editors, that check a config while it is written.
+pub fn validate_config(toml_str: &str) -> Result<Config> {
+    Config::from_toml(toml_str)
+}
+
+/// Reports an input file that is missing with its path as it is configured.
+fn input_error(input: &str, e: Error) -> Error {
+    match e {
+        Error::IoError(e) if e.kind() == io::ErrorKind::NotFound => {
+            Error::InputNotFound(PathBuf::from(input))
+        }
+        e => e,
+    }
+}
+
 /// Compiles a spandex project.
 pub fn build(config: &Config) -> Result<()> {
+    build_document(config)?.save("output.pdf");
+    Ok(())
+}
+
+/// Typesets the input files of a spandex project, in order, into one document.
+pub fn build_document(config: &Config) -> Result<Document> {
+    build_document_at(config, build_date(config.reproducible))
+}
+
+/// The content of an input file, ready to be rendered.
+enum Chapter {
+    /// The ast of a dex file.
+    Ast(Ast),
+
+    /// The text of a plain text file.
+    Text(String),
+}
+
+/// Renders some chapters on a document in turn.
+///
+/// Each chapter is loaded only once the previous one is rendered and dropped, so that the
+/// chapters of a long document are never all held in memory at once.
+fn render_chapters<C: Borrow<Chapter>>(
+    document: &mut Document,
+    chapters: impl IntoIterator<Item = Result<C>>,
+    font_config: &FontConfig,
+) -> Result<()> {
+    for chapter in chapters {
+        match chapter?.borrow() {
+            Chapter::Ast(ast) => document.render(ast, font_config, DEFAULT_FONT_SIZE),
+            Chapter::Text(content) => {
+                document.write_content(content, font_config, DEFAULT_FONT_SIZE)
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the document described by a config as if it was built at a given date.
+pub fn build_document_at(config: &Config, now: OffsetDateTime) -> Result<Document> {
+    // The inputs are only checked here, and each of them is read, parsed and rendered in turn,
+    // see `render_chapters`.
+    for input in config.input.paths() {
+        fs::metadata(input).map_err(|e| input_error(input, e.into()))?;
+    }
+
     let (mut document, font_manager) = config.init()?;
     let font_config = font_manager.default_config();
 
-    let mut content = String::new();
-    let mut file = File::open(&config.input)?;
-    file.read_to_string(&mut content)?;
+    if !config.quiet {
+        for (file, e) in font_manager.skipped() {
+            eprintln!(
+                "{}skipped font \"{}\": {}",
+                "warning: ".bold().yellow(),
+                file,
+                e
+            );
+        }
+    }
 
-    if config.input.ends_with(".dex") {
-        let parsed = parse(&config.input)?;
-        println!("{}", parsed.warnings);
-        println!("{:?}", parsed.ast);
-        document.render(&parsed.ast, &font_config, Pt(10.0));
+    let today = config.format_date(now)?;
+    let mode = if config.draft {
+        BuildMode::Draft
     } else {
-        document.write_content(&content, &font_config, Pt(10.0));
+        BuildMode::Final
+    };
+
+    let mut warning_count = 0;
+    let chapters = config.input.paths().into_iter().map(|input| {
+        if input.ends_with(".dex") {
+            let mut parsed = parse(input)?;
+            let footnote_warnings = parsed.ast.resolve_footnotes();
+            parsed.warnings.warnings.extend(footnote_warnings);
+            warning_count += parsed.warnings.warnings.len();
+            parsed.ast.resolve_conditionals(mode);
+            parsed.ast.include_code()?;
+            parsed.ast.expand_today(&today);
+            if config.curly_apostrophes {
+                parsed.ast.curl_apostrophes();
+            }
+            parsed.ast.ligate(&config.typesetting.ligatures);
+            if !config.quiet {
+                eprint!("{}", parsed.warnings);
+                eprintln!("{:?}", parsed.ast);
+            }
+            Ok(Chapter::Ast(parsed.ast))
+        } else {
+            let content = read_source(input).map_err(|e| input_error(input, e))?;
+            Ok(Chapter::Text(content))
+        }
+    });
+    render_chapters(&mut document, chapters, &font_config)?;
+    document.check_last_page();
+
+    if let (true, Some(max), PageOverflow::Error) =
+        (document.clipped(), config.max_pages, config.page_overflow)
+    {
+        return Err(Error::TooManyPages(max));
+    }
+
+    if !config.quiet {
+        for warning in document.warnings() {
+            eprint!("{}", warning);
+        }
+
+        if config.line_stats {
+            eprintln!("{}", document.line_stats());
+        }
+    }
+
+    if let Some(font_cache) = &config.font_cache {
+        font_manager.save_metrics(font_cache)?;
+    }
+
+    warning_count += document.warnings().len();
+    if config.deny_warnings && warning_count > 0 {
+        return Err(Error::DeniedWarnings(warning_count));
+    }
+
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+    use std::cell::Cell;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::rc::Rc;
+
+    use printpdf::{OffsetDateTime, Pt};
+
+    use crate::document::configuration::{Config, Input, PageOverflow};
+    use crate::parser::parse;
+    use crate::parser::warning::WarningType;
+    use crate::{
+        build, build_document, build_document_at, render_chapters, Chapter, Error, Result,
+    };
+
+    #[test]
+    fn test_input_not_found() {
+        let mut config = Config::with_title("Test");
+        config.input = Input::File(String::from("assets/tests/does-not-exist.dex"));
+
+        match build(&config) {
+            Err(Error::InputNotFound(path)) => {
+                assert_eq!(path, PathBuf::from("assets/tests/does-not-exist.dex"))
+            }
+            _ => panic!("expected an input not found error"),
+        }
+    }
+
+    #[test]
+    fn test_many_input_files() {
+        let mut config = Config::with_title("Test");
+        config.input = Input::Files(vec![
+            String::from("assets/tests/successes/test-chapter-one.dex"),
+            String::from("assets/tests/successes/test-chapter-two.dex"),
+        ]);
+
+        let document = build_document(&config).unwrap();
+        assert_eq!(document.counters().to_string(), "2");
+    }
+
+    /// A chapter that keeps track of how many chapters are alive.
+    struct CountedChapter {
+        chapter: Chapter,
+        alive: Rc<Cell<usize>>,
+    }
+
+    impl CountedChapter {
+        fn new(chapter: Chapter, alive: &Rc<Cell<usize>>) -> CountedChapter {
+            alive.set(alive.get() + 1);
+            CountedChapter {
+                chapter,
+                alive: Rc::clone(alive),
+            }
+        }
+    }
+
+    impl Borrow<Chapter> for CountedChapter {
+        fn borrow(&self) -> &Chapter {
+            &self.chapter
+        }
+    }
+
+    impl Drop for CountedChapter {
+        fn drop(&mut self) {
+            self.alive.set(self.alive.get() - 1);
+        }
+    }
+
+    #[test]
+    fn test_render_chapters() -> Result<()> {
+        let (mut document, font_manager) = Config::with_title("Test").init()?;
+        let font_config = font_manager.default_config();
+
+        // Each chapter is dropped before the next one is parsed.
+        let alive = Rc::new(Cell::new(0));
+        let chapters = ["one", "two"].iter().cycle().take(10).map(|name| {
+            assert_eq!(alive.get(), 0);
+            let path = format!("assets/tests/successes/test-chapter-{}.dex", name);
+            Ok(CountedChapter::new(Chapter::Ast(parse(path)?.ast), &alive))
+        });
+        render_chapters(&mut document, chapters, &font_config)?;
+
+        assert_eq!(alive.get(), 0);
+        assert_eq!(document.counters().to_string(), "10");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_many_chapters() {
+        // The chapters of all the inputs are numbered in turn.
+        let chapters = ["one", "two"]
+            .iter()
+            .map(|name| format!("assets/tests/successes/test-chapter-{}.dex", name))
+            .cycle()
+            .take(50)
+            .collect();
+        let mut config = Config::with_title("Test");
+        config.input = Input::Files(chapters);
+        config.quiet = true;
+
+        let document = build_document(&config).unwrap();
+        assert_eq!(document.counters().to_string(), "50");
+    }
+
+    #[test]
+    fn test_max_pages() {
+        let mut config = Config::with_title("Test");
+        config.input = Input::Files(vec![
+            String::from("assets/tests/successes/test-chapter-one.dex"),
+            String::from("assets/tests/successes/test-chapter-two.dex"),
+        ]);
+        config.text_height = Pt(30.0);
+        config.max_pages = Some(1);
+        config.quiet = true;
+
+        let document = build_document(&config).unwrap();
+        assert!(document.clipped());
+
+        config.page_overflow = PageOverflow::Error;
+        match build_document(&config) {
+            Err(Error::TooManyPages(1)) => (),
+            _ => panic!("expected a too many pages error"),
+        }
+    }
+
+    #[test]
+    fn test_skipped_title_level() {
+        let mut config = Config::with_title("Test");
+        config.input = Input::File(String::from(
+            "assets/tests/successes/test-skipped-title-level.dex",
+        ));
+        config.quiet = true;
+
+        let document = build_document(&config).unwrap();
+        let text: String = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.glyph.as_str())
+            .collect();
+        assert_eq!(text, "1Introduction1.1.1Details");
+
+        let warnings = document.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].ty, WarningType::SkippedTitleLevel);
+        assert_eq!(warnings[0].content, "Details");
+    }
+
+    #[test]
+    fn test_today() {
+        let mut config = Config::with_title("Test");
+        config.input = Input::File(String::from("assets/tests/successes/test-today.dex"));
+        config.date_format = String::from("%d/%m/%Y");
+
+        let now = OffsetDateTime::from_unix_timestamp(1_000_000_000);
+        let document = build_document_at(&config, now).unwrap();
+        let text: String = document
+            .layout()
+            .iter()
+            .map(|glyph| glyph.glyph.as_str())
+            .collect();
+        assert_eq!(text, "Writtenon09/09/2001.");
+
+        // An invalid format fails the build instead of panicking.
+        config.date_format = String::from("100%");
+        match build_document_at(&config, now) {
+            Err(Error::InvalidConfig(_)) => (),
+            _ => panic!("expected an invalid config error"),
+        }
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let text = |draft| {
+            let mut config = Config::with_title("Test");
+            config.input =
+                Input::File(String::from("assets/tests/successes/test-conditionals.dex"));
+            config.draft = draft;
+
+            let document = build_document(&config).unwrap();
+            document
+                .layout()
+                .iter()
+                .map(|glyph| glyph.glyph.as_str())
+                .collect::<String>()
+        };
+
+        assert_eq!(text(true), "Introduction.Draftnote.Conclusion.");
+        assert_eq!(text(false), "Introduction.Finalnote.Conclusion.");
+    }
+
+    #[test]
+    fn test_include_code() {
+        let mut config = Config::with_title("Test");
+        config.input = Input::File(String::from("assets/tests/successes/test-include-code.dex"));
+
+        let document = build_document(&config).unwrap();
+        let code = document
+            .layout()
+            .iter()
+            .filter(|glyph| glyph.font.contains("Typewriter"))
+            .collect::<Vec<_>>();
+
+        // Each line of the code is on its own line, in the monospace font, with its numbers.
+        let mut lines: Vec<(f64, String)> = vec![];
+        for glyph in &code {
+            match lines.last_mut() {
+                Some((y, line)) if *y == glyph.y => line.push_str(&glyph.glyph),
+                _ => lines.push((glyph.y, glyph.glyph.clone())),
+            }
+        }
+        let lines = lines.into_iter().map(|(_, line)| line).collect::<Vec<_>>();
+        assert_eq!(lines, vec!["1fnmain(){", "2println!(\"Hi\");", "3}"]);
+
+        // The indentation of the second line is kept.
+        let x = |c: &str| code.iter().find(|glyph| glyph.glyph == c).unwrap().x;
+        assert!(x("p") > x("f") + 10.0);
+    }
+
+    #[test]
+    fn test_include_missing_code() {
+        let path = env::temp_dir().join(format!("spandex-include-{}.dex", process::id()));
+        fs::write(&path, "\\includecode{does-not-exist.rs}\n").unwrap();
+
+        let mut config = Config::with_title("Test");
+        config.input = Input::File(path.to_string_lossy().into_owned());
+        let result = build_document(&config);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(Error::IoError(_))));
     }
-    document.save("output.pdf");
-    Ok(())
 }
@@ -1,13 +1,18 @@
 use std::env::{self, current_dir};
 use std::fs::{create_dir_all, File};
 use std::io::{Read, Write};
-use std::process::exit;
+use std::path::Path;
+use std::process::{exit, Command};
 
 use colored::*;
 
 use spandex::document::configuration::Config;
+use spandex::parser::ast::Ast;
 use spandex::Error;
 
+/// The path of the generated pdf.
+const OUTPUT: &str = "output.pdf";
+
 macro_rules! unwrap {
     ($e: expr, $error: expr) => {
         match $e {
@@ -35,7 +40,13 @@ fn print_help() {
 
 {SUBCOMMANDS}
     {build}           Builds SpanDeX project
-    {init}    Creates new default SpanDeX project"#,
+        {deny_warnings}       Fails if any warning is raised
+        {dump_layout}    Writes the positioned glyphs as JSON
+        {open}                  Opens the generated pdf
+        {quiet}                 Only prints the errors
+    {fmt}             Rewrites the dex files of SpanDeX project in a canonical form
+    {init}    Creates new default SpanDeX project
+    {wordcount}       Counts the words of SpanDeX project"#,
         name = "SpanDeX".green(),
         version = env!("CARGO_PKG_VERSION"),
         description = env!("CARGO_PKG_DESCRIPTION"),
@@ -48,7 +59,13 @@ fn print_help() {
         version_long = "--version".green(),
         SUBCOMMANDS = "SUBCOMMANDS:".yellow(),
         build = "build".green(),
+        deny_warnings = "--deny-warnings".green(),
+        dump_layout = "--dump-layout <path>".green(),
+        open = "--open".green(),
+        quiet = "--quiet".green(),
+        fmt = "fmt".green(),
         init = "init [title]".green(),
+        wordcount = "wordcount".green(),
     );
 }
 
@@ -98,7 +115,54 @@ fn init(name: Option<&String>) -> Result<(), Error> {
     Ok(())
 }
 
-fn build() -> Result<(), Error> {
+/// Opens a file in the system viewer, or warns if there is no viewer to open it in, unless
+/// quiet.
+fn open(path: &Path, quiet: bool) {
+    let headless = env::var_os("CI").is_some()
+        || (cfg!(target_os = "linux")
+            && env::var_os("DISPLAY").is_none()
+            && env::var_os("WAYLAND_DISPLAY").is_none());
+
+    if headless {
+        if quiet {
+            return;
+        }
+
+        eprintln!(
+            "{}: no display available, not opening \"{}\"",
+            "warning".bold().yellow(),
+            path.display()
+        );
+        return;
+    }
+
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    if let Err(e) = command.arg(path).spawn() {
+        if quiet {
+            return;
+        }
+
+        eprintln!(
+            "{}: couldn't open \"{}\": {}",
+            "warning".bold().yellow(),
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Looks for the spandex.toml of the project in the current directory and its parents, and
+/// parses it.
+fn find_config() -> Result<Config, Error> {
     // Look up for spandex config file
     let mut current_dir = unwrap!(current_dir().ok(), Error::CannotReadCurrentDir);
     let config_path = loop {
@@ -121,8 +185,62 @@ fn build() -> Result<(), Error> {
     let mut file = File::open(&config_path)?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
-    let config: Config = toml::from_str(&content).expect("Failed to parse toml");
-    spandex::build(&config)?;
+    Ok(toml::from_str(&content).expect("Failed to parse toml"))
+}
+
+fn build(
+    dump_layout: Option<&String>,
+    open_output: bool,
+    quiet: bool,
+    deny_warnings: bool,
+) -> Result<(), Error> {
+    let mut config = find_config()?;
+    config.quiet = quiet;
+    config.deny_warnings |= deny_warnings;
+
+    let document = spandex::build_document(&config)?;
+    if let Some(path) = dump_layout {
+        document.dump_layout(path)?;
+    }
+    document.save(OUTPUT);
+
+    // Only reached when the build succeeded.
+    if open_output {
+        open(Path::new(OUTPUT), quiet);
+    }
+
+    Ok(())
+}
+
+fn fmt() -> Result<(), Error> {
+    let config = find_config()?;
+
+    for input in config.input.paths() {
+        if input.ends_with(".dex") {
+            let ast = spandex::parser::parse(input)?.ast;
+            File::create(input)?.write_all(ast.to_dex_string().as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn wordcount() -> Result<(), Error> {
+    let config = find_config()?;
+    let (mut words, mut chars) = (0, 0);
+
+    for input in config.input.paths() {
+        let ast = if input.ends_with(".dex") {
+            spandex::parser::parse(input)?.ast
+        } else {
+            Ast::Text(spandex::read_source(input)?)
+        };
+
+        words += ast.word_count();
+        chars += ast.char_count();
+    }
+
+    println!("{} words, {} characters", words, chars);
 
     Ok(())
 }
@@ -150,7 +268,27 @@ fn run() -> Result<(), Error> {
     match args[1].as_ref() {
         "init" => init(args.get(2))?,
 
-        "build" => build()?,
+        "build" => {
+            let dump_layout = args
+                .iter()
+                .position(|arg| arg == "--dump-layout")
+                .map(|i| args.get(i + 1));
+            let open_output = args.contains(&String::from("--open"));
+            let quiet = args.contains(&String::from("--quiet"));
+            let deny_warnings = args.contains(&String::from("--deny-warnings"));
+
+            match dump_layout {
+                Some(None) => {
+                    eprintln!("{}: --dump-layout expects a path", "error".bold().red());
+                    exit(1);
+                }
+                path => build(path.flatten(), open_output, quiet, deny_warnings)?,
+            }
+        }
+
+        "fmt" => fmt()?,
+
+        "wordcount" => wordcount()?,
 
         command => {
             // Unknwon command
@@ -1,12 +1,28 @@
 //! This module contains everything related to the ast.
 
 use std::fmt;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use colored::*;
 
+use crate::layout::paragraphs::apostrophes::curl_apostrophes;
+use crate::layout::paragraphs::ligatures::{ligature_with, Ligatures};
+use crate::parser::combinators::LIST_INDENT_WIDTH;
 use crate::parser::error::EmptyError;
-use crate::parser::warning::EmptyWarning;
+use crate::parser::warning::{EmptyWarning, WarningType};
+use crate::parser::Position;
+use crate::Error;
+
+/// The mode in which a document is built.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BuildMode {
+    /// A draft, for the reviewers.
+    Draft,
+
+    /// The final document.
+    Final,
+}
 
 /// The abstract syntax tree representing the parsed file.
 #[derive(PartialEq, Eq, Clone)]
@@ -37,11 +53,145 @@ pub enum Ast {
     /// It contains many elements but must be rendered on a single paragraph.
     Paragraph(Vec<Ast>),
 
+    /// An unordered list.
+    ///
+    /// It contains only `UnorderedListItem`s, the nesting of the items being given by their level.
+    UnorderedList(Vec<Ast>),
+
+    /// An item of an unordered list.
+    UnorderedListItem {
+        /// The level of nesting of the item, 0 being the top level.
+        level: u8,
+
+        /// The content of the item.
+        children: Vec<Ast>,
+    },
+
+    /// The beginning of the appendices.
+    ///
+    /// The titles after this marker are numbered with letters.
+    AppendixMarker,
+
+    /// The beginning of the main matter, written `\mainmatter`.
+    ///
+    /// It starts a new page, from which the pages are numbered from the first page number.
+    MainMatterMarker,
+
+    /// The end of the current page, written `\clearpage`.
+    ///
+    /// The content after it starts on a new page.
+    ClearPage,
+
+    /// The date at which the document is built, written `\today`.
+    ///
+    /// It is replaced by the formatted date before the document is rendered, see
+    /// `Ast::expand_today`.
+    Today,
+
+    /// Some content included only in a build mode, written `\ifdraft{...}` or `\iffinal{...}`.
+    ///
+    /// It is replaced by its content or removed before the document is rendered, see
+    /// `Ast::resolve_conditionals`.
+    Conditional {
+        /// The mode in which the content is included.
+        mode: BuildMode,
+
+        /// The content.
+        children: Vec<Ast>,
+    },
+
+    /// A comment for the reviewers of the document, written `[[note: comment]]`.
+    ///
+    /// It is not printed with the text, but attached to it as a pdf annotation.
+    Annotation(String),
+
+    /// A reference to a footnote, written `[^label]`.
+    ///
+    /// It is replaced by the footnote defined with the same label, see `Ast::resolve_footnotes`.
+    FootnoteReference {
+        /// The label of the footnote.
+        label: String,
+
+        /// The position of the reference, to warn about it if its footnote is not defined.
+        position: Position,
+    },
+
+    /// The definition of a footnote, written as a block starting with `[^label]:`.
+    FootnoteDefinition {
+        /// The label of the footnote.
+        label: String,
+
+        /// The position of the definition, to warn about it if it is not referenced.
+        position: Position,
+
+        /// The content of the footnote.
+        children: Vec<Ast>,
+    },
+
+    /// A footnote, in place of its reference.
+    Footnote {
+        /// The label of the footnote.
+        label: String,
+
+        /// The content of the footnote.
+        children: Vec<Ast>,
+    },
+
+    /// The inclusion of a source file as a code block, written `\includecode{path}`, or
+    /// `\includecode[numbers]{path}` to number its lines.
+    ///
+    /// It is replaced by the code block before the document is rendered, see
+    /// `Ast::include_code`.
+    CodeInclusion {
+        /// The path of the file, relative to the including file.
+        path: String,
+
+        /// Whether the lines of the code are numbered.
+        line_numbers: bool,
+    },
+
+    /// A block of code, written between lines of three backticks, the first ones followed by its
+    /// language, and rendered verbatim in a monospace font.
+    CodeBlock {
+        /// The language of the code, if it is known.
+        language: Option<String>,
+
+        /// The code, with its whitespace and line breaks.
+        content: String,
+
+        /// Whether the lines of the code are numbered.
+        line_numbers: bool,
+    },
+
+    /// Some content passed verbatim to a backend, written between lines of three backticks, the
+    /// first ones followed by the backend in braces, e.g. `{=pdf}`.
+    ///
+    /// It is dropped by the other backends, so that it never breaks their output.
+    Raw {
+        /// The backend the content is written for, e.g. `"pdf"`.
+        backend: String,
+
+        /// The content, with its whitespace and line breaks.
+        content: String,
+    },
+
+    /// Some math on its own lines, written between `$$`, and numbered if it is followed by
+    /// `\label{name}`.
+    DisplayMath {
+        /// The math, typeset as plain text for now.
+        content: String,
+
+        /// The label of the equation, if it is numbered.
+        label: Option<String>,
+    },
+
     /// Content stored in a specific file.
     File(PathBuf, Vec<Ast>),
 
-    /// An empty line.
-    Newline,
+    /// A comment, with its text after the `||`.
+    ///
+    /// It is not printed, but kept so that the source can be written back with it.
+    Comment(String),
 
     /// An error.
     ///
@@ -63,24 +213,239 @@ impl Ast {
             | Ast::Paragraph(children)
             | Ast::Title { children, .. }
             | Ast::Bold(children)
-            | Ast::Italic(children) => Some(children),
+            | Ast::Italic(children)
+            | Ast::UnorderedList(children)
+            | Ast::UnorderedListItem { children, .. }
+            | Ast::Conditional { children, .. }
+            | Ast::FootnoteDefinition { children, .. }
+            | Ast::Footnote { children, .. } => Some(children),
             _ => None,
         }
     }
 
-    /// Returns all the errors contained in the ast.
-    pub fn errors(&self) -> Vec<EmptyError> {
-        let mut errors = vec![];
-
-        if let Ast::Error(e) = self {
-            errors.push(e.clone());
+    /// Returns the children of the ast, if any, mutably.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Ast>> {
+        match self {
+            Ast::File(_, children)
+            | Ast::Paragraph(children)
+            | Ast::Title { children, .. }
+            | Ast::Bold(children)
+            | Ast::Italic(children)
+            | Ast::UnorderedList(children)
+            | Ast::UnorderedListItem { children, .. }
+            | Ast::Conditional { children, .. }
+            | Ast::FootnoteDefinition { children, .. }
+            | Ast::Footnote { children, .. } => Some(children),
+            _ => None,
         }
+    }
+
+    /// Calls a function on the ast and all its descendants, depth-first, each node before its
+    /// children.
+    pub fn visit(&self, f: &mut impl FnMut(&Ast)) {
+        f(self);
 
         if let Some(children) = self.children() {
             for child in children {
-                errors.extend(child.errors());
+                child.visit(f);
+            }
+        }
+    }
+
+    /// Calls a function on the ast and all its descendants mutably, depth-first, each node before
+    /// its children.
+    ///
+    /// The children are visited after the function was called on their parent, so the function
+    /// can replace them.
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Ast)) {
+        f(self);
+
+        if let Some(children) = self.children_mut() {
+            for child in children {
+                child.walk_mut(f);
             }
         }
+    }
+
+    /// Replaces each `\today` of the ast by the date, already formatted.
+    pub fn expand_today(&mut self, date: &str) {
+        self.walk_mut(&mut |ast| {
+            if let Ast::Today = ast {
+                *ast = Ast::Text(String::from(date));
+            }
+        });
+    }
+
+    /// Replaces each inclusion of a source file by a code block with the content of the file.
+    ///
+    /// The paths are relative to the directory of the file of the ast, if it is an `Ast::File`,
+    /// and the language of the code is given by the extension of the included file.
+    pub fn include_code(&mut self) -> Result<(), Error> {
+        let dir = match self {
+            Ast::File(path, _) => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            _ => PathBuf::new(),
+        };
+
+        let mut result = Ok(());
+        self.walk_mut(&mut |ast| {
+            if let (Ast::CodeInclusion { path, line_numbers }, Ok(())) = (&*ast, &result) {
+                match fs::read_to_string(dir.join(path)) {
+                    Ok(content) => {
+                        *ast = Ast::CodeBlock {
+                            language: Path::new(path)
+                                .extension()
+                                .map(|e| e.to_string_lossy().into_owned()),
+                            content,
+                            line_numbers: *line_numbers,
+                        }
+                    }
+                    Err(e) => result = Err(Error::from(e)),
+                }
+            }
+        });
+
+        result
+    }
+
+    /// Replaces the conditional content of the ast by its children if it matches the build mode,
+    /// and removes it otherwise.
+    ///
+    /// The nodes that are left empty once their conditional content is removed, like a paragraph
+    /// that only held draft content, are removed as well.
+    pub fn resolve_conditionals(&mut self, mode: BuildMode) {
+        if let Some(children) = self.children_mut() {
+            *children = resolve_conditionals(std::mem::take(children), mode);
+        }
+    }
+
+    /// Replaces the references to the footnotes of the ast by the footnotes defined with the same
+    /// labels, and removes the definitions.
+    ///
+    /// The definitions are the blocks of the ast. Returns warnings for the references to undefined
+    /// footnotes, which are left as they are, and for the definitions that are never referenced.
+    pub fn resolve_footnotes(&mut self) -> Vec<EmptyWarning> {
+        let mut definitions: Vec<Definition> = vec![];
+
+        if let Some(children) = self.children_mut() {
+            children.retain(|child| match child {
+                Ast::FootnoteDefinition {
+                    label,
+                    position,
+                    children,
+                } => {
+                    if definitions.iter().all(|d| d.label != *label) {
+                        definitions.push(Definition {
+                            label: label.clone(),
+                            position: *position,
+                            children: children.clone(),
+                            used: false,
+                        });
+                    }
+                    false
+                }
+                _ => true,
+            });
+        }
+
+        let mut warnings = vec![];
+        resolve_footnote_references(self, &mut definitions, &mut warnings);
+
+        for definition in definitions.iter().filter(|d| !d.used) {
+            warnings.push(EmptyWarning {
+                position: definition.position,
+                ty: WarningType::UnusedFootnote,
+            });
+        }
+
+        warnings
+    }
+
+    /// Replaces the letters of the text of the ast by their ligatures.
+    pub fn ligate(&mut self, ligatures: &Ligatures) {
+        self.walk_mut(&mut |ast| {
+            if let Ast::Text(text) = ast {
+                *text = ligature_with(text, ligatures);
+            }
+        });
+    }
+
+    /// Replaces the straight apostrophes between two letters of the text of the ast by
+    /// typographic ones.
+    pub fn curl_apostrophes(&mut self) {
+        self.walk_mut(&mut |ast| {
+            if let Ast::Text(text) = ast {
+                *text = curl_apostrophes(text);
+            }
+        });
+    }
+
+    /// Returns the number of words of the text of the ast, math excluded.
+    ///
+    /// A word may span many adjacent inline nodes, e.g. `un*believ*able` is a single word.
+    pub fn word_count(&self) -> usize {
+        self.count_words(&mut false)
+    }
+
+    /// Counts the words starting in the ast, knowing whether the text before it ended in the
+    /// middle of a word, and keeps track of whether the text of the ast does.
+    fn count_words(&self, in_word: &mut bool) -> usize {
+        match self {
+            Ast::Text(text) => {
+                let mut count = 0;
+                for c in text.chars() {
+                    if c.is_whitespace() {
+                        *in_word = false;
+                    } else if !*in_word {
+                        *in_word = true;
+                        count += 1;
+                    }
+                }
+                count
+            }
+
+            // The styles and the conditionals don't break the words of the text around them.
+            Ast::Bold(children) | Ast::Italic(children) | Ast::Conditional { children, .. } => {
+                children
+                    .iter()
+                    .map(|child| child.count_words(in_word))
+                    .sum()
+            }
+
+            // Any other node, from a paragraph to some math, separates the words around it.
+            _ => {
+                *in_word = false;
+                let count = self
+                    .children()
+                    .map(|children| children.iter().map(|c| c.count_words(in_word)).sum())
+                    .unwrap_or(0);
+                *in_word = false;
+                count
+            }
+        }
+    }
+
+    /// Returns the number of characters of the text of the ast, whitespaces and math excluded.
+    pub fn char_count(&self) -> usize {
+        let mut count = 0;
+
+        self.visit(&mut |ast| {
+            if let Ast::Text(text) = ast {
+                count += text.chars().filter(|c| !c.is_whitespace()).count();
+            }
+        });
+
+        count
+    }
+
+    /// Returns all the errors contained in the ast.
+    pub fn errors(&self) -> Vec<EmptyError> {
+        let mut errors = vec![];
+
+        self.visit(&mut |ast| {
+            if let Ast::Error(e) = ast {
+                errors.push(e.clone());
+            }
+        });
 
         errors
     }
@@ -89,17 +454,138 @@ impl Ast {
     pub fn warnings(&self) -> Vec<EmptyWarning> {
         let mut warnings = vec![];
 
-        if let Ast::Warning(e) = self {
-            warnings.push(e.clone());
-        }
+        self.visit(&mut |ast| {
+            if let Ast::Warning(e) = ast {
+                warnings.push(e.clone());
+            }
+        });
 
-        if let Some(children) = self.children() {
-            for child in children {
-                warnings.extend(child.warnings());
+        warnings
+    }
+
+    /// Writes the ast back as dex source, in a canonical form.
+    ///
+    /// The blocks are separated by a single empty line, the titles have a single space after
+    /// their hashes, and the list items are indented by `LIST_INDENT_WIDTH` spaces per level.
+    /// Parsing the source gives the ast back, without its warnings, since what they warn about
+    /// is either ignored or rewritten.
+    pub fn to_dex_string(&self) -> String {
+        let mut output = String::new();
+
+        match self {
+            Ast::File(_, children) => {
+                let blocks = children
+                    .iter()
+                    .map(|block| block.to_dex_string().trim_end().to_string())
+                    .filter(|block| !block.is_empty())
+                    .collect::<Vec<_>>();
+
+                if !blocks.is_empty() {
+                    output.push_str(&blocks.join("\n\n"));
+                    output.push('\n');
+                }
             }
+
+            Ast::Title { level, children } => {
+                output.push_str(&"#".repeat(*level as usize + 1));
+                output.push(' ');
+                write_dex_children(children, &mut output);
+            }
+
+            Ast::UnorderedList(children) => {
+                let items = children.iter().map(Ast::to_dex_string).collect::<Vec<_>>();
+                output.push_str(&items.join("\n"));
+            }
+
+            Ast::UnorderedListItem { level, children } => {
+                output.push_str(&" ".repeat(*level as usize * LIST_INDENT_WIDTH));
+                output.push_str("- ");
+                write_dex_children(children, &mut output);
+            }
+
+            Ast::Paragraph(children) => write_dex_children(children, &mut output),
+
+            Ast::Bold(children) => {
+                output.push('*');
+                write_dex_children(children, &mut output);
+                output.push('*');
+            }
+
+            Ast::Italic(children) => {
+                output.push('/');
+                write_dex_children(children, &mut output);
+                output.push('/');
+            }
+
+            Ast::Conditional { mode, children } => {
+                output.push_str(match mode {
+                    BuildMode::Draft => "\\ifdraft{",
+                    BuildMode::Final => "\\iffinal{",
+                });
+                write_dex_children(children, &mut output);
+                output.push('}');
+            }
+
+            Ast::InlineMath(content) => {
+                output.push('$');
+                output.push_str(&content.replace('$', "\\$"));
+                output.push('$');
+            }
+
+            Ast::DisplayMath { content, label } => {
+                output.push_str(&format!("$${}$$", content));
+                if let Some(label) = label {
+                    output.push_str(&format!("\\label{{{}}}", label));
+                }
+            }
+
+            Ast::Text(content) => output.push_str(content),
+            Ast::AppendixMarker => output.push_str("\\appendix"),
+            Ast::MainMatterMarker => output.push_str("\\mainmatter"),
+            Ast::ClearPage => output.push_str("\\clearpage"),
+            Ast::Today => output.push_str("\\today"),
+            Ast::Annotation(note) => output.push_str(&format!("[[note: {}]]", note)),
+            Ast::Comment(text) => output.push_str(&format!("||{}", text)),
+            Ast::FootnoteReference { label, .. } | Ast::Footnote { label, .. } => {
+                output.push_str(&format!("[^{}]", label))
+            }
+            Ast::FootnoteDefinition {
+                label, children, ..
+            } => {
+                output.push_str(&format!("[^{}]: ", label));
+                write_dex_children(children, &mut output);
+            }
+            Ast::CodeInclusion { path, line_numbers } => {
+                output.push_str("\\includecode");
+                if *line_numbers {
+                    output.push_str("[numbers]");
+                }
+                output.push_str(&format!("{{{}}}", path));
+            }
+            Ast::CodeBlock {
+                language, content, ..
+            } => {
+                output.push_str("```");
+                output.push_str(language.as_deref().unwrap_or_default());
+                output.push('\n');
+                output.push_str(content);
+                if !content.is_empty() && !content.ends_with('\n') {
+                    output.push('\n');
+                }
+                output.push_str("```");
+            }
+            Ast::Raw { backend, content } => {
+                output.push_str(&format!("```{{={}}}\n", backend));
+                output.push_str(content);
+                if !content.is_empty() && !content.ends_with('\n') {
+                    output.push('\n');
+                }
+                output.push_str("```");
+            }
+            Ast::Error(_) | Ast::Warning(_) => (),
         }
 
-        warnings
+        output
     }
 
     /// Pretty prints the ast.
@@ -118,9 +604,21 @@ impl Ast {
         };
 
         let delimiter2 = match self {
-            Ast::Error(_) | Ast::Warning(_) | Ast::Text(_) | Ast::Newline | Ast::InlineMath(_) => {
-                "──"
-            }
+            Ast::Error(_)
+            | Ast::Warning(_)
+            | Ast::Text(_)
+            | Ast::Comment(_)
+            | Ast::InlineMath(_)
+            | Ast::DisplayMath { .. }
+            | Ast::AppendixMarker
+            | Ast::MainMatterMarker
+            | Ast::ClearPage
+            | Ast::Today
+            | Ast::Annotation(_)
+            | Ast::FootnoteReference { .. }
+            | Ast::CodeInclusion { .. }
+            | Ast::CodeBlock { .. }
+            | Ast::Raw { .. } => "──",
             _ => "─┬",
         };
 
@@ -148,8 +646,30 @@ impl Ast {
                 &format!("{:?}", t).dimmed(),
                 ")".green()
             )?,
-            Ast::Newline => writeln!(fmt, "{}NewLine", new_indent)?,
+            Ast::Comment(text) => writeln!(fmt, "{}Comment({:?})", new_indent, text)?,
+            Ast::AppendixMarker => writeln!(fmt, "{}AppendixMarker", new_indent)?,
+            Ast::MainMatterMarker => writeln!(fmt, "{}MainMatterMarker", new_indent)?,
+            Ast::ClearPage => writeln!(fmt, "{}ClearPage", new_indent)?,
+            Ast::Today => writeln!(fmt, "{}Today", new_indent)?,
+            Ast::Annotation(note) => writeln!(fmt, "{}Annotation({:?})", new_indent, note)?,
+            Ast::FootnoteReference { label, .. } => {
+                writeln!(fmt, "{}FootnoteReference({:?})", new_indent, label)?
+            }
+            Ast::FootnoteDefinition { label, .. } => {
+                writeln!(fmt, "{}FootnoteDefinition({:?})", new_indent, label)?
+            }
+            Ast::Footnote { label, .. } => writeln!(fmt, "{}Footnote({:?})", new_indent, label)?,
+            Ast::CodeInclusion { path, .. } => {
+                writeln!(fmt, "{}CodeInclusion({:?})", new_indent, path)?
+            }
+            Ast::CodeBlock { language, .. } => {
+                writeln!(fmt, "{}CodeBlock({:?})", new_indent, language)?
+            }
+            Ast::Raw { backend, .. } => writeln!(fmt, "{}Raw({:?})", new_indent, backend)?,
             Ast::InlineMath(math) => writeln!(fmt, "{}Math({:?})", new_indent, math)?,
+            Ast::DisplayMath { content, label } => {
+                writeln!(fmt, "{}DisplayMath({:?}, {:?})", new_indent, content, label)?
+            }
             Ast::File(path, _) => writeln!(
                 fmt,
                 "{}{}",
@@ -168,6 +688,24 @@ impl Ast {
             Ast::Bold(_) => writeln!(fmt, "{}{}", new_indent, "Bold".cyan().bold())?,
 
             Ast::Italic(_) => writeln!(fmt, "{}{}", new_indent, "Italic".cyan().bold())?,
+
+            Ast::UnorderedList(_) => {
+                writeln!(fmt, "{}{}", new_indent, "UnorderedList".blue().bold())?
+            }
+
+            Ast::UnorderedListItem { level, .. } => writeln!(
+                fmt,
+                "{}{}",
+                new_indent,
+                &format!("UnorderedListItem(level={})", level).blue()
+            )?,
+
+            Ast::Conditional { mode, .. } => writeln!(
+                fmt,
+                "{}{}",
+                new_indent,
+                &format!("Conditional({:?})", mode).cyan()
+            )?,
         }
 
         if let Some(children) = self.children() {
@@ -190,8 +728,26 @@ impl fmt::Display for Ast {
                 }
             }
 
-            Ast::InlineMath(content) => write!(fmt, "${}$", content)?,
-            Ast::Text(content) => write!(fmt, "{}", content)?,
+            Ast::UnorderedListItem { level, .. } => {
+                write!(fmt, "{}- ", "  ".repeat(*level as usize))?;
+            }
+
+            Ast::AppendixMarker => write!(fmt, "\\appendix")?,
+            Ast::MainMatterMarker => write!(fmt, "\\mainmatter")?,
+            Ast::ClearPage => write!(fmt, "\\clearpage")?,
+            Ast::Today => write!(fmt, "\\today")?,
+            Ast::Annotation(note) => write!(fmt, "[[note: {}]]", note)?,
+            Ast::FootnoteReference { label, .. } | Ast::Footnote { label, .. } => {
+                write!(fmt, "[^{}]", label)?
+            }
+            Ast::FootnoteDefinition { label, .. } => write!(fmt, "[^{}]: ", label)?,
+            Ast::Conditional { mode, .. } => match mode {
+                BuildMode::Draft => write!(fmt, "\\ifdraft{{")?,
+                BuildMode::Final => write!(fmt, "\\iffinal{{")?,
+            },
+            Ast::InlineMath(content) => write!(fmt, "${}$", content.replace('$', "\\$"))?,
+            Ast::DisplayMath { content, .. } => write!(fmt, "$${}$$", content)?,
+            Ast::Text(content) | Ast::CodeBlock { content, .. } => write!(fmt, "{}", content)?,
             _ => (),
         }
 
@@ -201,10 +757,106 @@ impl fmt::Display for Ast {
             }
         }
 
+        if let Ast::Conditional { .. } = self {
+            write!(fmt, "}}")?;
+        }
+
+        if let Ast::UnorderedListItem { .. } = self {
+            writeln!(fmt)?;
+        }
+
         Ok(())
     }
 }
 
+/// The definition of a footnote, while the references are resolved.
+struct Definition {
+    /// The label of the footnote.
+    label: String,
+
+    /// The position of the definition.
+    position: Position,
+
+    /// The content of the footnote.
+    children: Vec<Ast>,
+
+    /// Whether the footnote is referenced.
+    used: bool,
+}
+
+/// Resolves the references to the footnotes of an ast, see `Ast::resolve_footnotes`.
+///
+/// The content of the footnotes is not searched for references, so that a footnote that refers to
+/// itself isn't expanded endlessly.
+fn resolve_footnote_references(
+    ast: &mut Ast,
+    definitions: &mut [Definition],
+    warnings: &mut Vec<EmptyWarning>,
+) {
+    if let Ast::FootnoteReference { label, position } = ast {
+        match definitions.iter_mut().find(|d| d.label == *label) {
+            Some(definition) => {
+                definition.used = true;
+                *ast = Ast::Footnote {
+                    label: label.clone(),
+                    children: definition.children.clone(),
+                };
+            }
+            None => warnings.push(EmptyWarning {
+                position: *position,
+                ty: WarningType::UndefinedFootnote,
+            }),
+        }
+        return;
+    }
+
+    if let Some(children) = ast.children_mut() {
+        for child in children {
+            resolve_footnote_references(child, definitions, warnings);
+        }
+    }
+}
+
+/// Writes some nodes back as dex source, see `Ast::to_dex_string`.
+fn write_dex_children(children: &[Ast], output: &mut String) {
+    for (i, child) in children.iter().enumerate() {
+        output.push_str(&child.to_dex_string());
+
+        // A comment runs until the end of its line, so what follows it starts on the next one.
+        if let (Ast::Comment(_), true) = (child, i + 1 < children.len()) {
+            output.push('\n');
+        }
+    }
+}
+
+/// Resolves the conditional content of some nodes, see `Ast::resolve_conditionals`.
+fn resolve_conditionals(children: Vec<Ast>, mode: BuildMode) -> Vec<Ast> {
+    let mut resolved = vec![];
+
+    for mut child in children {
+        if let Ast::Conditional { mode: m, children } = child {
+            if m == mode {
+                resolved.extend(resolve_conditionals(children, mode));
+            }
+            continue;
+        }
+
+        let emptied = match child.children_mut() {
+            Some(children) if !children.is_empty() => {
+                *children = resolve_conditionals(std::mem::take(children), mode);
+                children.is_empty()
+            }
+            _ => false,
+        };
+
+        if !emptied {
+            resolved.push(child);
+        }
+    }
+
+    resolved
+}
+
 impl fmt::Debug for Ast {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         self.print_debug(fmt, "", true)
@@ -5,27 +5,26 @@
 // Allow redundant closure because of nom.
 #![allow(clippy::redundant_closure)]
 
-use std::fs::File;
-use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take_till1, take_until};
+use nom::bytes::complete::{tag, take_till1, take_until, take_while};
 use nom::character::complete::{char, line_ending, not_line_ending, space0};
-use nom::combinator::{map, map_res, opt, rest, verify};
-use nom::multi::{fold_many0, many0, many1_count};
-use nom::sequence::delimited;
+use nom::combinator::{eof, map, map_res, opt, peek, rest, verify};
+use nom::error::ErrorKind;
+use nom::multi::{fold_many0, many0, many1, many1_count};
+use nom::sequence::{delimited, terminated};
 use nom::{IResult, Slice};
 
-use crate::layout::paragraphs::ligatures::ligature;
-use crate::parser::ast::Ast;
+use crate::parser::ast::{Ast, BuildMode};
 use crate::parser::error::{EmptyError, ErrorType, Errors};
 use crate::parser::warning::{EmptyWarning, WarningType, Warnings};
 use crate::parser::{position, Error, Parsed, Span};
+use crate::read_source;
 
 /// Returns true if the character passed as parameter changes the type of parsing we're going to do.
 pub fn should_stop(c: char) -> bool {
-    c == '*' || c == '/' || c == '$' || c == '|'
+    c == '*' || c == '/' || c == '$' || c == '|' || c == '\\' || c == '['
 }
 
 /// Creates an error.
@@ -86,10 +85,37 @@ pub fn parse_italic(input: Span) -> IResult<Span, Ast> {
 /// let input = Span::new("$x = 9$");
 /// let parse = parse_inline_math(input).unwrap().1;
 /// assert_eq!(parse, Ast::InlineMath(String::from("x = 9")));
+///
+/// let input = Span::new(r"$a \$ b$");
+/// let parse = parse_inline_math(input).unwrap().1;
+/// assert_eq!(parse, Ast::InlineMath(String::from("a $ b")));
 /// ```
 pub fn parse_inline_math(input: Span) -> IResult<Span, Ast> {
-    let (input, content) = in_between("$", input)?;
-    Ok((input, Ast::InlineMath(content.fragment().to_string())))
+    let (input, content) = delimited(tag("$"), take_inline_math_content, tag("$"))(input)?;
+    Ok((input, Ast::InlineMath(content)))
+}
+
+/// Takes the content of some inline math, up to the first `$` that is not escaped, and resolves
+/// the escaped `\$`.
+fn take_inline_math_content(input: Span) -> IResult<Span, String> {
+    let mut content = String::new();
+    let mut chars = input.fragment().char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars.peek().map(|&(_, next)| next) == Some('$') => {
+                chars.next();
+                content.push('$');
+            }
+            '$' => return Ok((input.slice(i..), content)),
+            c => content.push(c),
+        }
+    }
+
+    Err(nom::Err::Error(nom::error::Error::new(
+        input,
+        ErrorKind::TakeUntil,
+    )))
 }
 
 /// Parses a delimited element.
@@ -112,13 +138,13 @@ fn parse_delimited_unmatch_error(input: Span) -> IResult<Span, Ast> {
 /// # use spandex::parser::combinators::parse_comment;
 /// let input = Span::new("|| comment");
 /// let parse = parse_comment(input).unwrap().1;
-/// assert_eq!(parse, Ast::Newline);
+/// assert_eq!(parse, Ast::Comment(String::from(" comment")));
 /// ```
 pub fn parse_comment(input: Span) -> IResult<Span, Ast> {
     let (input, _) = tag("||")(input)?;
-    let (input, _) = not_line_ending(input)?;
+    let (input, text) = not_line_ending(input)?;
     let (input, _) = opt(line_ending)(input)?;
-    Ok((input, Ast::Newline))
+    Ok((input, Ast::Comment(text.fragment().to_string())))
 }
 
 /// Parses some multiline inline content.
@@ -128,9 +154,15 @@ pub fn parse_any(input: Span) -> IResult<Span, Ast> {
         parse_comment,
         parse_delimited,
         parse_delimited_unmatch_error,
+        parse_today,
+        parse_conditional,
+        parse_annotation,
+        parse_footnote_reference,
         map(tag("|"), |_| Ast::Text(String::from("|"))),
+        map(tag("\\"), |_| Ast::Text(String::from("\\"))),
+        map(tag("["), |_| Ast::Text(String::from("["))),
         map(take_till1(should_stop), |x: Span| {
-            Ast::Text(ligature(x.fragment()))
+            Ast::Text(x.fragment().to_string())
         }),
     ))(input)
 }
@@ -233,6 +265,357 @@ pub fn parse_title(input: Span) -> IResult<Span, Ast> {
     ))
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// For lists
+////////////////////////////////////////////////////////////////////////////////
+
+/// The number of spaces that makes one level of nesting in lists.
+///
+/// A tab counts as one level of nesting.
+pub const LIST_INDENT_WIDTH: usize = 2;
+
+/// Returns true if the line is the beginning of an item of an unordered list.
+///
+/// An empty item is a dash alone on its line, since the trailing spaces of the last line of a
+/// block are trimmed.
+pub fn is_unordered_list_item(line: &str) -> bool {
+    let line = line.trim_start_matches(&[' ', '\t'][..]);
+    line.starts_with("- ") || line.trim_end_matches(&['\r', '\n'][..]) == "-"
+}
+
+/// Takes the content of a list item, up to the beginning of the next item.
+fn take_list_item_content(input: Span) -> IResult<Span, Span> {
+    let mut offset = 0;
+
+    for line in input.fragment().split_inclusive('\n') {
+        if offset > 0 && is_unordered_list_item(line) {
+            break;
+        }
+        offset += line.len();
+    }
+
+    let content = input.slice(..offset);
+    let len_after_trimmed = content.fragment().trim_end_matches(&['\r', '\n'][..]).len();
+    Ok((input.slice(offset..), content.slice(..len_after_trimmed)))
+}
+
+/// Parses an item of an unordered list.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_unordered_list_item;
+/// let input = Span::new("  - Nested item");
+/// let item = parse_unordered_list_item(input).unwrap().1;
+/// assert_eq!(item, Ast::UnorderedListItem { level: 1, children: vec![
+///     Ast::Text(String::from("Nested item"))]
+/// });
+/// ```
+pub fn parse_unordered_list_item(input: Span) -> IResult<Span, Ast> {
+    let (input, indent) = take_while(|c| c == ' ' || c == '\t')(input)?;
+    let (input, _) = alt((
+        tag("- "),
+        terminated(tag("-"), peek(alt((line_ending, eof)))),
+    ))(input)?;
+    let (input, content) = take_list_item_content(input)?;
+    let (_, mut children) = parse_group(content)?;
+
+    let width = indent
+        .fragment()
+        .chars()
+        .map(|c| if c == '\t' { LIST_INDENT_WIDTH } else { 1 })
+        .sum::<usize>();
+
+    if let Some(tab) = indent.fragment().find('\t') {
+        children.insert(
+            0,
+            warning(indent.slice(tab..tab + 1), WarningType::TabInListIndent),
+        );
+    }
+
+    Ok((
+        input,
+        Ast::UnorderedListItem {
+            level: (width / LIST_INDENT_WIDTH) as u8,
+            children,
+        },
+    ))
+}
+
+/// Parses an unordered list.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_unordered_list;
+/// # fn main() -> spandex::Result<()> {
+/// let input = Span::new("- First item\n- Second item");
+/// let list = parse_unordered_list(input)?.1;
+/// assert_eq!(list, Ast::UnorderedList(vec![
+///     Ast::UnorderedListItem { level: 0, children: vec![Ast::Text(String::from("First item"))] },
+///     Ast::UnorderedListItem { level: 0, children: vec![Ast::Text(String::from("Second item"))] },
+/// ]));
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_unordered_list(input: Span) -> IResult<Span, Ast> {
+    map(many1(parse_unordered_list_item), Ast::UnorderedList)(input)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// For directives
+////////////////////////////////////////////////////////////////////////////////
+
+/// Parses the marker of the beginning of the appendices.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_appendix;
+/// let input = Span::new("\\appendix");
+/// let marker = parse_appendix(input).unwrap().1;
+/// assert_eq!(marker, Ast::AppendixMarker);
+/// ```
+pub fn parse_appendix(input: Span) -> IResult<Span, Ast> {
+    let (input, _) = tag("\\appendix")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = eof(input)?;
+    Ok((input, Ast::AppendixMarker))
+}
+
+/// Parses the marker of the beginning of the main matter.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_main_matter;
+/// let input = Span::new("\\mainmatter");
+/// let marker = parse_main_matter(input).unwrap().1;
+/// assert_eq!(marker, Ast::MainMatterMarker);
+/// ```
+pub fn parse_main_matter(input: Span) -> IResult<Span, Ast> {
+    let (input, _) = tag("\\mainmatter")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = eof(input)?;
+    Ok((input, Ast::MainMatterMarker))
+}
+
+/// Parses the end of a page.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_clear_page;
+/// let input = Span::new("\\clearpage");
+/// let marker = parse_clear_page(input).unwrap().1;
+/// assert_eq!(marker, Ast::ClearPage);
+/// ```
+pub fn parse_clear_page(input: Span) -> IResult<Span, Ast> {
+    let (input, _) = tag("\\clearpage")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = eof(input)?;
+    Ok((input, Ast::ClearPage))
+}
+
+/// Parses some display math, with the label that numbers it if any.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_display_math;
+/// let input = Span::new("$$ e = mc^2 $$ \\label{energy}");
+/// let parsed = parse_display_math(input).unwrap().1;
+/// assert_eq!(parsed, Ast::DisplayMath {
+///     content: String::from("e = mc^2"),
+///     label: Some(String::from("energy")),
+/// });
+/// ```
+pub fn parse_display_math(input: Span) -> IResult<Span, Ast> {
+    let (input, content) = in_between("$$", input)?;
+    let (input, _) = space0(input)?;
+    let (input, label) = opt(delimited(tag("\\label{"), take_until("}"), tag("}")))(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = eof(input)?;
+    Ok((
+        input,
+        Ast::DisplayMath {
+            content: content.fragment().trim().to_string(),
+            label: label.map(|label| label.fragment().trim().to_string()),
+        },
+    ))
+}
+
+/// Parses the date at which the document is built.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_group;
+/// let input = Span::new("Written on \\today.");
+/// let parsed = parse_group(input).unwrap().1;
+/// assert_eq!(parsed, vec![
+///     Ast::Text(String::from("Written on ")),
+///     Ast::Today,
+///     Ast::Text(String::from(".")),
+/// ]);
+/// ```
+pub fn parse_today(input: Span) -> IResult<Span, Ast> {
+    map(tag("\\today"), |_| Ast::Today)(input)
+}
+
+/// Parses some content included only in a build mode.
+/// ```
+/// # use spandex::parser::ast::{Ast, BuildMode};
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_conditional;
+/// let input = Span::new("\\ifdraft{*TODO*}");
+/// let parsed = parse_conditional(input).unwrap().1;
+/// assert_eq!(parsed, Ast::Conditional {
+///     mode: BuildMode::Draft,
+///     children: vec![Ast::Bold(vec![Ast::Text(String::from("TODO"))])],
+/// });
+///
+/// // The content ends at the brace that closes the one of the conditional.
+/// let input = Span::new("\\ifdraft{See \\iffinal{this} later} after");
+/// let (input, parsed) = parse_conditional(input).unwrap();
+/// assert_eq!(*input.fragment(), " after");
+/// assert_eq!(parsed, Ast::Conditional {
+///     mode: BuildMode::Draft,
+///     children: vec![
+///         Ast::Text(String::from("See ")),
+///         Ast::Conditional {
+///             mode: BuildMode::Final,
+///             children: vec![Ast::Text(String::from("this"))],
+///         },
+///         Ast::Text(String::from(" later")),
+///     ],
+/// });
+/// ```
+pub fn parse_conditional(input: Span) -> IResult<Span, Ast> {
+    let (input, mode) = alt((
+        map(tag("\\ifdraft{"), |_| BuildMode::Draft),
+        map(tag("\\iffinal{"), |_| BuildMode::Final),
+    ))(input)?;
+    let (input, content) = take_group_content(input)?;
+    let (input, _) = tag("}")(input)?;
+    let (_, children) = parse_group(content)?;
+    Ok((input, Ast::Conditional { mode, children }))
+}
+
+/// Takes the content of a group, up to the `}` that closes it, including the groups nested in
+/// it.
+fn take_group_content(input: Span) -> IResult<Span, Span> {
+    let mut depth = 0;
+
+    for (i, c) in input.fragment().char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Ok((input.slice(i..), input.slice(..i))),
+            '}' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    Err(nom::Err::Error(nom::error::Error::new(
+        input,
+        ErrorKind::TakeUntil,
+    )))
+}
+
+/// Parses the inclusion of a source file as a code block.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_code_inclusion;
+/// let input = Span::new("\\includecode[numbers]{src/main.rs}");
+/// let parsed = parse_code_inclusion(input).unwrap().1;
+/// assert_eq!(parsed, Ast::CodeInclusion {
+///     path: String::from("src/main.rs"),
+///     line_numbers: true,
+/// });
+/// ```
+pub fn parse_code_inclusion(input: Span) -> IResult<Span, Ast> {
+    let (input, _) = tag("\\includecode")(input)?;
+    let (input, numbers) = opt(tag("[numbers]"))(input)?;
+    let (input, path) = delimited(tag("{"), take_until("}"), tag("}"))(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = eof(input)?;
+    Ok((
+        input,
+        Ast::CodeInclusion {
+            path: path.fragment().trim().to_string(),
+            line_numbers: numbers.is_some(),
+        },
+    ))
+}
+
+/// Parses a comment for the reviewers of the document.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_annotation;
+/// let input = Span::new("[[note: check this figure]]");
+/// let parsed = parse_annotation(input).unwrap().1;
+/// assert_eq!(parsed, Ast::Annotation(String::from("check this figure")));
+/// ```
+pub fn parse_annotation(input: Span) -> IResult<Span, Ast> {
+    let (input, note) = delimited(tag("[[note:"), take_until("]]"), tag("]]"))(input)?;
+    Ok((input, Ast::Annotation(note.fragment().trim().to_string())))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// For footnotes
+////////////////////////////////////////////////////////////////////////////////
+
+/// Parses the label of a footnote, between `[^` and `]`.
+fn parse_footnote_label(input: Span) -> IResult<Span, String> {
+    let label = take_till1(|c: char| c == ']' || c.is_whitespace());
+    map(delimited(tag("[^"), label, tag("]")), |x: Span| {
+        x.fragment().to_string()
+    })(input)
+}
+
+/// Parses a reference to a footnote.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::{Position, Span};
+/// # use spandex::parser::combinators::parse_footnote_reference;
+/// let input = Span::new("[^1]");
+/// let parsed = parse_footnote_reference(input).unwrap().1;
+/// assert_eq!(parsed, Ast::FootnoteReference {
+///     label: String::from("1"),
+///     position: Position { line: 1, column: 1, offset: 0 },
+/// });
+/// ```
+pub fn parse_footnote_reference(input: Span) -> IResult<Span, Ast> {
+    let position = position(&input);
+    let (input, label) = parse_footnote_label(input)?;
+    Ok((input, Ast::FootnoteReference { label, position }))
+}
+
+/// Parses the definition of a footnote.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::{Position, Span};
+/// # use spandex::parser::combinators::parse_footnote_definition;
+/// let input = Span::new("[^1]: The footnote.");
+/// let parsed = parse_footnote_definition(input).unwrap().1;
+/// assert_eq!(parsed, Ast::FootnoteDefinition {
+///     label: String::from("1"),
+///     position: Position { line: 1, column: 1, offset: 0 },
+///     children: vec![Ast::Text(String::from("The footnote."))],
+/// });
+/// ```
+pub fn parse_footnote_definition(input: Span) -> IResult<Span, Ast> {
+    let position = position(&input);
+    let (input, label) = parse_footnote_label(input)?;
+    let (input, _) = tag(":")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, children) = parse_group(input)?;
+    let (input, _) = eof(input)?;
+    Ok((
+        input,
+        Ast::FootnoteDefinition {
+            label,
+            position,
+            children,
+        },
+    ))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // For main
 ////////////////////////////////////////////////////////////////////////////////
@@ -274,24 +657,166 @@ pub fn get_block(input: Span) -> IResult<Span, Span> {
 /// assert_eq!(block, Ast::Paragraph(vec![Ast::Text(String::from("First paragraph"))]));
 /// ```
 pub fn parse_block_content(input: Span) -> IResult<Span, Ast> {
-    alt((parse_title, parse_paragraph))(input)
+    alt((
+        parse_title,
+        parse_appendix,
+        parse_main_matter,
+        parse_clear_page,
+        parse_display_math,
+        parse_footnote_definition,
+        parse_code_inclusion,
+        parse_unordered_list,
+        parse_paragraph,
+    ))(input)
+}
+
+/// Parses a fenced block of code, between lines of three backticks, with the language of the
+/// code after the opening ones.
+///
+/// Unlike the other blocks, it may contain empty lines, so it is parsed before the content is
+/// split into blocks.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_code_block;
+/// let input = Span::new("```rust\nfn main() {}\n```");
+/// let parsed = parse_code_block(input).unwrap().1;
+/// assert_eq!(parsed, Ast::CodeBlock {
+///     language: Some(String::from("rust")),
+///     content: String::from("fn main() {}\n"),
+///     line_numbers: false,
+/// });
+/// ```
+pub fn parse_code_block(input: Span) -> IResult<Span, Ast> {
+    let (input, _) = tag("```")(input)?;
+    let (input, language) = not_line_ending(input)?;
+    let (input, _) = line_ending(input)?;
+    let (input, content) = take_code(input)?;
+    let (input, _) = tag("```")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = alt((line_ending, eof))(input)?;
+    let (input, _) = many0(line_ending)(input)?;
+
+    let language = language.fragment().trim();
+    Ok((
+        input,
+        Ast::CodeBlock {
+            language: (!language.is_empty()).then(|| language.to_string()),
+            content: content.fragment().to_string(),
+            line_numbers: false,
+        },
+    ))
+}
+
+/// Parses a fenced block of raw content, like a block of code whose opening backticks are
+/// followed by the backend of the content in braces.
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::Span;
+/// # use spandex::parser::combinators::parse_raw_block;
+/// let input = Span::new("```{=pdf}\n0 0 m 100 0 l S\n```");
+/// let parsed = parse_raw_block(input).unwrap().1;
+/// assert_eq!(parsed, Ast::Raw {
+///     backend: String::from("pdf"),
+///     content: String::from("0 0 m 100 0 l S\n"),
+/// });
+/// ```
+pub fn parse_raw_block(input: Span) -> IResult<Span, Ast> {
+    let (input, _) = tag("```{=")(input)?;
+    let (input, backend) = take_till1(|c| c == '}' || c == '\r' || c == '\n')(input)?;
+    let (input, _) = tag("}")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = line_ending(input)?;
+    let (input, content) = take_code(input)?;
+    let (input, _) = tag("```")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = alt((line_ending, eof))(input)?;
+    let (input, _) = many0(line_ending)(input)?;
+
+    Ok((
+        input,
+        Ast::Raw {
+            backend: backend.fragment().trim().to_string(),
+            content: content.fragment().to_string(),
+        },
+    ))
+}
+
+/// Takes the lines of a fenced block of code, up to the line that starts with the closing
+/// backticks.
+fn take_code(input: Span) -> IResult<Span, Span> {
+    let end = match input.fragment().starts_with("```") {
+        true => Some(0),
+        false => input.fragment().find("\n```").map(|i| i + 1),
+    };
+
+    match end {
+        Some(end) => Ok((input.slice(end..), input.slice(..end))),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            ErrorKind::TakeUntil,
+        ))),
+    }
 }
 
 /// Parses a whole dex file.
 pub fn parse_content(input: &str) -> IResult<Span, Vec<Ast>> {
-    let parse_block = map_res(get_block, parse_block_content);
-    fold_many0(parse_block, Vec::new, |mut content: Vec<_>, (_, block)| {
+    let parse_block = alt((
+        parse_raw_block,
+        parse_code_block,
+        map(map_res(get_block, parse_block_content), |(_, block)| block),
+    ));
+    fold_many0(parse_block, Vec::new, |mut content: Vec<_>, block| {
         content.push(block);
         content
     })(Span::new(input))
 }
 
+/// Parses some dex content without stopping at the errors.
+///
+/// The errors are left in place in the returned ast, as `Ast::Error` nodes, and are also returned
+/// on their own, so that the valid parts of the content can still be rendered, e.g. in a live
+/// preview. The returned ast is a `Ast::File` with an empty path.
+///
+/// # Example
+///
+/// ```
+/// # use spandex::parser::ast::Ast;
+/// # use spandex::parser::parse_lenient;
+/// let (ast, errors) = parse_lenient("Hello *world\n\nGoodbye");
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(ast.children().unwrap().len(), 2);
+/// ```
+pub fn parse_lenient(content: &str) -> (Ast, Vec<EmptyError>) {
+    let elements = match parse_content(content) {
+        Ok((_, elements)) => elements,
+        Err(_) => unreachable!(),
+    };
+
+    let ast = Ast::File(PathBuf::new(), elements);
+    let errors = ast.errors();
+    (ast, errors)
+}
+
+/// Returns a warning for each control character of some content that is not a whitespace,
+/// since it is kept in the text but never drawn.
+fn control_character_warnings(content: &str) -> Vec<EmptyWarning> {
+    let span = Span::new(content);
+
+    content
+        .char_indices()
+        .filter(|&(_, c)| c.is_control() && !c.is_whitespace())
+        .map(|(i, _)| EmptyWarning {
+            position: position(&span.slice(i..)),
+            ty: WarningType::ControlCharacter,
+        })
+        .collect()
+}
+
 /// Parses a whole dex file from a name.
 pub fn parse<P: AsRef<Path>>(path: P) -> Result<Parsed, Error> {
     let path = path.as_ref();
-    let mut file = File::open(&path)?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
+    let content = read_source(path)?;
 
     let elements = match parse_content(&content) {
         Ok((_, elements)) => elements,
@@ -301,7 +826,8 @@ pub fn parse<P: AsRef<Path>>(path: P) -> Result<Parsed, Error> {
     let ast = Ast::File(PathBuf::from(path), elements);
 
     let errors = ast.errors();
-    let warnings = ast.warnings();
+    let mut warnings = ast.warnings();
+    warnings.extend(control_character_warnings(&content));
 
     if errors.is_empty() {
         Ok(Parsed {
@@ -6,7 +6,7 @@ use std::path::PathBuf;
 
 use colored::*;
 
-use crate::parser::utils::{next_new_line, previous_new_line, replicate};
+use crate::parser::utils::{caret_margin, next_new_line, previous_new_line, replicate};
 use crate::parser::Position;
 
 /// The different types errors that can occur while parsing.
@@ -91,7 +91,7 @@ impl fmt::Display for Errors {
 
             let line_number = format!("{} ", line);
             let space = replicate(' ', line_number.len() - 1);
-            let margin = replicate(' ', column);
+            let margin = caret_margin(&self.content, start, error.position.offset);
             let hats = replicate('^', 1);
 
             writeln!(fmt, "{}{}", "error: ".bold().red(), error.ty.title().bold())?;
@@ -50,4 +50,4 @@ pub struct Parsed {
     pub warnings: Warnings,
 }
 
-pub use combinators::parse;
+pub use combinators::{parse, parse_lenient};
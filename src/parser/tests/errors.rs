@@ -1,7 +1,11 @@
 //! This module contains the tests that should fail and checks that the error messages are correct.
 
+use crate::parser::ast::Ast;
+use crate::parser::combinators::{parse_bold, parse_unordered_list};
 use crate::parser::error::ErrorType;
-use crate::parser::parse;
+use crate::parser::{parse, parse_lenient, Position, Span};
+use unicode_width::UnicodeWidthStr;
+
 use crate::{Error, Result};
 
 macro_rules! to_dex_error {
@@ -108,3 +112,87 @@ fn test_accent() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_wide_char() -> Result<()> {
+    let p = parse("assets/tests/errors/test-wide-char.dex");
+    let p = to_dex_error!(p);
+    assert_eq!(p.errors.len(), 1);
+    assert_eq!(p.errors[0].position.column, 8);
+
+    // Strip the colors and look for the caret under the star.
+    let display = p.to_string();
+    let display = display.split('\x1b').fold(String::new(), |acc, part| {
+        acc + part.find('m').map_or(part, |i| &part[i + 1..])
+    });
+
+    let lines = display.lines().collect::<Vec<_>>();
+    let source = lines.iter().find(|line| line.contains("*bold")).unwrap();
+    let caret = lines.iter().find(|line| line.contains('^')).unwrap();
+
+    let width = |line: &str, c| line[..line.find(c).unwrap()].width();
+    assert_eq!(width(caret, '^'), width(source, '*'));
+
+    Ok(())
+}
+
+#[test]
+fn test_lenient_unmatched_star() -> Result<()> {
+    let (ast, errors) = parse_lenient("Hello *world\n\nGoodbye");
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].ty, ErrorType::UnmatchedStar);
+
+    let blocks = ast.children().unwrap();
+    match &blocks[0] {
+        Ast::Paragraph(children) => {
+            assert_eq!(children[0], Ast::Text(String::from("Hello ")));
+            assert!(matches!(children[1], Ast::Error(_)));
+        }
+        _ => panic!("expected a paragraph"),
+    }
+    assert_eq!(
+        blocks[1],
+        Ast::Paragraph(vec![Ast::Text(String::from("Goodbye"))])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_utf8() -> Result<()> {
+    match parse("assets/tests/errors/test-invalid-utf8.dex") {
+        Err(Error::InvalidUtf8(path, offset)) => {
+            assert_eq!(
+                path.to_str(),
+                Some("assets/tests/errors/test-invalid-utf8.dex")
+            );
+            assert_eq!(offset, 6);
+        }
+        _ => panic!("expected an invalid utf-8 error"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_combinator_error() -> Result<()> {
+    // The results of the combinators can be propagated with `?`.
+    let (_, list) = parse_unordered_list(Span::new("- First item\n- Second item"))?;
+    assert_eq!(list.children().map(|items| items.len()), Some(2));
+
+    let p = parse_bold(Span::new("Not bold"));
+    match Error::from(p.unwrap_err()) {
+        Error::CombinatorError(Some(position), _) => assert_eq!(
+            position,
+            Position {
+                line: 1,
+                column: 1,
+                offset: 0
+            }
+        ),
+        _ => panic!("expected a combinator error"),
+    }
+
+    Ok(())
+}
@@ -1,9 +1,12 @@
 //! This module contains the tests that should success and checks that the ast is correct.
 
 use std::error::Error;
+use std::fs;
 use std::path::PathBuf;
 
-use crate::parser::{parse, Ast};
+use crate::parser::combinators::parse_content;
+use crate::parser::warning::WarningType;
+use crate::parser::{parse, parse_lenient, Ast, Position};
 
 #[test]
 fn test_title_1() -> Result<(), Box<dyn Error>> {
@@ -73,3 +76,467 @@ fn test_titles() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_unordered_list() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-unordered-list.dex";
+    let p = parse(path);
+    assert!(p.is_ok());
+
+    let ast = p.unwrap().ast;
+
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![Ast::UnorderedList(vec![
+            Ast::UnorderedListItem {
+                level: 0,
+                children: vec![Ast::Text("First item".into())],
+            },
+            Ast::UnorderedListItem {
+                level: 0,
+                children: vec![Ast::Text("Second item\n  that spans two lines".into())],
+            },
+        ])],
+    );
+
+    assert_eq!(expected_ast, ast);
+
+    Ok(())
+}
+
+#[test]
+fn test_empty_list_items() -> Result<(), Box<dyn Error>> {
+    // A dash at the start of a line with no space after it is an empty item, even the last one
+    // whose trailing space was trimmed with the block.
+    let path = "assets/tests/successes/test-empty-list-items.dex";
+    let ast = parse(path)?.ast;
+
+    let item = |text: &str| Ast::UnorderedListItem {
+        level: 0,
+        children: match text {
+            "" => vec![],
+            text => vec![Ast::Text(text.into())],
+        },
+    };
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![Ast::UnorderedList(vec![
+            item("First item"),
+            item(""),
+            item("Third item"),
+            item(""),
+        ])],
+    );
+
+    assert_eq!(expected_ast, ast);
+
+    Ok(())
+}
+
+#[test]
+fn test_nested_unordered_list() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-nested-unordered-list.dex";
+    let p = parse(path);
+    assert!(p.is_ok());
+
+    let ast = p.unwrap().ast;
+
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![Ast::UnorderedList(vec![
+            Ast::UnorderedListItem {
+                level: 0,
+                children: vec![Ast::Text("First item".into())],
+            },
+            Ast::UnorderedListItem {
+                level: 1,
+                children: vec![Ast::Text("Nested item".into())],
+            },
+            Ast::UnorderedListItem {
+                level: 2,
+                children: vec![Ast::Text("Deeply nested item".into())],
+            },
+            Ast::UnorderedListItem {
+                level: 0,
+                children: vec![Ast::Text("Second item".into())],
+            },
+        ])],
+    );
+
+    assert_eq!(expected_ast, ast);
+
+    Ok(())
+}
+
+#[test]
+fn test_tab_in_list_indent() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-tab-in-list-indent.dex";
+    let p = parse(path);
+    assert!(p.is_ok());
+
+    let p = p.unwrap();
+
+    // The tab counts as one level of nesting.
+    match &p.ast {
+        Ast::File(_, children) => match &children[0] {
+            Ast::UnorderedList(items) => {
+                assert!(matches!(items[1], Ast::UnorderedListItem { level: 1, .. }))
+            }
+            _ => panic!("expected an unordered list"),
+        },
+        _ => panic!("expected a file"),
+    }
+
+    assert_eq!(p.warnings.warnings.len(), 1);
+
+    let w = &p.warnings.warnings[0];
+    assert_eq!(w.ty, WarningType::TabInListIndent);
+    assert_eq!(w.position.line, 2);
+    assert_eq!(w.position.column, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_visit() -> Result<(), Box<dyn Error>> {
+    let mut ast = parse("assets/tests/successes/test-titles.dex")?.ast;
+
+    let mut nodes = 0;
+    let mut titles = 0;
+    ast.visit(&mut |node| {
+        nodes += 1;
+        if let Ast::Title { .. } = node {
+            titles += 1;
+        }
+    });
+
+    assert_eq!(nodes, 5);
+    assert_eq!(titles, 2);
+
+    ast.walk_mut(&mut |node| {
+        if let Ast::Text(text) = node {
+            *text = text.to_uppercase();
+        }
+    });
+
+    let mut texts = vec![];
+    ast.visit(&mut |node| {
+        if let Ast::Text(text) = node {
+            texts.push(text.clone());
+        }
+    });
+
+    assert_eq!(texts, vec!["A TITLE", "WITH ITS SUBTITLE"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_word_count() -> Result<(), Box<dyn Error>> {
+    let ast = parse("assets/tests/successes/test-titles.dex")?.ast;
+
+    assert_eq!(ast.word_count(), 5);
+    assert_eq!(ast.char_count(), 21);
+
+    // A word is counted once even if it is split between styles, but not between paragraphs.
+    let ast = Ast::File(
+        PathBuf::from("main.dex"),
+        vec![
+            Ast::Paragraph(vec![
+                Ast::Text("Quite un".into()),
+                Ast::Bold(vec![Ast::Italic(vec![Ast::Text("believ".into())])]),
+                Ast::Text("able".into()),
+            ]),
+            Ast::Paragraph(vec![Ast::Text("indeed".into())]),
+        ],
+    );
+    assert_eq!(ast.word_count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_bom() -> Result<(), Box<dyn Error>> {
+    let with_bom = parse("assets/tests/successes/test-bom.dex")?.ast;
+    let without_bom = parse("assets/tests/successes/test-titles.dex")?.ast;
+
+    assert_eq!(with_bom.children(), without_bom.children());
+
+    Ok(())
+}
+
+#[test]
+fn test_control_characters() -> Result<(), Box<dyn Error>> {
+    let p = parse("assets/tests/successes/test-control-characters.dex")?;
+
+    // The bell is kept in the text, since it is dropped when laid out, but it is warned about.
+    assert_eq!(p.ast.word_count(), 4);
+    assert_eq!(p.warnings.warnings.len(), 1);
+
+    let w = &p.warnings.warnings[0];
+    assert_eq!(w.ty, WarningType::ControlCharacter);
+    assert_eq!(w.position.line, 1);
+    assert_eq!(w.position.column, 7);
+
+    Ok(())
+}
+
+#[test]
+fn test_main_matter() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-main-matter.dex";
+    let ast = parse(path)?.ast;
+
+    let title = |text: &str| Ast::Title {
+        level: 0,
+        children: vec![Ast::Text(text.into())],
+    };
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![
+            title("Preface"),
+            Ast::MainMatterMarker,
+            title("Introduction"),
+        ],
+    );
+
+    assert_eq!(expected_ast, ast);
+
+    Ok(())
+}
+
+#[test]
+fn test_clear_page() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-clear-page.dex";
+    let ast = parse(path)?.ast;
+
+    let title = |text: &str| Ast::Title {
+        level: 0,
+        children: vec![Ast::Text(text.into())],
+    };
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![title("Figures"), Ast::ClearPage, title("Results")],
+    );
+
+    assert_eq!(expected_ast, ast);
+    assert_eq!(
+        ast.to_dex_string(),
+        "# Figures\n\n\\clearpage\n\n# Results\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_fmt() -> Result<(), Box<dyn Error>> {
+    let formatted = parse("assets/tests/successes/test-fmt.dex")?
+        .ast
+        .to_dex_string();
+
+    let expected = "# Formatting\n\n\
+                    Some *bold* and /italic/ text with $a \\$ b$ math || a comment\n\
+                    and a [[note: check this]] note.\n\n  \
+                    - First item || with a comment\n\
+                    - Second item\n\n\
+                    \\ifdraft{Only in drafts.}\n";
+    assert_eq!(formatted, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_fmt_round_trip() -> Result<(), Box<dyn Error>> {
+    // The warnings are dropped by the formatter, since what they warn about is rewritten.
+    fn without_warnings(mut ast: Ast) -> Ast {
+        ast.walk_mut(&mut |ast| {
+            if let Some(children) = ast.children_mut() {
+                children.retain(|child| !matches!(child, Ast::Warning(_)));
+            }
+        });
+        ast
+    }
+
+    for entry in fs::read_dir("assets/tests/successes")? {
+        let path = entry?.path();
+        let ast = parse(&path)?.ast;
+
+        let formatted = ast.to_dex_string();
+        let (_, children) = parse_content(&formatted).unwrap();
+        let reparsed = Ast::File(path.clone(), children);
+
+        assert_eq!(reparsed, without_warnings(ast), "{}", path.display());
+        assert_eq!(reparsed.to_dex_string(), formatted, "{}", path.display());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_footnotes() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-footnotes.dex";
+    let ast = parse(path)?.ast;
+
+    let position = |line, column, offset| Position {
+        line,
+        column,
+        offset,
+    };
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![
+            Ast::Paragraph(vec![
+                Ast::Text("Some text".into()),
+                Ast::FootnoteReference {
+                    label: "1".into(),
+                    position: position(1, 10, 9),
+                },
+                Ast::Text(" and ".into()),
+                Ast::Bold(vec![Ast::Text("more".into())]),
+                Ast::FootnoteReference {
+                    label: "note".into(),
+                    position: position(1, 25, 24),
+                },
+                Ast::Text(".".into()),
+            ]),
+            Ast::FootnoteDefinition {
+                label: "1".into(),
+                position: position(3, 1, 34),
+                children: vec![
+                    Ast::Text("The ".into()),
+                    Ast::Bold(vec![Ast::Text("first".into())]),
+                    Ast::Text(" footnote.".into()),
+                ],
+            },
+            Ast::FootnoteDefinition {
+                label: "note".into(),
+                position: position(5, 1, 63),
+                children: vec![Ast::Text("Another\nfootnote.".into())],
+            },
+        ],
+    );
+
+    assert_eq!(expected_ast, ast);
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_footnotes() {
+    let (mut ast, _) = parse_lenient("A[^1] and B[^missing].\n\n[^1]: One.\n\n[^unused]: Two.");
+    let warnings = ast.resolve_footnotes();
+
+    let expected_ast = Ast::File(
+        PathBuf::new(),
+        vec![Ast::Paragraph(vec![
+            Ast::Text("A".into()),
+            Ast::Footnote {
+                label: "1".into(),
+                children: vec![Ast::Text("One.".into())],
+            },
+            Ast::Text(" and B".into()),
+            Ast::FootnoteReference {
+                label: "missing".into(),
+                position: Position {
+                    line: 1,
+                    column: 12,
+                    offset: 11,
+                },
+            },
+            Ast::Text(".".into()),
+        ])],
+    );
+    assert_eq!(ast, expected_ast);
+
+    let warnings = warnings
+        .iter()
+        .map(|w| (w.ty, w.position.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        vec![
+            (WarningType::UndefinedFootnote, 1),
+            (WarningType::UnusedFootnote, 5)
+        ]
+    );
+}
+
+#[test]
+fn test_include_code() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-include-code.dex";
+    let mut ast = parse(path)?.ast;
+
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![
+            Ast::Paragraph(vec![Ast::Text("The program:".into())]),
+            Ast::CodeInclusion {
+                path: "../code/hello.rs".into(),
+                line_numbers: true,
+            },
+        ],
+    );
+    assert_eq!(ast, expected_ast);
+
+    // The path is relative to the including file.
+    ast.include_code()?;
+    let code = Ast::CodeBlock {
+        language: Some("rs".into()),
+        content: fs::read_to_string("assets/tests/code/hello.rs")?,
+        line_numbers: true,
+    };
+    assert_eq!(ast.children().unwrap()[1], code);
+
+    Ok(())
+}
+
+#[test]
+fn test_code_block() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-code-block.dex";
+    let ast = parse(path)?.ast;
+
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![
+            Ast::Paragraph(vec![Ast::Text("Some code:".into())]),
+            Ast::CodeBlock {
+                language: Some("rust".into()),
+                content: "fn main() {\n    let x = 1;\n\n    println!(\"{}\",   x);\n}\n".into(),
+                line_numbers: false,
+            },
+            Ast::CodeBlock {
+                language: None,
+                content: "".into(),
+                line_numbers: false,
+            },
+            Ast::Paragraph(vec![Ast::Text("The end.".into())]),
+        ],
+    );
+    assert_eq!(ast, expected_ast);
+
+    Ok(())
+}
+
+#[test]
+fn test_raw() -> Result<(), Box<dyn Error>> {
+    let path = "assets/tests/successes/test-raw.dex";
+    let ast = parse(path)?.ast;
+
+    let expected_ast = Ast::File(
+        PathBuf::from(path),
+        vec![
+            Ast::Paragraph(vec![Ast::Text("A red rule:".into())]),
+            Ast::Raw {
+                backend: "pdf".into(),
+                content: "q 1 0 0 RG 72 700 m 540 700 l S Q\n".into(),
+            },
+            Ast::Raw {
+                backend: "svg".into(),
+                content: "<line x1=\"0\" y1=\"0\" x2=\"100\" y2=\"0\" stroke=\"red\"/>\n".into(),
+            },
+        ],
+    );
+    assert_eq!(expected_ast, ast);
+    assert_eq!(ast.to_dex_string(), fs::read_to_string(path)?);
+
+    Ok(())
+}
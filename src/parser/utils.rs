@@ -1,5 +1,7 @@
 //! This module contains some functions that will help us managing strings.
 
+use unicode_width::UnicodeWidthChar;
+
 /// Replicates a char n times.
 pub fn replicate(c: char, n: usize) -> String {
     let mut string = String::new();
@@ -9,6 +11,26 @@ pub fn replicate(c: char, n: usize) -> String {
     string
 }
 
+/// Computes the margin that puts a caret under the char at the specified byte, when printed
+/// after the line that starts at the specified start.
+///
+/// Tabs are kept as they are, since their width depends on the terminal, and every other char
+/// is replaced by as many spaces as it takes columns once displayed, so that wide chars count
+/// twice.
+pub fn caret_margin(content: &str, start: usize, byte: usize) -> String {
+    let prefix = content.get(start..byte).unwrap_or("");
+    let mut margin = String::from(" ");
+
+    for c in prefix.chars() {
+        match c {
+            '\t' => margin.push('\t'),
+            c => margin.push_str(&replicate(' ', c.width().unwrap_or(0))),
+        }
+    }
+
+    margin
+}
+
 /// Returns true if the char at specified byte is a \n.
 pub fn is_new_line(content: &str, byte: usize) -> bool {
     content.is_char_boundary(byte)
@@ -43,7 +65,7 @@ pub fn previous_new_line(content: &str, byte: usize) -> usize {
         i -= 1;
     }
 
-    if &content[i..=i] == "\n" {
+    if content.as_bytes().get(i) == Some(&b'\n') {
         i + 1
     } else {
         i
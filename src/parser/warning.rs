@@ -5,7 +5,7 @@ use std::path::PathBuf;
 
 use colored::*;
 
-use crate::parser::utils::{next_new_line, previous_new_line, replicate};
+use crate::parser::utils::{caret_margin, next_new_line, previous_new_line, replicate};
 use crate::parser::Position;
 
 /// The different types of warning that can occur.
@@ -13,6 +13,45 @@ use crate::parser::Position;
 pub enum WarningType {
     /// Two consecutive stars only seperated by whitespaces.
     ConsecutiveStars,
+
+    /// A tab in the indentation of a list item.
+    TabInListIndent,
+
+    /// A title wider than the text, once typeset on a single line.
+    TitleOverflow,
+
+    /// A line that exceeds the text by more than the overfull tolerance.
+    OverfullLine,
+
+    /// A line whose badness exceeds the underfull threshold.
+    UnderfullLine,
+
+    /// A word wider than the line, broken between its characters.
+    EmergencyBreak,
+
+    /// A reference to a footnote that is not defined.
+    UndefinedFootnote,
+
+    /// The definition of a footnote that is never referenced.
+    UnusedFootnote,
+
+    /// Content laid out after the last page allowed by the configuration.
+    ContentClipped,
+
+    /// A list marker that the font can't draw.
+    MissingListMarker,
+
+    /// A title whose level is deeper than the one below the previous title.
+    SkippedTitleLevel,
+
+    /// A page that holds fewer lines than the minimum of the configuration.
+    SparsePage,
+
+    /// Some raw pdf content that doesn't close the graphics states or the text objects it opens.
+    UnbalancedRaw,
+
+    /// A control character that is not a whitespace, like a bell.
+    ControlCharacter,
 }
 
 impl WarningType {
@@ -20,6 +59,19 @@ impl WarningType {
     pub fn title(self) -> &'static str {
         match self {
             WarningType::ConsecutiveStars => "empty bold section",
+            WarningType::TabInListIndent => "tab in the indentation of a list item",
+            WarningType::TitleOverflow => "title wider than the text",
+            WarningType::OverfullLine => "overfull line",
+            WarningType::UnderfullLine => "underfull line",
+            WarningType::EmergencyBreak => "word wider than the line",
+            WarningType::UndefinedFootnote => "undefined footnote",
+            WarningType::UnusedFootnote => "unused footnote",
+            WarningType::ContentClipped => "content clipped",
+            WarningType::MissingListMarker => "list marker missing from the font",
+            WarningType::SkippedTitleLevel => "skipped title level",
+            WarningType::SparsePage => "sparse page",
+            WarningType::UnbalancedRaw => "unbalanced raw content",
+            WarningType::ControlCharacter => "control character",
         }
     }
 
@@ -27,6 +79,19 @@ impl WarningType {
     pub fn detail(self) -> &'static str {
         match self {
             WarningType::ConsecutiveStars => "this will be ignored",
+            WarningType::TabInListIndent => "this tab counts as one level of nesting",
+            WarningType::TitleOverflow => "this title will wrap or overflow the margin",
+            WarningType::OverfullLine => "this line overflows the margin",
+            WarningType::UnderfullLine => "the spaces of this line are stretched a lot",
+            WarningType::EmergencyBreak => "this word will be broken between its characters",
+            WarningType::UndefinedFootnote => "this reference will be ignored",
+            WarningType::UnusedFootnote => "this footnote will not be printed",
+            WarningType::ContentClipped => "this content exceeds the maximum number of pages",
+            WarningType::MissingListMarker => "this marker will be replaced by a hyphen",
+            WarningType::SkippedTitleLevel => "the skipped levels of this title will be numbered 1",
+            WarningType::SparsePage => "this page holds only a few lines",
+            WarningType::UnbalancedRaw => "this raw content will be dropped",
+            WarningType::ControlCharacter => "this character will not be drawn",
         }
     }
 
@@ -36,6 +101,41 @@ impl WarningType {
             WarningType::ConsecutiveStars => {
                 Some("to use bold, you should use single stars, e.g. '*this is bold*'")
             }
+            WarningType::TabInListIndent => {
+                Some("to nest list items, you should indent them with two spaces per level")
+            }
+            WarningType::TitleOverflow => Some("you may want to shorten this title"),
+            WarningType::OverfullLine => {
+                Some("you may want to add a hyphenation exception or to rephrase this text")
+            }
+            WarningType::UnderfullLine => Some("you may want to rephrase this text"),
+            WarningType::EmergencyBreak => {
+                Some("you may want to allow breaks in this word with zero-width spaces")
+            }
+            WarningType::UndefinedFootnote => Some(
+                "to define a footnote, you should start a block with its label, e.g. '[^1]: ...'",
+            ),
+            WarningType::UnusedFootnote => {
+                Some("to reference a footnote, you should write its label in the text, e.g. '[^1]'")
+            }
+            WarningType::ContentClipped => {
+                Some("you may want to raise 'max_pages' in your configuration")
+            }
+            WarningType::MissingListMarker => {
+                Some("you may want to set 'list_marker' to a char of your font")
+            }
+            WarningType::SkippedTitleLevel => {
+                Some("you may want to add the missing titles or to raise the level of this one")
+            }
+            WarningType::SparsePage => {
+                Some("you may want to shorten the previous page or to remove a page break")
+            }
+            WarningType::UnbalancedRaw => {
+                Some("each 'q' should be closed by a 'Q', and each 'BT' by an 'ET'")
+            }
+            WarningType::ControlCharacter => {
+                Some("you may want to remove it, it often comes from pasted text")
+            }
         }
     }
 }
@@ -74,7 +174,7 @@ impl fmt::Display for Warnings {
 
             let line_number = format!("{} ", line);
             let space = replicate(' ', line_number.len() - 1);
-            let margin = replicate(' ', column);
+            let margin = caret_margin(&self.content, start, warning.position.offset);
             let hats = replicate('^', 1);
 
             writeln!(
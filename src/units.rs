@@ -0,0 +1,123 @@
+//! This module contains a length that can be computed from lengths in different units.
+//!
+//! Printpdf's `Pt` and `Mm` can only be added to lengths of the same unit, and since they are
+//! foreign types, the operators between them can't be implemented here. A `Length` converts
+//! every operand to pt instead.
+
+use std::ops::{Add, Mul, Sub};
+
+use printpdf::{Mm, Pt};
+use serde::{Deserialize, Serialize};
+
+/// A length, stored in pt, that can be combined with lengths in pt or in mm.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Length(pub f64);
+
+impl Length {
+    /// Returns the length in pt.
+    pub fn pt(self) -> Pt {
+        Pt(self.0)
+    }
+}
+
+impl From<Pt> for Length {
+    fn from(pt: Pt) -> Length {
+        Length(pt.0)
+    }
+}
+
+impl From<Mm> for Length {
+    fn from(mm: Mm) -> Length {
+        Length::from(Pt::from(mm))
+    }
+}
+
+impl From<Length> for Pt {
+    fn from(length: Length) -> Pt {
+        length.pt()
+    }
+}
+
+impl<L: Into<Length>> Add<L> for Length {
+    type Output = Length;
+
+    fn add(self, other: L) -> Length {
+        Length(self.0 + other.into().0)
+    }
+}
+
+impl<L: Into<Length>> Sub<L> for Length {
+    type Output = Length;
+
+    fn sub(self, other: L) -> Length {
+        Length(self.0 - other.into().0)
+    }
+}
+
+impl Mul<f64> for Length {
+    type Output = Length;
+
+    fn mul(self, factor: f64) -> Length {
+        Length(self.0 * factor)
+    }
+}
+
+/// The unit in which the lengths are reported to the user.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportUnit {
+    /// Points.
+    Pt,
+
+    /// Millimeters.
+    Mm,
+
+    /// Centimeters.
+    Cm,
+}
+
+impl Default for ReportUnit {
+    fn default() -> ReportUnit {
+        ReportUnit::Pt
+    }
+}
+
+impl ReportUnit {
+    /// Converts a length to the unit.
+    pub fn convert<L: Into<Length>>(self, length: L) -> f64 {
+        let pt = length.into().pt();
+        match self {
+            ReportUnit::Pt => pt.0,
+            ReportUnit::Mm => Mm::from(pt).0,
+            ReportUnit::Cm => Mm::from(pt).0 / 10.0,
+        }
+    }
+
+    /// Formats a length in the unit, e.g. `"1.25mm"`.
+    pub fn format<L: Into<Length>>(self, length: L) -> String {
+        let suffix = match self {
+            ReportUnit::Pt => "pt",
+            ReportUnit::Mm => "mm",
+            ReportUnit::Cm => "cm",
+        };
+        format!("{:.2}{}", self.convert(length), suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::{Mm, Pt};
+
+    use crate::units::Length;
+
+    #[test]
+    fn test_mixed_units() {
+        // An inch in pt plus an inch in mm.
+        let length = Length::from(Pt(72.0)) + Mm(25.4);
+        assert!((length.0 - 144.0).abs() < 1e-3);
+
+        let length = Length::from(Mm(210.0)) - Length::from(Mm(30.0)) * 2.0 - Pt(10.0);
+        let expected = Pt::from(Mm(150.0)).0 - 10.0;
+        assert!((Pt::from(length).0 - expected).abs() < 1e-9);
+    }
+}
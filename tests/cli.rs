@@ -0,0 +1,101 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use spandex::document::configuration::Config;
+
+/// Creates a project in a fresh temporary directory, with a warning in its content.
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("spandex-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let config = toml::to_string(&Config::with_title("Test")).unwrap();
+    File::create(dir.join("spandex.toml"))
+        .unwrap()
+        .write_all(config.as_bytes())
+        .unwrap();
+
+    File::create(dir.join("main.dex"))
+        .unwrap()
+        .write_all(b"# Hello\n\nHello ** world\n")
+        .unwrap();
+
+    dir
+}
+
+#[test]
+fn test_build_quiet() {
+    let dir = project("quiet");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_spandex"))
+        .args(["build", "--quiet"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+    assert!(dir.join("output.pdf").is_file());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_build_diagnostics_on_stderr() {
+    let dir = project("stderr");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_spandex"))
+        .arg("build")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_build_deny_warnings() {
+    let dir = project("deny");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_spandex"))
+        .args(["build", "--deny-warnings"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warnings are denied"));
+    assert!(!dir.join("output.pdf").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fmt() {
+    let dir = project("fmt");
+    fs::write(
+        dir.join("main.dex"),
+        "#Hello\n\n\n\nSome text\n\n\t- An item\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_spandex"))
+        .arg("fmt")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("main.dex")).unwrap(),
+        "# Hello\n\nSome text\n\n  - An item\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}